@@ -75,7 +75,7 @@ async fn create_solana_trade_client() -> AnyResult<SolanaTrade> {
         lookup_table_key: None,
     };
 
-    let solana_trade_client = SolanaTrade::new(Arc::new(payer), trade_config).await;
+    let solana_trade_client = SolanaTrade::new(Arc::new(payer), trade_config).await?;
     println!("SolanaTrade client created successfully!");
 
     Ok(solana_trade_client)
@@ -116,7 +116,7 @@ async fn create_solana_trade_client() -> AnyResult<SolanaTrade> {
         lookup_table_key: None,
     };
 
-    let solana_trade_client = SolanaTrade::new(Arc::new(payer), trade_config).await;
+    let solana_trade_client = SolanaTrade::new(Arc::new(payer), trade_config).await?;
     println!("SolanaTrade client created successfully!");
 
     Ok(solana_trade_client)
@@ -141,7 +141,7 @@ async fn raydium_amm_v4_copy_trade_with_grpc(trade_info: RaydiumAmmV4SwapEvent)
     } else {
         amm_info.pc_mint
     };
-    let params = RaydiumAmmV4Params::from_amm_info_and_reserves(
+    let params = RaydiumAmmV4Params::from_amm_info_and_vault_balances(
         trade_info.amm,
         amm_info,
         coin_reserve,
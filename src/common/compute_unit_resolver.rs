@@ -0,0 +1,434 @@
+use anyhow::{anyhow, Result};
+use solana_client::rpc_config::RpcSimulateTransactionConfig;
+use solana_sdk::{
+    compute_budget::ComputeBudgetInstruction, instruction::Instruction, message::Message,
+    pubkey::Pubkey, transaction::Transaction,
+};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+use crate::common::{PriorityFee, SolanaRpcClient};
+use crate::constants::{bonk, pumpfun, pumpswap, raydium_cpmm};
+
+/// 单笔交易允许的最大 compute unit
+const MAX_COMPUTE_UNIT_LIMIT: u32 = 1_400_000;
+/// SyncNative 指令的标识
+const SYNC_NATIVE_INSTRUCTION: u8 = 17;
+/// CloseAccount 指令的标识
+const CLOSE_ACCOUNT_INSTRUCTION: u8 = 9;
+/// 确认存在的 ATA 多久内视为仍然存在，ATA 可能在 SDK 之外被关闭
+const ATA_EXISTENCE_TTL: Duration = Duration::from_secs(30);
+
+/// 指令数据中标识指令类型的前缀长度，不含金额等参数
+///
+/// Anchor 程序为 8 字节，System Program 为 4 字节，SPL Token、ATA、Compute Budget、
+/// Raydium AMM v4 等其他程序按首字节区分
+fn discriminator_len(program_id: &Pubkey) -> usize {
+    if *program_id == pumpfun::accounts::PUMPFUN
+        || *program_id == pumpswap::accounts::AMM_PROGRAM
+        || *program_id == bonk::accounts::BONK
+        || *program_id == raydium_cpmm::accounts::RAYDIUM_CPMM
+    {
+        8
+    } else if *program_id == solana_sdk::system_program::ID {
+        4
+    } else {
+        1
+    }
+}
+
+/// compute unit 上限解析配置
+#[derive(Debug, Clone, PartialEq)]
+pub struct ComputeUnitResolverConfig {
+    /// 在模拟消耗的基础上增加的比例，百分比
+    pub safety_margin_percent: u32,
+    /// 额外增加的固定数量，覆盖计算预算指令和小费转账
+    pub extra_units: u32,
+    /// 模拟失败后等待多久再重试
+    pub retry_after: Duration,
+}
+
+impl Default for ComputeUnitResolverConfig {
+    fn default() -> Self {
+        Self { safety_margin_percent: 20, extra_units: 1_000, retry_after: Duration::from_secs(10) }
+    }
+}
+
+impl ComputeUnitResolverConfig {
+    /// 按模拟消耗的 compute unit 计算上限
+    pub fn unit_limit(&self, units_consumed: u64) -> u32 {
+        let units = units_consumed * (100 + self.safety_margin_percent as u64) / 100
+            + self.extra_units as u64;
+        units.min(MAX_COMPUTE_UNIT_LIMIT as u64) as u32
+    }
+}
+
+/// 决定 compute unit 消耗的交易形态
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ComputeUnitShape {
+    pub protocol_name: String,
+    pub is_buy: bool,
+    /// 每条指令的程序和指令标识，区分 DEX、买卖方向和兑换路径，不随交易金额变化
+    pub instructions: Vec<(Pubkey, Vec<u8>)>,
+    /// 交易中幂等创建的 ATA 是否都已存在
+    pub ata_exists: bool,
+    /// 是否包装 SOL 为 WSOL
+    pub wraps_wsol: bool,
+}
+
+/// 一种交易形态的解析状态
+enum ResolvedUnits {
+    Resolving,
+    Resolved(u32),
+    Failed(Instant),
+}
+
+/// ComputeUnitResolver 单例，按交易形态模拟一次并缓存 compute unit 上限
+///
+/// 构建交易时只读取缓存，未缓存的形态在后台模拟，期间使用 `PriorityFee` 中的固定上限。
+pub struct ComputeUnitResolver {
+    /// 未初始化时不解析
+    state: Mutex<Option<(Arc<SolanaRpcClient>, ComputeUnitResolverConfig)>>,
+    units: Mutex<HashMap<ComputeUnitShape, ResolvedUnits>>,
+    /// 已确认存在的 ATA 及确认时间
+    existing_atas: Mutex<HashMap<Pubkey, Instant>>,
+}
+
+static COMPUTE_UNIT_RESOLVER: OnceLock<Arc<ComputeUnitResolver>> = OnceLock::new();
+
+impl ComputeUnitResolver {
+    /// 获取 ComputeUnitResolver 单例实例
+    pub fn get_instance() -> Arc<ComputeUnitResolver> {
+        COMPUTE_UNIT_RESOLVER.get_or_init(|| Arc::new(ComputeUnitResolver::new())).clone()
+    }
+
+    fn new() -> Self {
+        ComputeUnitResolver {
+            state: Mutex::new(None),
+            units: Mutex::new(HashMap::new()),
+            existing_atas: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// 启用解析，清空之前的缓存
+    pub fn init(&self, rpc: Arc<SolanaRpcClient>, config: ComputeUnitResolverConfig) {
+        *self.state.lock().unwrap() = Some((rpc, config));
+        self.units.lock().unwrap().clear();
+    }
+
+    /// 停用解析，交易恢复使用固定上限
+    pub fn disable(&self) {
+        *self.state.lock().unwrap() = None;
+    }
+
+    /// 交易中幂等创建的 ATA
+    fn created_atas(instructions: &[Instruction]) -> Vec<Pubkey> {
+        instructions
+            .iter()
+            .filter(|instruction| instruction.program_id == spl_associated_token_account::ID)
+            .filter_map(|instruction| instruction.accounts.get(1).map(|meta| meta.pubkey))
+            .collect()
+    }
+
+    /// 交易中被 CloseAccount 关闭的账户
+    fn closed_accounts(instructions: &[Instruction]) -> Vec<Pubkey> {
+        instructions
+            .iter()
+            .filter(|instruction| {
+                (instruction.program_id == spl_token::ID
+                    || instruction.program_id == spl_token_2022::ID)
+                    && instruction.data.first() == Some(&CLOSE_ACCOUNT_INSTRUCTION)
+            })
+            .filter_map(|instruction| instruction.accounts.first().map(|meta| meta.pubkey))
+            .collect()
+    }
+
+    /// 交易会关闭的 ATA 不再视为存在，之后的交易需要重新创建
+    fn forget_closed_atas(&self, instructions: &[Instruction]) {
+        let closed = Self::closed_accounts(instructions);
+        if closed.is_empty() {
+            return;
+        }
+        let mut existing_atas = self.existing_atas.lock().unwrap();
+        for account in closed {
+            existing_atas.remove(&account);
+        }
+    }
+
+    /// 按已知存在的 ATA 计算交易形态
+    pub fn shape(
+        &self,
+        protocol_name: &str,
+        is_buy: bool,
+        instructions: &[Instruction],
+    ) -> ComputeUnitShape {
+        let ata_exists = {
+            let mut existing_atas = self.existing_atas.lock().unwrap();
+            existing_atas.retain(|_, confirmed_at| confirmed_at.elapsed() < ATA_EXISTENCE_TTL);
+            Self::created_atas(instructions).iter().all(|ata| existing_atas.contains_key(ata))
+        };
+        let wraps_wsol = instructions.iter().any(|instruction| {
+            instruction.program_id == spl_token::ID
+                && instruction.data.first() == Some(&SYNC_NATIVE_INSTRUCTION)
+        });
+        ComputeUnitShape {
+            protocol_name: protocol_name.to_string(),
+            is_buy,
+            instructions: instructions
+                .iter()
+                .map(|instruction| {
+                    let len = discriminator_len(&instruction.program_id);
+                    (instruction.program_id, instruction.data.iter().take(len).copied().collect())
+                })
+                .collect(),
+            ata_exists,
+            wraps_wsol,
+        }
+    }
+
+    /// 模拟交易并缓存其形态的 compute unit 上限
+    pub async fn resolve(
+        &self,
+        protocol_name: &str,
+        is_buy: bool,
+        instructions: Vec<Instruction>,
+    ) -> Result<u32> {
+        let Some((rpc, config)) = self.state.lock().unwrap().clone() else {
+            return Err(anyhow!("Compute unit resolver is not initialized"));
+        };
+        let payer = instructions
+            .iter()
+            .flat_map(|instruction| instruction.accounts.iter())
+            .find(|meta| meta.is_signer)
+            .map(|meta| meta.pubkey)
+            .ok_or_else(|| anyhow!("Instructions have no signer to pay for the simulation"))?;
+
+        // 先确认 ATA 是否存在，结果按真实形态缓存
+        let atas = Self::created_atas(&instructions);
+        if !atas.is_empty() {
+            let accounts = rpc.get_multiple_accounts(&atas).await?;
+            let mut existing_atas = self.existing_atas.lock().unwrap();
+            let now = Instant::now();
+            for (ata, account) in atas.into_iter().zip(accounts) {
+                match account {
+                    Some(_) => existing_atas.insert(ata, now),
+                    None => existing_atas.remove(&ata),
+                };
+            }
+        }
+        let shape = self.shape(protocol_name, is_buy, &instructions);
+
+        let mut simulated =
+            vec![ComputeBudgetInstruction::set_compute_unit_limit(MAX_COMPUTE_UNIT_LIMIT)];
+        simulated.extend(instructions);
+        let transaction = Transaction::new_unsigned(Message::new(&simulated, Some(&payer)));
+        let simulation = rpc
+            .simulate_transaction_with_config(
+                &transaction,
+                RpcSimulateTransactionConfig {
+                    sig_verify: false,
+                    replace_recent_blockhash: true,
+                    ..Default::default()
+                },
+            )
+            .await
+            .map_err(anyhow::Error::from)
+            .and_then(|response| {
+                if let Some(err) = response.value.err {
+                    return Err(anyhow!("Simulation failed: {}", err));
+                }
+                response.value.units_consumed.ok_or_else(|| anyhow!("Simulation has no units"))
+            });
+
+        let mut units = self.units.lock().unwrap();
+        match simulation {
+            Ok(units_consumed) => {
+                let unit_limit = config.unit_limit(units_consumed);
+                units.insert(shape, ResolvedUnits::Resolved(unit_limit));
+                Ok(unit_limit)
+            }
+            Err(e) => {
+                units.insert(shape, ResolvedUnits::Failed(Instant::now()));
+                Err(e)
+            }
+        }
+    }
+
+    /// 结束 `unit_limit` 标记为 Resolving 的形态
+    ///
+    /// ATA 查询后形态可能改变，结果已按真实形态缓存，此时移除标记，下次遇到该形态重新模拟；
+    /// 模拟前就失败时标记为失败，等待重试
+    fn finish_resolving(&self, marked: ComputeUnitShape, result: &Result<u32>) {
+        let mut units = self.units.lock().unwrap();
+        if !matches!(units.get(&marked), Some(ResolvedUnits::Resolving)) {
+            return;
+        }
+        match result {
+            Ok(_) => {
+                units.remove(&marked);
+            }
+            Err(_) => {
+                units.insert(marked, ResolvedUnits::Failed(Instant::now()));
+            }
+        }
+    }
+
+    /// 缓存中交易形态的 compute unit 上限
+    ///
+    /// 未缓存时启动后台模拟并返回 None
+    pub fn unit_limit(
+        self: &Arc<Self>,
+        protocol_name: &str,
+        is_buy: bool,
+        instructions: &[Instruction],
+    ) -> Option<u32> {
+        let retry_after = self.state.lock().unwrap().as_ref()?.1.retry_after;
+        self.forget_closed_atas(instructions);
+        let shape = self.shape(protocol_name, is_buy, instructions);
+        let mut units = self.units.lock().unwrap();
+        match units.get(&shape) {
+            Some(ResolvedUnits::Resolved(unit_limit)) => return Some(*unit_limit),
+            Some(ResolvedUnits::Resolving) => return None,
+            Some(ResolvedUnits::Failed(failed_at)) if failed_at.elapsed() < retry_after => {
+                return None
+            }
+            _ => {}
+        }
+        let Ok(handle) = tokio::runtime::Handle::try_current() else {
+            return None;
+        };
+        units.insert(shape.clone(), ResolvedUnits::Resolving);
+        let resolver = self.clone();
+        let protocol_name = protocol_name.to_string();
+        let instructions = instructions.to_vec();
+        handle.spawn(async move {
+            let result = resolver.resolve(&protocol_name, is_buy, instructions).await;
+            if let Err(e) = &result {
+                println!("Failed to resolve compute unit limit for {}: {}", protocol_name, e);
+            }
+            resolver.finish_resolving(shape, &result);
+        });
+        None
+    }
+
+    /// 用解析出的上限替换 `priority_fee` 中的 `rpc_unit_limit` 和 `tip_unit_limit`
+    pub fn apply(
+        self: &Arc<Self>,
+        priority_fee: &mut PriorityFee,
+        protocol_name: &str,
+        is_buy: bool,
+        instructions: &[Instruction],
+    ) {
+        if let Some(unit_limit) = self.unit_limit(protocol_name, is_buy, instructions) {
+            priority_fee.rpc_unit_limit = unit_limit;
+            priority_fee.tip_unit_limit = unit_limit;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use spl_associated_token_account::instruction::create_associated_token_account_idempotent;
+
+    #[test]
+    fn test_unit_limit_and_shape() {
+        let config = ComputeUnitResolverConfig::default();
+        assert_eq!(config.unit_limit(50_000), 61_000);
+        assert_eq!(config.unit_limit(1_300_000), MAX_COMPUTE_UNIT_LIMIT);
+
+        let resolver = ComputeUnitResolver::get_instance();
+        let (payer, mint) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let create_ata =
+            create_associated_token_account_idempotent(&payer, &payer, &mint, &spl_token::ID);
+        let ata = create_ata.accounts[1].pubkey;
+        let instructions = vec![create_ata];
+
+        let shape = resolver.shape("PumpFun", true, &instructions);
+        assert!(!shape.ata_exists);
+        assert!(!shape.wraps_wsol);
+        resolver.existing_atas.lock().unwrap().insert(ata, Instant::now());
+        assert!(resolver.shape("PumpFun", true, &instructions).ata_exists);
+        assert_ne!(resolver.shape("PumpFun", false, &instructions), shape);
+    }
+
+    #[test]
+    fn test_closed_or_expired_atas_are_recreated() {
+        let resolver = ComputeUnitResolver::new();
+        let (payer, mint) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let create_ata =
+            create_associated_token_account_idempotent(&payer, &payer, &mint, &spl_token::ID);
+        let ata = create_ata.accounts[1].pubkey;
+        let buy = vec![create_ata];
+        resolver.existing_atas.lock().unwrap().insert(ata, Instant::now());
+        assert!(resolver.shape("PumpFun", true, &buy).ata_exists);
+
+        // A sell closing the ATA makes the next buy create it again
+        let close =
+            spl_token::instruction::close_account(&spl_token::ID, &ata, &payer, &payer, &[])
+                .unwrap();
+        resolver.forget_closed_atas(&[close]);
+        assert!(!resolver.shape("PumpFun", true, &buy).ata_exists);
+
+        // Existence confirmed too long ago is checked again
+        resolver.existing_atas.lock().unwrap().insert(ata, Instant::now() - ATA_EXISTENCE_TTL);
+        assert!(!resolver.shape("PumpFun", true, &buy).ata_exists);
+    }
+
+    #[test]
+    fn test_shape_ignores_amounts() {
+        let resolver = ComputeUnitResolver::new();
+        let (payer, wsol_account) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let swap = |amount: u64| {
+            let mut data = vec![9];
+            data.extend_from_slice(&amount.to_le_bytes());
+            vec![
+                solana_system_interface::instruction::transfer(&payer, &wsol_account, amount),
+                Instruction::new_with_bytes(
+                    crate::constants::raydium_amm_v4::accounts::RAYDIUM_AMM_V4,
+                    &data,
+                    vec![],
+                ),
+            ]
+        };
+        assert_eq!(
+            resolver.shape("RaydiumAmmV4", true, &swap(1_000)),
+            resolver.shape("RaydiumAmmV4", true, &swap(2_000_000))
+        );
+    }
+
+    #[test]
+    fn test_finish_resolving_clears_stale_shape() {
+        let resolver = ComputeUnitResolver::new();
+        let (payer, mint) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let instructions =
+            vec![create_associated_token_account_idempotent(&payer, &payer, &mint, &spl_token::ID)];
+        let marked = resolver.shape("PumpFun", true, &instructions);
+
+        // The ATA turned out to exist, so the result was cached under another shape
+        resolver
+            .existing_atas
+            .lock()
+            .unwrap()
+            .insert(instructions[0].accounts[1].pubkey, Instant::now());
+        let resolved = resolver.shape("PumpFun", true, &instructions);
+        assert_ne!(resolved, marked);
+        resolver.units.lock().unwrap().insert(marked.clone(), ResolvedUnits::Resolving);
+        resolver.units.lock().unwrap().insert(resolved.clone(), ResolvedUnits::Resolved(80_000));
+        resolver.finish_resolving(marked.clone(), &Ok(80_000));
+        assert!(!resolver.units.lock().unwrap().contains_key(&marked));
+        assert!(matches!(
+            resolver.units.lock().unwrap().get(&resolved),
+            Some(ResolvedUnits::Resolved(80_000))
+        ));
+
+        // Failing before the simulation makes the shape retryable instead of stuck
+        resolver.units.lock().unwrap().insert(marked.clone(), ResolvedUnits::Resolving);
+        resolver.finish_resolving(marked.clone(), &Err(anyhow!("RPC error")));
+        assert!(matches!(
+            resolver.units.lock().unwrap().get(&marked),
+            Some(ResolvedUnits::Failed(_))
+        ));
+    }
+}
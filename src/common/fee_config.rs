@@ -0,0 +1,103 @@
+//! Fee config account of the Pump fee program
+//!
+//! Pump.fun and PumpSwap each have a fee config account owned by the Pump fee program. It holds
+//! flat fees plus an optional list of market cap tiers that override them.
+//!
+//! # Fields
+//!
+//! - `bump`: PDA bump
+//! - `admin`: Authority that can update the fees
+//! - `flat_fees`: Fees used when no tier applies
+//! - `fee_tiers`: Fee tiers sorted by ascending market cap threshold
+
+use borsh::BorshDeserialize;
+use serde::{Deserialize, Serialize};
+use solana_sdk::pubkey::Pubkey;
+
+/// Anchor discriminator of the `FeeConfig` account
+pub const FEE_CONFIG_DISCRIMINATOR: [u8; 8] = [143, 52, 146, 187, 219, 123, 76, 155];
+
+/// Fee basis points charged on a trade
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, BorshDeserialize)]
+pub struct Fees {
+    /// LP fee in basis points, only charged by PumpSwap
+    pub lp_fee_bps: u64,
+    /// Protocol fee in basis points
+    pub protocol_fee_bps: u64,
+    /// Coin creator fee in basis points
+    pub creator_fee_bps: u64,
+}
+
+/// Fees applied from a market cap threshold upwards
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, BorshDeserialize)]
+pub struct FeeTier {
+    /// Market cap in lamports from which this tier applies
+    pub market_cap_lamports_threshold: u128,
+    /// Fees of the tier
+    pub fees: Fees,
+}
+
+/// Fee config account
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, BorshDeserialize)]
+pub struct FeeConfig {
+    /// PDA bump
+    pub bump: u8,
+    /// Authority that can update the fees
+    pub admin: Pubkey,
+    /// Fees used when no tier applies
+    pub flat_fees: Fees,
+    /// Fee tiers sorted by ascending market cap threshold
+    pub fee_tiers: Vec<FeeTier>,
+}
+
+impl FeeConfig {
+    /// Decodes a fee config account, `data` includes the 8 byte discriminator
+    pub fn decode(data: &[u8]) -> Option<Self> {
+        if data.len() < 8 || data[..8] != FEE_CONFIG_DISCRIMINATOR {
+            return None;
+        }
+        let mut slice = &data[8..];
+        <FeeConfig as BorshDeserialize>::deserialize(&mut slice).ok()
+    }
+
+    /// Selects the fees for a market cap in lamports
+    ///
+    /// Below the first threshold the first tier applies; otherwise the highest tier whose
+    /// threshold is reached. Without tiers the flat fees apply.
+    pub fn get_fees(&self, market_cap_lamports: u128) -> Fees {
+        let Some(first_tier) = self.fee_tiers.first() else {
+            return self.flat_fees;
+        };
+        self.fee_tiers
+            .iter()
+            .rev()
+            .find(|tier| market_cap_lamports >= tier.market_cap_lamports_threshold)
+            .unwrap_or(first_tier)
+            .fees
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fees(protocol_fee_bps: u64) -> Fees {
+        Fees { lp_fee_bps: 0, protocol_fee_bps, creator_fee_bps: 0 }
+    }
+
+    #[test]
+    fn test_get_fees_by_market_cap() {
+        let mut fee_config = FeeConfig { flat_fees: fees(95), ..Default::default() };
+        assert_eq!(fee_config.get_fees(1_000).protocol_fee_bps, 95);
+
+        fee_config.fee_tiers = vec![
+            FeeTier { market_cap_lamports_threshold: 100, fees: fees(90) },
+            FeeTier { market_cap_lamports_threshold: 1_000, fees: fees(50) },
+            FeeTier { market_cap_lamports_threshold: 10_000, fees: fees(20) },
+        ];
+        assert_eq!(fee_config.get_fees(10).protocol_fee_bps, 90);
+        assert_eq!(fee_config.get_fees(100).protocol_fee_bps, 90);
+        assert_eq!(fee_config.get_fees(9_999).protocol_fee_bps, 50);
+        assert_eq!(fee_config.get_fees(50_000).protocol_fee_bps, 20);
+    }
+}
@@ -25,6 +25,7 @@
 
 use solana_sdk::pubkey::Pubkey;
 use serde::{Serialize, Deserialize};
+use solana_streamer_sdk::streaming::event_parser::protocols::pumpfun::types::Global;
 
 use crate::constants::pumpfun::global_constants::*;
 
@@ -93,6 +94,31 @@ impl GlobalAccount {
         }
     }
 
+    /// Creates a global account from the on-chain `Global` account
+    ///
+    /// # Arguments
+    /// * `account` - Address of the global account
+    /// * `global` - Decoded global account data
+    pub fn from_global(account: Pubkey, global: &Global) -> Self {
+        Self {
+            discriminator: 0,
+            account,
+            initialized: global.initialized,
+            authority: global.authority,
+            fee_recipient: global.fee_recipient,
+            initial_virtual_token_reserves: global.initial_virtual_token_reserves,
+            initial_virtual_sol_reserves: global.initial_virtual_sol_reserves,
+            initial_real_token_reserves: global.initial_real_token_reserves,
+            token_total_supply: global.token_total_supply,
+            fee_basis_points: global.fee_basis_points,
+            withdraw_authority: global.withdraw_authority,
+            enable_migrate: global.enable_migrate,
+            pool_migration_fee: global.pool_migration_fee,
+            creator_fee: global.creator_fee_basis_points,
+            fee_recipients: global.fee_recipients,
+        }
+    }
+
     /// Calculates the initial amount of tokens received for a given SOL amount
     ///
     /// # Arguments
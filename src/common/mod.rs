@@ -1,10 +1,15 @@
 pub mod address_lookup;
 pub mod nonce_cache;
 pub mod tip_cache;
+pub mod tip_strategy;
+pub mod priority_fee_estimator;
+pub mod compute_unit_resolver;
 pub mod types;
 pub mod address_lookup_cache;
 pub mod subscription_handle;
 pub mod bonding_curve;
 pub mod global;
+pub mod fee_config;
+pub mod volume_accumulator;
 
 pub use types::*;
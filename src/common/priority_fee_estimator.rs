@@ -0,0 +1,272 @@
+use anyhow::Result;
+use solana_sdk::{instruction::Instruction, pubkey::Pubkey};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::common::{PriorityFee, SolanaRpcClient};
+
+/// getRecentPrioritizationFees 最多接受的账户数量
+const MAX_PRIORITIZATION_FEE_ACCOUNTS: usize = 128;
+
+/// 账户组合多久没有更新后从缓存中移除，账户中包含用户 ATA，组合数量会不断增长
+const STALE_PRICE_TTL: Duration = Duration::from_secs(60);
+
+/// 最多缓存的账户组合数
+const MAX_CACHED_PRICES: usize = 4096;
+
+/// 优先费估算配置
+#[derive(Debug, Clone, PartialEq)]
+pub struct PriorityFeeEstimatorConfig {
+    /// 取最近各 slot 优先费的分位点，0-100
+    pub percentile: u8,
+    /// compute unit 价格下限（micro-lamports）
+    pub min_unit_price: u64,
+    /// compute unit 价格上限（micro-lamports）
+    pub max_unit_price: u64,
+    /// 缓存有效期，过期后在后台刷新
+    pub cache_ttl: Duration,
+}
+
+impl Default for PriorityFeeEstimatorConfig {
+    fn default() -> Self {
+        Self {
+            percentile: 75,
+            min_unit_price: 1_000,
+            max_unit_price: 5_000_000,
+            cache_ttl: Duration::from_secs(2),
+        }
+    }
+}
+
+impl PriorityFeeEstimatorConfig {
+    /// 按配置的分位点从最近各 slot 的优先费中取值，并限制在上下限之间
+    pub fn unit_price(&self, mut fees: Vec<u64>) -> u64 {
+        if fees.is_empty() {
+            return self.min_unit_price;
+        }
+        fees.sort_unstable();
+        let index = (fees.len() - 1) * self.percentile.min(100) as usize / 100;
+        fees[index].clamp(self.min_unit_price, self.max_unit_price)
+    }
+}
+
+/// 一组账户的估算价格
+#[derive(Default)]
+struct CachedUnitPrice {
+    unit_price: Option<u64>,
+    updated_at: Option<Instant>,
+    refreshing: bool,
+}
+
+impl CachedUnitPrice {
+    fn is_stale(&self, now: Instant) -> bool {
+        !self.refreshing
+            && self.updated_at.is_none_or(|updated_at| {
+                now.saturating_duration_since(updated_at) > STALE_PRICE_TTL
+            })
+    }
+}
+
+/// 按交易写入的账户估算 compute unit 价格，每个 `SolanaTrade` 各自持有，通过 `PriorityFee` 传递
+///
+/// 构建交易时只读取缓存，缓存缺失或过期时在后台刷新，不阻塞交易发送；
+/// 首次遇到某组账户时使用 `PriorityFee` 中的固定价格。
+pub struct PriorityFeeEstimator {
+    rpc: Arc<SolanaRpcClient>,
+    config: PriorityFeeEstimatorConfig,
+    /// 键为排序去重后的可写账户
+    prices: Mutex<HashMap<Vec<Pubkey>, CachedUnitPrice>>,
+}
+
+impl std::fmt::Debug for PriorityFeeEstimator {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PriorityFeeEstimator").field("config", &self.config).finish()
+    }
+}
+
+/// 同一个估算器才相等
+impl PartialEq for PriorityFeeEstimator {
+    fn eq(&self, other: &Self) -> bool {
+        std::ptr::eq(self, other)
+    }
+}
+
+impl PriorityFeeEstimator {
+    pub fn new(rpc: Arc<SolanaRpcClient>, config: PriorityFeeEstimatorConfig) -> Self {
+        Self { rpc, config, prices: Mutex::new(HashMap::new()) }
+    }
+
+    pub fn config(&self) -> &PriorityFeeEstimatorConfig {
+        &self.config
+    }
+
+    /// 缓存的账户组合数
+    pub fn len(&self) -> usize {
+        self.prices.lock().unwrap().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// 指令中可写且无需签名的账户，排序去重，即交易竞争写锁的账户
+    pub fn writable_accounts(instructions: &[Instruction]) -> Vec<Pubkey> {
+        let mut accounts: Vec<Pubkey> = instructions
+            .iter()
+            .flat_map(|instruction| instruction.accounts.iter())
+            .filter(|meta| meta.is_writable && !meta.is_signer)
+            .map(|meta| meta.pubkey)
+            .collect();
+        accounts.sort_unstable();
+        accounts.dedup();
+        accounts.truncate(MAX_PRIORITIZATION_FEE_ACCOUNTS);
+        accounts
+    }
+
+    /// 查询 `accounts` 最近的优先费并更新缓存
+    pub async fn refresh(&self, accounts: Vec<Pubkey>) -> Result<u64> {
+        let result = self.rpc.get_recent_prioritization_fees(&accounts).await;
+        let mut prices = self.prices.lock().unwrap();
+        let cached = prices.entry(accounts).or_default();
+        cached.refreshing = false;
+        let fees = result?;
+        let unit_price =
+            self.config.unit_price(fees.into_iter().map(|fee| fee.prioritization_fee).collect());
+        cached.unit_price = Some(unit_price);
+        cached.updated_at = Some(Instant::now());
+        Ok(unit_price)
+    }
+
+    /// 缓存中 `instructions` 的 compute unit 价格
+    ///
+    /// 缓存缺失或过期时启动后台刷新，过期时仍返回旧价格，缺失时返回 None
+    pub fn unit_price(self: &Arc<Self>, instructions: &[Instruction]) -> Option<u64> {
+        let cache_ttl = self.config.cache_ttl;
+        let accounts = Self::writable_accounts(instructions);
+        let mut prices = self.prices.lock().unwrap();
+        if !prices.contains_key(&accounts) {
+            prune(&mut prices, Instant::now());
+        }
+        let cached = prices.entry(accounts.clone()).or_default();
+        let fresh = cached.updated_at.is_some_and(|updated_at| updated_at.elapsed() <= cache_ttl);
+        if !fresh && !cached.refreshing {
+            if let Ok(handle) = tokio::runtime::Handle::try_current() {
+                cached.refreshing = true;
+                let estimator = self.clone();
+                handle.spawn(async move {
+                    if let Err(e) = estimator.refresh(accounts).await {
+                        log::warn!("Failed to refresh priority fee: {}", e);
+                    }
+                });
+            }
+        }
+        cached.unit_price
+    }
+
+    /// 用估算价格替换 `priority_fee` 中的 `rpc_unit_price` 和 `tip_unit_price`
+    pub fn apply(
+        self: &Arc<Self>,
+        priority_fee: &PriorityFee,
+        instructions: &[Instruction],
+    ) -> PriorityFee {
+        let mut priority_fee = priority_fee.clone();
+        if let Some(unit_price) = self.unit_price(instructions) {
+            priority_fee.rpc_unit_price = unit_price;
+            priority_fee.tip_unit_price = unit_price;
+        }
+        priority_fee
+    }
+}
+
+/// 移除长时间没有更新的账户组合，仍然超出上限时移除最早更新的
+fn prune(prices: &mut HashMap<Vec<Pubkey>, CachedUnitPrice>, now: Instant) {
+    if prices.len() < MAX_CACHED_PRICES {
+        return;
+    }
+    prices.retain(|_, cached| !cached.is_stale(now));
+    while prices.len() >= MAX_CACHED_PRICES {
+        let Some(oldest) = prices
+            .iter()
+            .min_by_key(|(_, cached)| cached.updated_at)
+            .map(|(accounts, _)| accounts.clone())
+        else {
+            break;
+        };
+        prices.remove(&oldest);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_sdk::instruction::AccountMeta;
+
+    #[test]
+    fn test_unit_price_and_writable_accounts() {
+        let config = PriorityFeeEstimatorConfig {
+            percentile: 75,
+            min_unit_price: 1_000,
+            max_unit_price: 100_000,
+            ..Default::default()
+        };
+        assert_eq!(config.unit_price(vec![0, 0, 5_000, 20_000, 50_000]), 20_000);
+        assert_eq!(config.unit_price(vec![0, 0, 0]), 1_000);
+        assert_eq!(config.unit_price(vec![1_000_000]), 100_000);
+        assert_eq!(config.unit_price(vec![]), 1_000);
+
+        let (payer, curve, vault, program) = (
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+        );
+        let instructions = vec![
+            Instruction::new_with_bytes(
+                program,
+                &[],
+                vec![
+                    AccountMeta::new(payer, true),
+                    AccountMeta::new(vault, false),
+                    AccountMeta::new(curve, false),
+                ],
+            ),
+            Instruction::new_with_bytes(
+                program,
+                &[],
+                vec![AccountMeta::new(curve, false), AccountMeta::new_readonly(program, false)],
+            ),
+        ];
+        let mut expected = vec![curve, vault];
+        expected.sort();
+        assert_eq!(PriorityFeeEstimator::writable_accounts(&instructions), expected);
+    }
+
+    #[test]
+    fn test_prune_caps_cached_prices() {
+        let now = Instant::now();
+        let mut prices = HashMap::new();
+        for i in 0..MAX_CACHED_PRICES {
+            let updated_at = if i % 2 == 0 { now - STALE_PRICE_TTL * 2 } else { now };
+            prices.insert(
+                vec![Pubkey::new_unique()],
+                CachedUnitPrice {
+                    unit_price: Some(1),
+                    updated_at: Some(updated_at),
+                    refreshing: false,
+                },
+            );
+        }
+        prune(&mut prices, now);
+        assert_eq!(prices.len(), MAX_CACHED_PRICES / 2);
+
+        for _ in prices.len()..MAX_CACHED_PRICES {
+            prices.insert(
+                vec![Pubkey::new_unique()],
+                CachedUnitPrice { unit_price: Some(1), updated_at: Some(now), refreshing: false },
+            );
+        }
+        prune(&mut prices, now);
+        assert_eq!(prices.len(), MAX_CACHED_PRICES - 1);
+    }
+}
@@ -1,9 +1,56 @@
+use serde::Deserialize;
 use std::sync::{Arc, Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+/// 最近落地交易的 tip 分布（单位 SOL），字段与 Jito tip floor 接口一致
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize)]
+pub struct TipFloor {
+    #[serde(rename = "landed_tips_25th_percentile")]
+    pub p25: f64,
+    #[serde(rename = "landed_tips_50th_percentile")]
+    pub p50: f64,
+    #[serde(rename = "landed_tips_75th_percentile")]
+    pub p75: f64,
+    #[serde(rename = "landed_tips_95th_percentile")]
+    pub p95: f64,
+    #[serde(rename = "landed_tips_99th_percentile")]
+    pub p99: f64,
+    /// 中位数的指数移动平均，比 p50 更平滑
+    #[serde(rename = "ema_landed_tips_50th_percentile")]
+    pub ema_p50: f64,
+}
+
+/// tip 分布中的分位点
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TipPercentile {
+    P25,
+    P50,
+    P75,
+    P95,
+    P99,
+    EmaP50,
+}
+
+impl TipFloor {
+    /// 指定分位点的 tip
+    pub fn tip_at(&self, percentile: TipPercentile) -> f64 {
+        match percentile {
+            TipPercentile::P25 => self.p25,
+            TipPercentile::P50 => self.p50,
+            TipPercentile::P75 => self.p75,
+            TipPercentile::P95 => self.p95,
+            TipPercentile::P99 => self.p99,
+            TipPercentile::EmaP50 => self.ema_p50,
+        }
+    }
+}
 
 /// TipCache 单例，用于存储和管理 tip 金额
 pub struct TipCache {
     /// tip 金额
     tip_amount: Mutex<f64>,
+    /// 最新的 tip 分布及更新时间
+    tip_floor: Mutex<Option<(TipFloor, Instant)>>,
 }
 
 static TIP_CACHE: OnceLock<Arc<TipCache>> = OnceLock::new();
@@ -13,9 +60,7 @@ impl TipCache {
     pub fn get_instance() -> Arc<TipCache> {
         TIP_CACHE
             .get_or_init(|| {
-                Arc::new(TipCache {
-                    tip_amount: Mutex::new(0.001),
-                })
+                Arc::new(TipCache { tip_amount: Mutex::new(0.001), tip_floor: Mutex::new(None) })
             })
             .clone()
     }
@@ -35,4 +80,18 @@ impl TipCache {
     pub fn update_tip(&self, amount: f64) {
         *self.tip_amount.lock().unwrap() = amount;
     }
-}
\ No newline at end of file
+
+    /// 更新 tip 分布
+    pub fn update_tip_floor(&self, tip_floor: TipFloor) {
+        *self.tip_floor.lock().unwrap() = Some((tip_floor, Instant::now()));
+    }
+
+    /// 获取 `max_age` 内更新过的 tip 分布，过期或从未更新时返回 None
+    pub fn get_tip_floor(&self, max_age: Duration) -> Option<TipFloor> {
+        self.tip_floor
+            .lock()
+            .unwrap()
+            .filter(|(_, updated_at)| updated_at.elapsed() <= max_age)
+            .map(|(tip_floor, _)| tip_floor)
+    }
+}
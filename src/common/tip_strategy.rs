@@ -0,0 +1,270 @@
+//! Tip 策略
+//!
+//! 根据最近落地交易的 tip 分布自动选择 tip，代替手动调整的固定值：
+//!
+//! - `TipFloorSource`: tip 分布来源，`JitoTipFloorSource` 读取 Jito tip floor 接口，
+//!   `StaticTipFloorSource` 返回固定分布，用于测试和离线环境
+//! - `spawn_tip_floor_updater`: 后台定时拉取分布并写入 `TipCache`
+//! - `TipStrategy`: 按交易类型选择分位点，限制在最小/最大值之间，并在发送时为每个
+//!   SWQOS 服务填充 tip
+
+use anyhow::{anyhow, Result};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::task::JoinHandle;
+
+use crate::{
+    common::{
+        tip_cache::{TipCache, TipFloor, TipPercentile},
+        PriorityFee,
+    },
+    constants::swqos::JITO_TIP_FLOOR_URL,
+    swqos::{SwqosType, TradeType},
+};
+
+/// tip 分布来源
+#[async_trait::async_trait]
+pub trait TipFloorSource: Send + Sync {
+    async fn fetch(&self) -> Result<TipFloor>;
+}
+
+/// 从 Jito tip floor 接口读取 tip 分布
+pub struct JitoTipFloorSource {
+    client: reqwest::Client,
+    url: String,
+}
+
+impl JitoTipFloorSource {
+    pub fn new(url: String) -> Self {
+        Self { client: reqwest::Client::new(), url }
+    }
+}
+
+impl Default for JitoTipFloorSource {
+    fn default() -> Self {
+        Self::new(JITO_TIP_FLOOR_URL.to_string())
+    }
+}
+
+#[async_trait::async_trait]
+impl TipFloorSource for JitoTipFloorSource {
+    async fn fetch(&self) -> Result<TipFloor> {
+        let tip_floors: Vec<TipFloor> =
+            self.client.get(&self.url).send().await?.error_for_status()?.json().await?;
+        tip_floors.into_iter().next().ok_or_else(|| anyhow!("Tip floor response is empty"))
+    }
+}
+
+/// 返回固定 tip 分布的本地来源，可随时替换分布
+pub struct StaticTipFloorSource {
+    tip_floor: Mutex<TipFloor>,
+}
+
+impl StaticTipFloorSource {
+    pub fn new(tip_floor: TipFloor) -> Self {
+        Self { tip_floor: Mutex::new(tip_floor) }
+    }
+
+    pub fn set(&self, tip_floor: TipFloor) {
+        *self.tip_floor.lock().unwrap() = tip_floor;
+    }
+}
+
+#[async_trait::async_trait]
+impl TipFloorSource for StaticTipFloorSource {
+    async fn fetch(&self) -> Result<TipFloor> {
+        Ok(*self.tip_floor.lock().unwrap())
+    }
+}
+
+/// 每隔 `interval` 拉取一次 tip 分布并写入 `TipCache`，拉取失败时保留上一次的分布
+pub fn spawn_tip_floor_updater(
+    source: Arc<dyn TipFloorSource>,
+    interval: Duration,
+) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+        loop {
+            ticker.tick().await;
+            match source.fetch().await {
+                Ok(tip_floor) => TipCache::get_instance().update_tip_floor(tip_floor),
+                Err(e) => println!("Failed to fetch tip floor: {}", e),
+            }
+        }
+    })
+}
+
+/// 根据 tip 分布为每笔交易选择 tip（单位 SOL）
+#[derive(Debug, Clone)]
+pub struct TipStrategy {
+    /// 创建代币时使用的分位点
+    pub create_percentile: TipPercentile,
+    /// 买入（包括创建并买入）时使用的分位点
+    pub buy_percentile: TipPercentile,
+    /// 卖出时使用的分位点
+    pub sell_percentile: TipPercentile,
+    /// tip 下限
+    pub min_tip: f64,
+    /// tip 上限
+    pub max_tip: f64,
+    /// 各 SWQOS 服务接受的最低 tip
+    pub provider_min_tips: Vec<(SwqosType, f64)>,
+    /// 分布超过该时间未更新则视为过期，回退到 `PriorityFee` 中配置的固定 tip
+    pub max_age: Duration,
+}
+
+impl Default for TipStrategy {
+    fn default() -> Self {
+        Self {
+            create_percentile: TipPercentile::P75,
+            buy_percentile: TipPercentile::P75,
+            sell_percentile: TipPercentile::P50,
+            min_tip: 0.00001,
+            max_tip: 0.01,
+            provider_min_tips: vec![],
+            max_age: Duration::from_secs(60),
+        }
+    }
+}
+
+impl TipStrategy {
+    pub fn new(min_tip: f64, max_tip: f64) -> Self {
+        Self { min_tip, max_tip, ..Default::default() }
+    }
+
+    pub fn with_percentile(mut self, trade_type: TradeType, percentile: TipPercentile) -> Self {
+        match trade_type {
+            TradeType::Create => self.create_percentile = percentile,
+            TradeType::CreateAndBuy | TradeType::Buy => self.buy_percentile = percentile,
+            TradeType::Sell => self.sell_percentile = percentile,
+        }
+        self
+    }
+
+    pub fn with_provider_min_tip(mut self, swqos_type: SwqosType, min_tip: f64) -> Self {
+        self.provider_min_tips.retain(|(provider, _)| *provider != swqos_type);
+        self.provider_min_tips.push((swqos_type, min_tip));
+        self
+    }
+
+    pub fn with_max_age(mut self, max_age: Duration) -> Self {
+        self.max_age = max_age;
+        self
+    }
+
+    /// 交易类型对应的分位点
+    pub fn percentile(&self, trade_type: TradeType) -> TipPercentile {
+        match trade_type {
+            TradeType::Create => self.create_percentile,
+            TradeType::CreateAndBuy | TradeType::Buy => self.buy_percentile,
+            TradeType::Sell => self.sell_percentile,
+        }
+    }
+
+    /// 从分布中选出的 tip，限制在 `min_tip` 与 `max_tip` 之间
+    pub fn tip(&self, tip_floor: &TipFloor, trade_type: TradeType) -> f64 {
+        tip_floor.tip_at(self.percentile(trade_type)).max(self.min_tip).min(self.max_tip)
+    }
+
+    /// 某个 SWQOS 服务的 tip，不低于该服务的最低 tip，但不超过 `max_tip`
+    pub fn provider_tip(&self, tip: f64, swqos_type: &SwqosType) -> f64 {
+        let provider_min = self
+            .provider_min_tips
+            .iter()
+            .find(|(provider, _)| provider == swqos_type)
+            .map(|(_, min_tip)| *min_tip)
+            .unwrap_or(0.0);
+        tip.max(provider_min).min(self.max_tip)
+    }
+
+    /// 按 `tip_floor` 填充 `priority_fee` 的 tip
+    ///
+    /// `buy_tip_fees` 按 `swqos_types` 的顺序逐个服务填充，`smart_buy_tip_fee` 记录选出的
+    /// tip。卖出对所有服务只有一个 `sell_tip_fee`，取各服务 tip 的最大值以满足每个服务的
+    /// 最低 tip。
+    pub fn apply_tip_floor(
+        &self,
+        tip_floor: &TipFloor,
+        priority_fee: &mut PriorityFee,
+        trade_type: TradeType,
+        swqos_types: &[SwqosType],
+    ) {
+        let tip = self.tip(tip_floor, trade_type);
+        let provider_tips: Vec<f64> =
+            swqos_types.iter().map(|swqos_type| self.provider_tip(tip, swqos_type)).collect();
+        if matches!(trade_type, TradeType::Sell) {
+            priority_fee.sell_tip_fee = provider_tips.iter().copied().fold(tip, f64::max);
+        } else {
+            priority_fee.smart_buy_tip_fee = tip;
+            priority_fee.buy_tip_fee = provider_tips.first().copied().unwrap_or(tip);
+            priority_fee.buy_tip_fees = provider_tips;
+        }
+    }
+
+    /// 按 `TipCache` 中的最新分布填充 `priority_fee` 的 tip
+    ///
+    /// # 返回值
+    /// 分布过期或从未更新时不修改 `priority_fee` 并返回 false
+    pub fn apply(
+        &self,
+        priority_fee: &mut PriorityFee,
+        trade_type: TradeType,
+        swqos_types: &[SwqosType],
+    ) -> bool {
+        let Some(tip_floor) = TipCache::get_instance().get_tip_floor(self.max_age) else {
+            return false;
+        };
+        self.apply_tip_floor(&tip_floor, priority_fee, trade_type, swqos_types);
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_apply_tip_floor() {
+        let source = StaticTipFloorSource::new(TipFloor {
+            p25: 0.00001,
+            p50: 0.0001,
+            p75: 0.0005,
+            p95: 0.002,
+            p99: 0.02,
+            ema_p50: 0.00012,
+        });
+        let strategy = TipStrategy::new(0.00005, 0.01)
+            .with_percentile(TradeType::Sell, TipPercentile::P25)
+            .with_provider_min_tip(SwqosType::NextBlock, 0.001);
+        let swqos_types = [SwqosType::Jito, SwqosType::NextBlock];
+
+        let mut priority_fee = PriorityFee::default();
+        strategy.apply_tip_floor(
+            &source.fetch().await.unwrap(),
+            &mut priority_fee,
+            TradeType::Buy,
+            &swqos_types,
+        );
+        assert_eq!(priority_fee.smart_buy_tip_fee, 0.0005);
+        assert_eq!(priority_fee.buy_tip_fees, vec![0.0005, 0.001]);
+
+        // 卖出的 p25 低于下限，且需要满足 NextBlock 的最低 tip
+        strategy.apply_tip_floor(
+            &source.fetch().await.unwrap(),
+            &mut priority_fee,
+            TradeType::Sell,
+            &swqos_types,
+        );
+        assert_eq!(priority_fee.sell_tip_fee, 0.001);
+
+        // 行情火热时不超过上限
+        source.set(TipFloor { p75: 0.05, ..Default::default() });
+        strategy.apply_tip_floor(
+            &source.fetch().await.unwrap(),
+            &mut priority_fee,
+            TradeType::Buy,
+            &swqos_types,
+        );
+        assert_eq!(priority_fee.buy_tip_fees, vec![0.01, 0.01]);
+    }
+}
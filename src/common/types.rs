@@ -1,6 +1,7 @@
 use std::sync::Arc;
 
 use crate::{
+    common::priority_fee_estimator::PriorityFeeEstimator,
     constants::trade::trade::{
         DEFAULT_BUY_TIP_FEE, DEFAULT_RPC_UNIT_LIMIT, DEFAULT_RPC_UNIT_PRICE, DEFAULT_SELL_TIP_FEE,
         DEFAULT_TIP_UNIT_LIMIT, DEFAULT_TIP_UNIT_PRICE,
@@ -42,6 +43,10 @@ pub struct PriorityFee {
     pub buy_tip_fees: Vec<f64>,
     pub smart_buy_tip_fee: f64,
     pub sell_tip_fee: f64,
+    /// Prices compute units from recent prioritization fees, set by
+    /// `SolanaTrade::with_priority_fee_estimator`
+    #[serde(skip)]
+    pub estimator: Option<Arc<PriorityFeeEstimator>>,
 }
 
 impl Default for PriorityFee {
@@ -55,6 +60,7 @@ impl Default for PriorityFee {
             buy_tip_fees: vec![],
             smart_buy_tip_fee: 0.0,
             sell_tip_fee: DEFAULT_SELL_TIP_FEE,
+            estimator: None,
         }
     }
 }
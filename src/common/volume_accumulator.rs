@@ -0,0 +1,180 @@
+//! Volume accumulator accounts of Pump.fun and PumpSwap
+//!
+//! Both programs track the SOL volume each wallet trades and pay token incentives for it. A
+//! global accumulator holds the incentive tokens and total SOL volume of each day; a user
+//! accumulator holds a wallet's volume for the current day and the tokens it earned so far.
+//! Earned tokens are paid out with `claim_token_incentives`.
+//!
+//! # Fields
+//!
+//! - `GlobalVolumeAccumulator`: incentive period, day length, incentive mint, per-day supply and volume
+//! - `UserVolumeAccumulator`: owner, unclaimed and claimed tokens, volume since the last update
+
+use borsh::BorshDeserialize;
+use serde::{Deserialize, Serialize};
+use solana_sdk::pubkey::Pubkey;
+
+/// Anchor discriminator of the `GlobalVolumeAccumulator` account
+pub const GLOBAL_VOLUME_ACCUMULATOR_DISCRIMINATOR: [u8; 8] = [202, 42, 246, 43, 142, 190, 30, 255];
+
+/// Anchor discriminator of the `UserVolumeAccumulator` account
+pub const USER_VOLUME_ACCUMULATOR_DISCRIMINATOR: [u8; 8] = [86, 255, 112, 14, 102, 53, 154, 250];
+
+/// Number of days tracked by the global accumulator
+pub const VOLUME_ACCUMULATOR_DAYS: usize = 30;
+
+/// Global volume accumulator account
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, BorshDeserialize)]
+pub struct GlobalVolumeAccumulator {
+    /// Unix timestamp the incentive period starts at
+    pub start_time: i64,
+    /// Unix timestamp the incentive period ends at
+    pub end_time: i64,
+    /// Length of one incentive day in seconds
+    pub seconds_in_a_day: i64,
+    /// Incentive token mint
+    pub mint: Pubkey,
+    /// Incentive tokens distributed on each day
+    pub total_token_supply: [u64; VOLUME_ACCUMULATOR_DAYS],
+    /// SOL volume traded on each day
+    pub sol_volumes: [u64; VOLUME_ACCUMULATOR_DAYS],
+}
+
+/// User volume accumulator account
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, BorshDeserialize)]
+pub struct UserVolumeAccumulator {
+    /// Wallet the volume belongs to
+    pub user: Pubkey,
+    /// Whether tokens earned on a past day still have to be claimed
+    pub needs_claim: bool,
+    /// Tokens earned up to the last update and not claimed yet
+    pub total_unclaimed_tokens: u64,
+    /// Tokens claimed so far
+    pub total_claimed_tokens: u64,
+    /// SOL volume traded since the last update, counted towards that update's day
+    pub current_sol_volume: u64,
+    /// Unix timestamp of the last update
+    pub last_update_timestamp: i64,
+}
+
+impl GlobalVolumeAccumulator {
+    /// Decodes a global volume accumulator account, `data` includes the 8 byte discriminator
+    pub fn decode(data: &[u8]) -> Option<Self> {
+        if data.len() < 8 || data[..8] != GLOBAL_VOLUME_ACCUMULATOR_DISCRIMINATOR {
+            return None;
+        }
+        let mut slice = &data[8..];
+        <GlobalVolumeAccumulator as BorshDeserialize>::deserialize(&mut slice).ok()
+    }
+
+    /// Index of the incentive day containing `timestamp`, `None` outside the period
+    fn day_index(&self, timestamp: i64) -> Option<usize> {
+        if self.seconds_in_a_day <= 0 || timestamp < self.start_time {
+            return None;
+        }
+        Some(((timestamp - self.start_time) / self.seconds_in_a_day) as usize)
+    }
+}
+
+impl UserVolumeAccumulator {
+    /// Decodes a user volume accumulator account, `data` includes the 8 byte discriminator
+    ///
+    /// Fields appended by later program versions are ignored.
+    pub fn decode(data: &[u8]) -> Option<Self> {
+        if data.len() < 8 || data[..8] != USER_VOLUME_ACCUMULATOR_DISCRIMINATOR {
+            return None;
+        }
+        let mut slice = &data[8..];
+        <UserVolumeAccumulator as BorshDeserialize>::deserialize(&mut slice).ok()
+    }
+
+    /// Tokens `claim_token_incentives` would pay out at `now`
+    ///
+    /// Adds the share of the last update's day to the stored unclaimed tokens once that day
+    /// is over, the same way the program settles it on the next claim or trade.
+    pub fn unclaimed_tokens(&self, global: &GlobalVolumeAccumulator, now: i64) -> u64 {
+        let unclaimed = self.total_unclaimed_tokens;
+        let (Some(current_day), Some(last_update_day), Some(end_day)) = (
+            global.day_index(now),
+            global.day_index(self.last_update_timestamp),
+            global.day_index(global.end_time),
+        ) else {
+            return unclaimed;
+        };
+        if current_day <= last_update_day
+            || last_update_day > end_day
+            || last_update_day >= VOLUME_ACCUMULATOR_DAYS
+        {
+            return unclaimed;
+        }
+        let day_sol_volume = global.sol_volumes[last_update_day];
+        if day_sol_volume == 0 {
+            return unclaimed;
+        }
+        let earned = self.current_sol_volume as u128
+            * global.total_token_supply[last_update_day] as u128
+            / day_sol_volume as u128;
+        unclaimed + earned as u64
+    }
+
+    /// Incentives of this wallet at `now`
+    pub fn incentives(&self, global: &GlobalVolumeAccumulator, now: i64) -> TokenIncentives {
+        TokenIncentives {
+            mint: global.mint,
+            unclaimed_tokens: self.unclaimed_tokens(global, now),
+            total_claimed_tokens: self.total_claimed_tokens,
+            current_sol_volume: self.current_sol_volume,
+        }
+    }
+}
+
+/// Current unix timestamp, used to settle incentives off-chain
+pub(crate) fn unix_timestamp() -> i64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|duration| duration.as_secs() as i64)
+        .unwrap_or_default()
+}
+
+/// Incentives of one wallet in one program
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TokenIncentives {
+    /// Incentive token mint
+    pub mint: Pubkey,
+    /// Tokens claimable now
+    pub unclaimed_tokens: u64,
+    /// Tokens claimed so far
+    pub total_claimed_tokens: u64,
+    /// SOL volume traded since the last update
+    pub current_sol_volume: u64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unclaimed_tokens() {
+        let mut global = GlobalVolumeAccumulator {
+            start_time: 1_000,
+            end_time: 1_000 + 30 * 100,
+            seconds_in_a_day: 100,
+            ..Default::default()
+        };
+        global.total_token_supply[1] = 1_000_000;
+        global.sol_volumes[1] = 400;
+        let user = UserVolumeAccumulator {
+            total_unclaimed_tokens: 50,
+            current_sol_volume: 100,
+            last_update_timestamp: 1_150,
+            ..Default::default()
+        };
+
+        // Day 1 is still running, its share is not settled yet
+        assert_eq!(user.unclaimed_tokens(&global, 1_199), 50);
+        // Once day 1 is over the user earns a quarter of its supply
+        assert_eq!(user.unclaimed_tokens(&global, 1_200), 250_050);
+        // Before the period starts nothing is added
+        assert_eq!(user.unclaimed_tokens(&global, 900), 50);
+    }
+}
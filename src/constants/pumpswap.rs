@@ -24,8 +24,20 @@ pub mod seeds {
     /// Seed for metadata PDAs
     pub const METADATA_SEED: &[u8] = b"metadata";
 
+    /// Seed for pool PDAs
+    pub const POOL_SEED: &[u8] = b"pool";
+
+    /// Seed for pool LP mint PDAs
+    pub const POOL_LP_MINT_SEED: &[u8] = b"pool_lp_mint";
+
+    /// Seed of the Pump.fun PDA that creates the canonical pool of a migrated coin
+    pub const POOL_AUTHORITY_SEED: &[u8] = b"pool-authority";
+
     pub const USER_VOLUME_ACCUMULATOR_SEED: &[u8] = b"user_volume_accumulator";
     pub const GLOBAL_VOLUME_ACCUMULATOR_SEED: &[u8] = b"global_volume_accumulator";
+
+    /// Seed for the fee config PDA, derived under the Pump fee program
+    pub const FEE_CONFIG_SEED: &[u8] = b"fee_config";
 }
 
 /// Constants related to program accounts and authorities
@@ -49,6 +61,9 @@ pub mod accounts {
     /// Token Program ID
     pub const TOKEN_PROGRAM: Pubkey = spl_token::ID;
 
+    /// Token-2022 Program ID, owner of every pool LP mint
+    pub const TOKEN_PROGRAM_2022: Pubkey = spl_token_2022::ID;
+
     /// Associated Token Program ID
     pub const ASSOCIATED_TOKEN_PROGRAM: Pubkey =
         pubkey!("ATokenGPvbdGVxr1b2hvZbsiqW5xWH25efTNsLJA8knL");
@@ -65,8 +80,20 @@ pub mod accounts {
     pub const LP_FEE_BASIS_POINTS: u64 = 20;
     pub const PROTOCOL_FEE_BASIS_POINTS: u64 = 5;
     pub const COIN_CREATOR_FEE_BASIS_POINTS: u64 = 5;
-    
+
+    /// Pump fee program, owner of the fee config account
+    pub const FEE_PROGRAM: Pubkey = pubkey!("pfeeUxB6jkeY1Hxd7CsFCAjcbHA9rWtchMGdZ6VojVZ");
+
+    /// Base mint supply of coins migrated from Pump.fun, used when the real supply is unknown
+    pub const BASE_MINT_SUPPLY: u64 = 1_000_000_000_000_000;
+
+    /// Compute unit limit floor for create_pool, which initializes several accounts
+    pub const CREATE_POOL_UNIT_LIMIT: u32 = 300_000;
 }
 
 pub const BUY_DISCRIMINATOR: [u8; 8] = [102, 6, 61, 18, 1, 218, 235, 234];
 pub const SELL_DISCRIMINATOR: [u8; 8] = [51, 230, 133, 164, 1, 127, 131, 173];
+pub const CREATE_POOL_DISCRIMINATOR: [u8; 8] = [233, 146, 209, 142, 207, 104, 64, 188];
+pub const COLLECT_COIN_CREATOR_FEE_DISCRIMINATOR: [u8; 8] = [160, 57, 89, 42, 181, 139, 43, 66];
+pub const CLAIM_TOKEN_INCENTIVES_DISCRIMINATOR: [u8; 8] = [16, 4, 71, 28, 204, 1, 40, 27];
+pub const INIT_USER_VOLUME_ACCUMULATOR_DISCRIMINATOR: [u8; 8] = [94, 6, 202, 115, 255, 96, 232, 183];
@@ -167,3 +167,5 @@ pub const SWQOS_ENDPOINTS_FLASHBLOCK: [&str; 8] = [
     "http://ny.flashblock.trade",
 ];
 
+/// Jito 最近落地 bundle 的 tip 分布接口
+pub const JITO_TIP_FLOOR_URL: &str = "https://bundles.jito.wtf/api/v1/bundles/tip_floor";
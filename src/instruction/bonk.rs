@@ -13,7 +13,7 @@ use crate::{
         bonk::common::{get_pool_pda, get_vault_pda},
         common::utils::get_token_balance,
         core::{
-            params::{BonkParams, BuyParams, SellParams},
+            params::{BonkParams, BuyParams, SellParams, SwapQuote},
             traits::InstructionBuilder,
         },
     },
@@ -37,6 +37,57 @@ impl InstructionBuilder for BonkInstructionBuilder {
     async fn build_sell_instructions(&self, params: &SellParams) -> Result<Vec<Instruction>> {
         self.build_sell_instructions_with_accounts(params).await
     }
+
+    async fn quote_buy(&self, params: &BuyParams) -> Result<SwapQuote> {
+        let protocol_params = params
+            .protocol_params
+            .as_any()
+            .downcast_ref::<BonkParams>()
+            .ok_or_else(|| anyhow!("Invalid protocol params for Bonk"))?;
+
+        let quote = |slippage_basis_points: u64| {
+            get_buy_token_amount_from_sol_amount(
+                params.sol_amount,
+                protocol_params.virtual_base,
+                protocol_params.virtual_quote,
+                protocol_params.real_base,
+                protocol_params.real_quote,
+                slippage_basis_points as u128,
+            )
+        };
+        Ok(SwapQuote {
+            amount_in: params.sol_amount,
+            max_amount_in: params.sol_amount,
+            amount_out: quote(0),
+            min_amount_out: quote(params.slippage_basis_points.unwrap_or(DEFAULT_SLIPPAGE)),
+        })
+    }
+
+    async fn quote_sell(&self, params: &SellParams) -> Result<SwapQuote> {
+        let protocol_params = params
+            .protocol_params
+            .as_any()
+            .downcast_ref::<BonkParams>()
+            .ok_or_else(|| anyhow!("Invalid protocol params for Bonk"))?;
+
+        let token_amount = params.token_amount.unwrap_or(0);
+        let quote = |slippage_basis_points: u64| {
+            get_sell_sol_amount_from_token_amount(
+                token_amount,
+                protocol_params.virtual_base,
+                protocol_params.virtual_quote,
+                protocol_params.real_base,
+                protocol_params.real_quote,
+                slippage_basis_points as u128,
+            )
+        };
+        Ok(SwapQuote {
+            amount_in: token_amount,
+            max_amount_in: token_amount,
+            amount_out: quote(0),
+            min_amount_out: quote(params.slippage_basis_points.unwrap_or(DEFAULT_SLIPPAGE)),
+        })
+    }
 }
 
 impl BonkInstructionBuilder {
@@ -1,28 +1,33 @@
 use anyhow::{anyhow, Result};
 use solana_sdk::instruction::Instruction;
 use spl_associated_token_account::{
-    get_associated_token_address, instruction::create_associated_token_account,
+    get_associated_token_address, get_associated_token_address_with_program_id,
+    instruction::create_associated_token_account,
 };
 use spl_token::instruction::close_account;
 
 use crate::{
+    common::bonding_curve::BondingCurveAccount,
     constants,
     trading::pumpfun::common::{
-        get_bonding_curve_pda, get_global_volume_accumulator_pda, get_user_volume_accumulator_pda,
+        get_bonding_curve_pda, get_cached_fee_config, get_cached_global_account,
+        get_global_volume_accumulator_pda, get_user_volume_accumulator_pda,
     },
     utils::calc::{
         common::{calculate_with_slippage_buy, calculate_with_slippage_sell},
-        pumpfun::{get_buy_token_amount_from_sol_amount, get_sell_sol_amount_from_token_amount},
+        pumpfun::{
+            compute_fees, get_buy_token_amount_from_sol_amount,
+            get_sell_sol_amount_from_token_amount, PumpFunFees,
+        },
     },
 };
 
 use solana_sdk::{instruction::AccountMeta, pubkey::Pubkey, signature::Keypair, signer::Signer};
 
 use crate::{
-    constants::pumpfun::global_constants::FEE_RECIPIENT,
     constants::trade::trade::DEFAULT_SLIPPAGE,
     trading::core::{
-        params::{BuyParams, PumpFunParams, SellParams},
+        params::{BuyParams, PumpFunParams, SellParams, SwapQuote},
         traits::InstructionBuilder,
     },
     trading::pumpfun::common::get_creator_vault_pda,
@@ -53,12 +58,9 @@ impl InstructionBuilder for PumpFunInstructionBuilder {
         );
         let creator_vault_pda = protocol_params.creator_vault;
 
-        let mut creator = Pubkey::default();
-        if let Some(default_creator_ata) = get_creator_vault_pda(&creator) {
-            if default_creator_ata != creator_vault_pda {
-                creator = creator_vault_pda;
-            }
-        }
+        // Fees and fee recipient come from the cached on-chain config, constants otherwise
+        let global_account = get_cached_global_account().await;
+        let (creator, fees) = creator_and_fees(&bonding_curve, creator_vault_pda).await;
 
         let buy_token_amount = get_buy_token_amount_from_sol_amount(
             bonding_curve.virtual_token_reserves as u128,
@@ -66,6 +68,7 @@ impl InstructionBuilder for PumpFunInstructionBuilder {
             bonding_curve.real_token_reserves as u128,
             creator,
             params.sol_amount,
+            &fees,
         );
 
         let mut instructions = vec![];
@@ -84,7 +87,7 @@ impl InstructionBuilder for PumpFunInstructionBuilder {
             &params.mint,
             &bonding_curve.account,
             &creator_vault_pda,
-            &FEE_RECIPIENT,
+            &global_account.fee_recipient,
             Buy { _amount: buy_token_amount, _max_sol_cost: max_sol_cost },
         ));
 
@@ -112,18 +115,16 @@ impl InstructionBuilder for PumpFunInstructionBuilder {
         let creator_vault_pda = protocol_params.creator_vault;
         let ata = get_associated_token_address(&params.payer.pubkey(), &params.mint);
 
-        let mut creator = Pubkey::default();
-        if let Some(default_creator_ata) = get_creator_vault_pda(&creator) {
-            if default_creator_ata != creator_vault_pda {
-                creator = creator_vault_pda;
-            }
-        }
+        // Fees and fee recipient come from the cached on-chain config, constants otherwise
+        let global_account = get_cached_global_account().await;
+        let (creator, fees) = creator_and_fees(&bonding_curve, creator_vault_pda).await;
 
         let sol_amount = get_sell_sol_amount_from_token_amount(
             bonding_curve.virtual_token_reserves as u128,
             bonding_curve.virtual_sol_reserves as u128,
             creator,
             token_amount,
+            &fees,
         );
         let min_sol_output = calculate_with_slippage_sell(
             sol_amount,
@@ -134,7 +135,7 @@ impl InstructionBuilder for PumpFunInstructionBuilder {
             params.payer.as_ref(),
             &params.mint,
             &creator_vault_pda,
-            &FEE_RECIPIENT,
+            &global_account.fee_recipient,
             Sell { _amount: token_amount, _min_sol_output: min_sol_output },
         )];
 
@@ -151,6 +152,87 @@ impl InstructionBuilder for PumpFunInstructionBuilder {
 
         Ok(instructions)
     }
+
+    async fn quote_buy(&self, params: &BuyParams) -> Result<SwapQuote> {
+        let protocol_params = params
+            .protocol_params
+            .as_any()
+            .downcast_ref::<PumpFunParams>()
+            .ok_or_else(|| anyhow!("Invalid protocol params for PumpFun"))?;
+        let bonding_curve = &protocol_params.bonding_curve;
+        let (creator, fees) = creator_and_fees(bonding_curve, protocol_params.creator_vault).await;
+
+        // The buy instruction takes an exact token amount and caps the SOL spent
+        let token_amount = get_buy_token_amount_from_sol_amount(
+            bonding_curve.virtual_token_reserves as u128,
+            bonding_curve.virtual_sol_reserves as u128,
+            bonding_curve.real_token_reserves as u128,
+            creator,
+            params.sol_amount,
+            &fees,
+        );
+        Ok(SwapQuote {
+            amount_in: params.sol_amount,
+            max_amount_in: calculate_with_slippage_buy(
+                params.sol_amount,
+                params.slippage_basis_points.unwrap_or(DEFAULT_SLIPPAGE),
+            ),
+            amount_out: token_amount,
+            min_amount_out: token_amount,
+        })
+    }
+
+    async fn quote_sell(&self, params: &SellParams) -> Result<SwapQuote> {
+        let protocol_params = params
+            .protocol_params
+            .as_any()
+            .downcast_ref::<PumpFunParams>()
+            .ok_or_else(|| anyhow!("Invalid protocol params for PumpFun"))?;
+        let bonding_curve = &protocol_params.bonding_curve;
+        let (creator, fees) = creator_and_fees(bonding_curve, protocol_params.creator_vault).await;
+
+        let token_amount = params.token_amount.unwrap_or(0);
+        let sol_amount = get_sell_sol_amount_from_token_amount(
+            bonding_curve.virtual_token_reserves as u128,
+            bonding_curve.virtual_sol_reserves as u128,
+            creator,
+            token_amount,
+            &fees,
+        );
+        Ok(SwapQuote {
+            amount_in: token_amount,
+            max_amount_in: token_amount,
+            amount_out: sol_amount,
+            min_amount_out: calculate_with_slippage_sell(
+                sol_amount,
+                params.slippage_basis_points.unwrap_or(DEFAULT_SLIPPAGE),
+            ),
+        })
+    }
+}
+
+/// Creator charged the creator fee and the fees for a trade on the bonding curve
+async fn creator_and_fees(
+    bonding_curve: &BondingCurveAccount,
+    creator_vault: Pubkey,
+) -> (Pubkey, PumpFunFees) {
+    let mut creator = Pubkey::default();
+    if let Some(default_creator_ata) = get_creator_vault_pda(&creator) {
+        if default_creator_ata != creator_vault {
+            creator = creator_vault;
+        }
+    }
+
+    let global_account = get_cached_global_account().await;
+    let fee_config = get_cached_fee_config().await;
+    let fees = compute_fees(
+        &global_account,
+        fee_config.as_deref(),
+        bonding_curve.virtual_sol_reserves as u128,
+        bonding_curve.virtual_token_reserves as u128,
+        bonding_curve.token_total_supply as u128,
+    );
+    (creator, fees)
 }
 
 pub struct Buy {
@@ -242,3 +324,77 @@ pub fn sell(
         ],
     )
 }
+
+/// Sweeps the creator vault, shared by every coin `creator` launched, into the creator wallet
+pub fn collect_creator_fee(creator: &Pubkey) -> Instruction {
+    Instruction::new_with_bytes(
+        constants::pumpfun::accounts::PUMPFUN,
+        &[20, 22, 86, 123, 198, 28, 219, 132], // discriminator
+        vec![
+            AccountMeta::new(*creator, true),
+            AccountMeta::new(get_creator_vault_pda(creator).unwrap(), false),
+            AccountMeta::new_readonly(constants::pumpfun::accounts::SYSTEM_PROGRAM, false),
+            AccountMeta::new_readonly(constants::pumpfun::accounts::EVENT_AUTHORITY, false),
+            AccountMeta::new_readonly(constants::pumpfun::accounts::PUMPFUN, false),
+        ],
+    )
+}
+
+/// Claims the incentive tokens `user` earned by trading on bonding curves
+///
+/// The tokens go to the user's token account, which `payer` creates if it does not exist yet.
+pub fn claim_token_incentives(
+    payer: &Pubkey,
+    user: &Pubkey,
+    mint: &Pubkey,
+    token_program: &Pubkey,
+) -> Instruction {
+    let global_volume_accumulator = get_global_volume_accumulator_pda().unwrap();
+    Instruction::new_with_bytes(
+        constants::pumpfun::accounts::PUMPFUN,
+        &[16, 4, 71, 28, 204, 1, 40, 27], // discriminator
+        vec![
+            AccountMeta::new_readonly(*user, false),
+            AccountMeta::new(
+                get_associated_token_address_with_program_id(user, mint, token_program),
+                false,
+            ),
+            AccountMeta::new_readonly(global_volume_accumulator, false),
+            AccountMeta::new(
+                get_associated_token_address_with_program_id(
+                    &global_volume_accumulator,
+                    mint,
+                    token_program,
+                ),
+                false,
+            ),
+            AccountMeta::new(get_user_volume_accumulator_pda(user).unwrap(), false),
+            AccountMeta::new_readonly(*mint, false),
+            AccountMeta::new_readonly(*token_program, false),
+            AccountMeta::new_readonly(constants::pumpfun::accounts::SYSTEM_PROGRAM, false),
+            AccountMeta::new_readonly(
+                constants::pumpfun::accounts::ASSOCIATED_TOKEN_PROGRAM,
+                false,
+            ),
+            AccountMeta::new_readonly(constants::pumpfun::accounts::EVENT_AUTHORITY, false),
+            AccountMeta::new_readonly(constants::pumpfun::accounts::PUMPFUN, false),
+            AccountMeta::new(*payer, true),
+        ],
+    )
+}
+
+/// Creates the volume accumulator of `user`, paid by `payer`
+pub fn init_user_volume_accumulator(payer: &Pubkey, user: &Pubkey) -> Instruction {
+    Instruction::new_with_bytes(
+        constants::pumpfun::accounts::PUMPFUN,
+        &[94, 6, 202, 115, 255, 96, 232, 183], // discriminator
+        vec![
+            AccountMeta::new(*payer, true),
+            AccountMeta::new_readonly(*user, false),
+            AccountMeta::new(get_user_volume_accumulator_pda(user).unwrap(), false),
+            AccountMeta::new_readonly(constants::pumpfun::accounts::SYSTEM_PROGRAM, false),
+            AccountMeta::new_readonly(constants::pumpfun::accounts::EVENT_AUTHORITY, false),
+            AccountMeta::new_readonly(constants::pumpfun::accounts::PUMPFUN, false),
+        ],
+    )
+}
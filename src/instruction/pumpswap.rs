@@ -6,22 +6,48 @@ use spl_token::instruction::close_account;
 
 use crate::{
     constants::{
-        pumpswap::{accounts, BUY_DISCRIMINATOR, SELL_DISCRIMINATOR},
+        pumpswap::{
+            accounts, BUY_DISCRIMINATOR, CLAIM_TOKEN_INCENTIVES_DISCRIMINATOR,
+            COLLECT_COIN_CREATOR_FEE_DISCRIMINATOR, CREATE_POOL_DISCRIMINATOR,
+            INIT_USER_VOLUME_ACCUMULATOR_DISCRIMINATOR, SELL_DISCRIMINATOR,
+        },
         trade::trade::DEFAULT_SLIPPAGE,
     },
     trading::{
         core::{
-            params::{BuyParams, PumpSwapParams, SellParams},
+            params::{BuyParams, PumpSwapCreatePoolParams, PumpSwapParams, SellParams, SwapQuote},
             traits::InstructionBuilder,
         },
         pumpswap::common::{
             coin_creator_vault_ata, coin_creator_vault_authority, fee_recipient_ata,
-            get_global_volume_accumulator_pda, get_user_volume_accumulator_pda,
+            get_cached_fee_config, get_cached_global_config, get_global_volume_accumulator_pda,
+            get_lp_mint_pda, get_pool_pda, get_pool_token_account, get_user_volume_accumulator_pda,
+            is_canonical_pool,
         },
     },
-    utils::calc::pumpswap::{buy_quote_input_internal, sell_base_input_internal},
+    utils::calc::pumpswap::{
+        buy_quote_input_internal, compute_fees, sell_base_input_internal, PumpSwapFees,
+    },
 };
 
+/// Fees for a pool trade from the cached on-chain config, falling back to the constants
+pub async fn pool_fees(protocol_params: &PumpSwapParams) -> PumpSwapFees {
+    let global_config = get_cached_global_config().await;
+    let fee_config = get_cached_fee_config().await;
+    compute_fees(
+        global_config.as_deref(),
+        fee_config.as_deref(),
+        is_canonical_pool(
+            &protocol_params.pool,
+            &protocol_params.base_mint,
+            &protocol_params.quote_mint,
+        ),
+        protocol_params.pool_base_token_reserves,
+        protocol_params.pool_quote_token_reserves,
+        protocol_params.base_mint_supply,
+    )
+}
+
 /// Instruction builder for PumpSwap protocol
 pub struct PumpSwapInstructionBuilder;
 
@@ -46,6 +72,7 @@ impl InstructionBuilder for PumpSwapInstructionBuilder {
         let pool_quote_token_reserves = protocol_params.pool_quote_token_reserves;
         let coin_creator_vault_ata = protocol_params.coin_creator_vault_ata;
         let coin_creator_vault_authority = protocol_params.coin_creator_vault_authority;
+        let fees = pool_fees(protocol_params).await;
 
         self.build_buy_instructions_with_accounts(
             params,
@@ -59,6 +86,7 @@ impl InstructionBuilder for PumpSwapInstructionBuilder {
             protocol_params.auto_handle_wsol,
             protocol_params.base_token_program,
             protocol_params.quote_token_program,
+            &fees,
         )
         .await
     }
@@ -77,6 +105,7 @@ impl InstructionBuilder for PumpSwapInstructionBuilder {
         let pool_quote_token_reserves = protocol_params.pool_quote_token_reserves;
         let coin_creator_vault_ata = protocol_params.coin_creator_vault_ata;
         let coin_creator_vault_authority = protocol_params.coin_creator_vault_authority;
+        let fees = pool_fees(protocol_params).await;
 
         self.build_sell_instructions_with_accounts(
             params,
@@ -90,12 +119,386 @@ impl InstructionBuilder for PumpSwapInstructionBuilder {
             protocol_params.auto_handle_wsol,
             protocol_params.base_token_program,
             protocol_params.quote_token_program,
+            &fees,
         )
         .await
     }
+
+    async fn quote_buy(&self, params: &BuyParams) -> Result<SwapQuote> {
+        let protocol_params = params
+            .protocol_params
+            .as_any()
+            .downcast_ref::<PumpSwapParams>()
+            .ok_or_else(|| anyhow!("Invalid protocol params for PumpSwap"))?;
+        let is_quote_in = protocol_params.quote_mint == accounts::WSOL_TOKEN_ACCOUNT;
+        quote_swap(protocol_params, params.sol_amount, params.slippage_basis_points, is_quote_in)
+            .await
+    }
+
+    async fn quote_sell(&self, params: &SellParams) -> Result<SwapQuote> {
+        let protocol_params = params
+            .protocol_params
+            .as_any()
+            .downcast_ref::<PumpSwapParams>()
+            .ok_or_else(|| anyhow!("Invalid protocol params for PumpSwap"))?;
+        let is_quote_in = protocol_params.quote_mint != accounts::WSOL_TOKEN_ACCOUNT;
+        quote_swap(
+            protocol_params,
+            params.token_amount.unwrap_or(0),
+            params.slippage_basis_points,
+            is_quote_in,
+        )
+        .await
+    }
+}
+
+/// Quote a swap the way the buy/sell builders do: quote input buys base with an exact base
+/// output and a capped quote input, base input sells base for a minimum quote output
+async fn quote_swap(
+    protocol_params: &PumpSwapParams,
+    amount_in: u64,
+    slippage_basis_points: Option<u64>,
+    is_quote_in: bool,
+) -> Result<SwapQuote> {
+    let slippage_basis_points = slippage_basis_points.unwrap_or(DEFAULT_SLIPPAGE);
+    let base_reserve = protocol_params.pool_base_token_reserves;
+    let quote_reserve = protocol_params.pool_quote_token_reserves;
+    let creator = protocol_params.coin_creator_vault_authority;
+    let fees = pool_fees(protocol_params).await;
+
+    if is_quote_in {
+        let result = buy_quote_input_internal(
+            amount_in,
+            slippage_basis_points,
+            base_reserve,
+            quote_reserve,
+            &creator,
+            &fees,
+        )
+        .map_err(|e| anyhow!(e))?;
+        Ok(SwapQuote {
+            amount_in,
+            max_amount_in: result.max_quote,
+            amount_out: result.base,
+            min_amount_out: result.base,
+        })
+    } else {
+        let result = sell_base_input_internal(
+            amount_in,
+            slippage_basis_points,
+            base_reserve,
+            quote_reserve,
+            &creator,
+            &fees,
+        )
+        .map_err(|e| anyhow!(e))?;
+        Ok(SwapQuote {
+            amount_in,
+            max_amount_in: amount_in,
+            amount_out: result.ui_quote,
+            min_amount_out: result.min_quote,
+        })
+    }
 }
 
 impl PumpSwapInstructionBuilder {
+    /// Build create_pool instructions
+    ///
+    /// Derives the pool, LP mint and pool vault addresses, wraps the WSOL side of the deposit
+    /// when `auto_handle_wsol` is set, and returns the instructions together with
+    /// `PumpSwapParams` describing the new pool so it can be traded right away.
+    pub fn build_create_pool_instructions(
+        &self,
+        params: &PumpSwapCreatePoolParams,
+    ) -> Result<(Vec<Instruction>, PumpSwapParams)> {
+        if params.base_amount_in == 0 || params.quote_amount_in == 0 {
+            return Err(anyhow!("Initial deposit amounts cannot be zero"));
+        }
+        if params.base_mint == params.quote_mint {
+            return Err(anyhow!("Base mint and quote mint must be different"));
+        }
+
+        let creator = params.payer.pubkey();
+        let base_mint = params.base_mint;
+        let quote_mint = params.quote_mint;
+        let base_token_program = params.base_token_program;
+        let quote_token_program = params.quote_token_program;
+
+        let pool = get_pool_pda(params.index, &creator, &base_mint, &quote_mint)
+            .ok_or_else(|| anyhow!("Failed to derive pool address"))?;
+        let lp_mint =
+            get_lp_mint_pda(&pool).ok_or_else(|| anyhow!("Failed to derive LP mint address"))?;
+
+        let user_base_token_account =
+            spl_associated_token_account::get_associated_token_address_with_program_id(
+                &creator,
+                &base_mint,
+                &base_token_program,
+            );
+        let user_quote_token_account =
+            spl_associated_token_account::get_associated_token_address_with_program_id(
+                &creator,
+                &quote_mint,
+                &quote_token_program,
+            );
+        let user_pool_token_account =
+            spl_associated_token_account::get_associated_token_address_with_program_id(
+                &creator,
+                &lp_mint,
+                &accounts::TOKEN_PROGRAM_2022,
+            );
+        let pool_base_token_account =
+            get_pool_token_account(&pool, &base_mint, &base_token_program);
+        let pool_quote_token_account =
+            get_pool_token_account(&pool, &quote_mint, &quote_token_program);
+
+        // Which side of the deposit, if any, has to be wrapped from SOL
+        let wsol_deposit = if quote_mint == accounts::WSOL_TOKEN_ACCOUNT {
+            Some((user_quote_token_account, params.quote_amount_in))
+        } else if base_mint == accounts::WSOL_TOKEN_ACCOUNT {
+            Some((user_base_token_account, params.base_amount_in))
+        } else {
+            None
+        };
+
+        let mut instructions = vec![];
+
+        if params.auto_handle_wsol {
+            if let Some((wsol_token_account, wsol_amount)) = wsol_deposit {
+                // Create wSOL ATA account if it doesn't exist
+                instructions.push(create_associated_token_account_idempotent(
+                    &creator,
+                    &creator,
+                    &accounts::WSOL_TOKEN_ACCOUNT,
+                    &accounts::TOKEN_PROGRAM,
+                ));
+                // Transfer SOL to wSOL ATA account
+                instructions.push(transfer(&creator, &wsol_token_account, wsol_amount));
+                // Sync wSOL balance
+                instructions.push(
+                    spl_token::instruction::sync_native(
+                        &accounts::TOKEN_PROGRAM,
+                        &wsol_token_account,
+                    )
+                    .unwrap(),
+                );
+            }
+        }
+
+        // Create create_pool instruction
+        let accounts = vec![
+            solana_sdk::instruction::AccountMeta::new(pool, false), // pool
+            solana_sdk::instruction::AccountMeta::new_readonly(accounts::GLOBAL_ACCOUNT, false), // global_config (readonly)
+            solana_sdk::instruction::AccountMeta::new(creator, true), // creator (signer)
+            solana_sdk::instruction::AccountMeta::new_readonly(base_mint, false), // base_mint (readonly)
+            solana_sdk::instruction::AccountMeta::new_readonly(quote_mint, false), // quote_mint (readonly)
+            solana_sdk::instruction::AccountMeta::new(lp_mint, false),             // lp_mint
+            solana_sdk::instruction::AccountMeta::new(user_base_token_account, false), // user_base_token_account
+            solana_sdk::instruction::AccountMeta::new(user_quote_token_account, false), // user_quote_token_account
+            solana_sdk::instruction::AccountMeta::new(user_pool_token_account, false), // user_pool_token_account
+            solana_sdk::instruction::AccountMeta::new(pool_base_token_account, false), // pool_base_token_account
+            solana_sdk::instruction::AccountMeta::new(pool_quote_token_account, false), // pool_quote_token_account
+            solana_sdk::instruction::AccountMeta::new_readonly(accounts::SYSTEM_PROGRAM, false), // System Program (readonly)
+            solana_sdk::instruction::AccountMeta::new_readonly(accounts::TOKEN_PROGRAM_2022, false), // TOKEN_2022_PROGRAM_ID (readonly)
+            solana_sdk::instruction::AccountMeta::new_readonly(base_token_program, false), // base_token_program (readonly)
+            solana_sdk::instruction::AccountMeta::new_readonly(quote_token_program, false), // quote_token_program (readonly)
+            solana_sdk::instruction::AccountMeta::new_readonly(
+                accounts::ASSOCIATED_TOKEN_PROGRAM,
+                false,
+            ), // ASSOCIATED_TOKEN_PROGRAM_ID (readonly)
+            solana_sdk::instruction::AccountMeta::new_readonly(accounts::EVENT_AUTHORITY, false), // event_authority (readonly)
+            solana_sdk::instruction::AccountMeta::new_readonly(accounts::AMM_PROGRAM, false), // PUMP_AMM_PROGRAM_ID (readonly)
+        ];
+
+        // Create instruction data
+        let mut data = Vec::with_capacity(8 + 2 + 8 + 8 + 32);
+        data.extend_from_slice(&CREATE_POOL_DISCRIMINATOR);
+        // index
+        data.extend_from_slice(&params.index.to_le_bytes());
+        // base_amount_in
+        data.extend_from_slice(&params.base_amount_in.to_le_bytes());
+        // quote_amount_in
+        data.extend_from_slice(&params.quote_amount_in.to_le_bytes());
+        // coin_creator
+        data.extend_from_slice(params.coin_creator.as_ref());
+
+        instructions.push(Instruction { program_id: accounts::AMM_PROGRAM, accounts, data });
+
+        if params.auto_handle_wsol {
+            if let Some((wsol_token_account, _)) = wsol_deposit {
+                // Close wSOL ATA account, reclaim rent
+                instructions.push(
+                    close_account(
+                        &accounts::TOKEN_PROGRAM,
+                        &wsol_token_account,
+                        &creator,
+                        &creator,
+                        &[&creator],
+                    )
+                    .unwrap(),
+                );
+            }
+        }
+
+        let pool_params = PumpSwapParams {
+            pool,
+            base_mint,
+            quote_mint,
+            pool_base_token_reserves: params.base_amount_in,
+            pool_quote_token_reserves: params.quote_amount_in,
+            // Pools created here are never canonical, so the supply does not affect their fees
+            base_mint_supply: accounts::BASE_MINT_SUPPLY,
+            coin_creator_vault_ata: coin_creator_vault_ata(params.coin_creator, quote_mint),
+            coin_creator_vault_authority: coin_creator_vault_authority(params.coin_creator),
+            base_token_program,
+            quote_token_program,
+            auto_handle_wsol: params.auto_handle_wsol,
+        };
+
+        Ok((instructions, pool_params))
+    }
+
+    /// Build collect_coin_creator_fee instructions
+    ///
+    /// Moves the `quote_mint` fees of every pool `coin_creator` created into the creator's
+    /// token account. WSOL fees are unwrapped to SOL.
+    pub fn build_collect_coin_creator_fee_instructions(
+        &self,
+        coin_creator: &Pubkey,
+        quote_mint: &Pubkey,
+        quote_token_program: &Pubkey,
+    ) -> Vec<Instruction> {
+        let vault_authority = coin_creator_vault_authority(*coin_creator);
+        let vault_ata = spl_associated_token_account::get_associated_token_address_with_program_id(
+            &vault_authority,
+            quote_mint,
+            quote_token_program,
+        );
+        let creator_token_account =
+            spl_associated_token_account::get_associated_token_address_with_program_id(
+                coin_creator,
+                quote_mint,
+                quote_token_program,
+            );
+
+        let mut instructions = vec![create_associated_token_account_idempotent(
+            coin_creator,
+            coin_creator,
+            quote_mint,
+            quote_token_program,
+        )];
+
+        let accounts = vec![
+            solana_sdk::instruction::AccountMeta::new_readonly(*quote_mint, false), // quote_mint (readonly)
+            solana_sdk::instruction::AccountMeta::new_readonly(*quote_token_program, false), // quote_token_program (readonly)
+            solana_sdk::instruction::AccountMeta::new(*coin_creator, true), // coin_creator (signer)
+            solana_sdk::instruction::AccountMeta::new_readonly(vault_authority, false), // coin_creator_vault_authority (readonly)
+            solana_sdk::instruction::AccountMeta::new(vault_ata, false), // coin_creator_vault_ata
+            solana_sdk::instruction::AccountMeta::new(creator_token_account, false), // coin_creator_token_account
+            solana_sdk::instruction::AccountMeta::new_readonly(accounts::EVENT_AUTHORITY, false), // event_authority (readonly)
+            solana_sdk::instruction::AccountMeta::new_readonly(accounts::AMM_PROGRAM, false), // PUMP_AMM_PROGRAM_ID (readonly)
+        ];
+        instructions.push(Instruction {
+            program_id: accounts::AMM_PROGRAM,
+            accounts,
+            data: COLLECT_COIN_CREATOR_FEE_DISCRIMINATOR.to_vec(),
+        });
+
+        if *quote_mint == accounts::WSOL_TOKEN_ACCOUNT {
+            // Close wSOL ATA account, unwrapping the fees
+            instructions.push(
+                close_account(
+                    quote_token_program,
+                    &creator_token_account,
+                    coin_creator,
+                    coin_creator,
+                    &[coin_creator],
+                )
+                .unwrap(),
+            );
+        }
+
+        instructions
+    }
+
+    /// Build a claim_token_incentives instruction
+    ///
+    /// Claims the incentive tokens `user` earned by trading on PumpSwap into the user's token
+    /// account, which `payer` creates if it does not exist yet.
+    pub fn build_claim_token_incentives_instruction(
+        &self,
+        payer: &Pubkey,
+        user: &Pubkey,
+        mint: &Pubkey,
+        token_program: &Pubkey,
+    ) -> Instruction {
+        let global_volume_accumulator = get_global_volume_accumulator_pda().unwrap();
+        let accounts = vec![
+            solana_sdk::instruction::AccountMeta::new_readonly(*user, false), // user (readonly)
+            solana_sdk::instruction::AccountMeta::new(
+                spl_associated_token_account::get_associated_token_address_with_program_id(
+                    user,
+                    mint,
+                    token_program,
+                ),
+                false,
+            ), // user_ata
+            solana_sdk::instruction::AccountMeta::new_readonly(global_volume_accumulator, false), // global_volume_accumulator (readonly)
+            solana_sdk::instruction::AccountMeta::new(
+                spl_associated_token_account::get_associated_token_address_with_program_id(
+                    &global_volume_accumulator,
+                    mint,
+                    token_program,
+                ),
+                false,
+            ), // global_incentive_token_account
+            solana_sdk::instruction::AccountMeta::new(
+                get_user_volume_accumulator_pda(user).unwrap(),
+                false,
+            ), // user_volume_accumulator
+            solana_sdk::instruction::AccountMeta::new_readonly(*mint, false), // mint (readonly)
+            solana_sdk::instruction::AccountMeta::new_readonly(*token_program, false), // token_program (readonly)
+            solana_sdk::instruction::AccountMeta::new_readonly(accounts::SYSTEM_PROGRAM, false), // System Program (readonly)
+            solana_sdk::instruction::AccountMeta::new_readonly(
+                accounts::ASSOCIATED_TOKEN_PROGRAM,
+                false,
+            ), // ASSOCIATED_TOKEN_PROGRAM_ID (readonly)
+            solana_sdk::instruction::AccountMeta::new_readonly(accounts::EVENT_AUTHORITY, false), // event_authority (readonly)
+            solana_sdk::instruction::AccountMeta::new_readonly(accounts::AMM_PROGRAM, false), // PUMP_AMM_PROGRAM_ID (readonly)
+            solana_sdk::instruction::AccountMeta::new(*payer, true), // payer (signer)
+        ];
+        Instruction {
+            program_id: accounts::AMM_PROGRAM,
+            accounts,
+            data: CLAIM_TOKEN_INCENTIVES_DISCRIMINATOR.to_vec(),
+        }
+    }
+
+    /// Build an init_user_volume_accumulator instruction creating the volume accumulator of
+    /// `user`, paid by `payer`
+    pub fn build_init_user_volume_accumulator_instruction(
+        &self,
+        payer: &Pubkey,
+        user: &Pubkey,
+    ) -> Instruction {
+        let accounts = vec![
+            solana_sdk::instruction::AccountMeta::new(*payer, true), // payer (signer)
+            solana_sdk::instruction::AccountMeta::new_readonly(*user, false), // user (readonly)
+            solana_sdk::instruction::AccountMeta::new(
+                get_user_volume_accumulator_pda(user).unwrap(),
+                false,
+            ), // user_volume_accumulator
+            solana_sdk::instruction::AccountMeta::new_readonly(accounts::SYSTEM_PROGRAM, false), // System Program (readonly)
+            solana_sdk::instruction::AccountMeta::new_readonly(accounts::EVENT_AUTHORITY, false), // event_authority (readonly)
+            solana_sdk::instruction::AccountMeta::new_readonly(accounts::AMM_PROGRAM, false), // PUMP_AMM_PROGRAM_ID (readonly)
+        ];
+        Instruction {
+            program_id: accounts::AMM_PROGRAM,
+            accounts,
+            data: INIT_USER_VOLUME_ACCUMULATOR_DISCRIMINATOR.to_vec(),
+        }
+    }
+
     /// Build buy instructions with provided account information
     async fn build_buy_instructions_with_accounts(
         &self,
@@ -110,6 +513,7 @@ impl PumpSwapInstructionBuilder {
         auto_handle_wsol: bool,
         base_token_program: Pubkey,
         quote_token_program: Pubkey,
+        fees: &PumpSwapFees,
     ) -> Result<Vec<Instruction>> {
         if base_mint != accounts::WSOL_TOKEN_ACCOUNT && quote_mint != accounts::WSOL_TOKEN_ACCOUNT {
             return Err(anyhow!("Invalid base mint and quote mint"));
@@ -132,6 +536,7 @@ impl PumpSwapInstructionBuilder {
                 pool_base_token_reserves,
                 pool_quote_token_reserves,
                 &creator,
+                fees,
             )
             .unwrap();
             // base_amount_out
@@ -145,6 +550,7 @@ impl PumpSwapInstructionBuilder {
                 pool_base_token_reserves,
                 pool_quote_token_reserves,
                 &creator,
+                fees,
             )
             .unwrap();
             // min_quote_amount_out
@@ -322,6 +728,7 @@ impl PumpSwapInstructionBuilder {
         auto_handle_wsol: bool,
         base_token_program: Pubkey,
         quote_token_program: Pubkey,
+        fees: &PumpSwapFees,
     ) -> Result<Vec<Instruction>> {
         if base_mint != accounts::WSOL_TOKEN_ACCOUNT && quote_mint != accounts::WSOL_TOKEN_ACCOUNT {
             return Err(anyhow!("Invalid base mint and quote mint"));
@@ -348,6 +755,7 @@ impl PumpSwapInstructionBuilder {
                 pool_base_token_reserves,
                 pool_quote_token_reserves,
                 &creator,
+                fees,
             )
             .unwrap();
             // base_amount_in
@@ -361,6 +769,7 @@ impl PumpSwapInstructionBuilder {
                 pool_base_token_reserves,
                 pool_quote_token_reserves,
                 &creator,
+                fees,
             )
             .unwrap();
             // max_quote_amount_in
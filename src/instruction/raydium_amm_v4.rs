@@ -6,16 +6,78 @@ use spl_token::instruction::close_account;
 
 use crate::{
     constants::{
-        raydium_amm_v4::{accounts, SWAP_BASE_IN_DISCRIMINATOR},
+        raydium_amm_v4::{accounts, SWAP_BASE_IN_DISCRIMINATOR, SWAP_BASE_OUT_DISCRIMINATOR},
         trade::trade::DEFAULT_SLIPPAGE,
     },
     trading::core::{
-        params::{BuyParams, RaydiumAmmV4Params, SellParams},
+        params::{BuyParams, RaydiumAmmV4Params, SellParams, SwapQuote},
         traits::InstructionBuilder,
     },
-    utils::calc::raydium_amm_v4::compute_swap_amount,
+    utils::calc::raydium_amm_v4::{compute_swap_amount, compute_swap_amount_base_out},
 };
 
+/// Quotes a swap spending `amount_in`, or when `exact_amount_out` is set a swap for exactly
+/// that output with `amount_in` as the input cap
+fn quote_swap(
+    protocol_params: &RaydiumAmmV4Params,
+    is_base_in: bool,
+    amount_in: u64,
+    slippage_basis_points: Option<u64>,
+) -> Result<SwapQuote> {
+    if let Some(amount_out) = protocol_params.exact_amount_out {
+        let required_amount_in = compute_swap_amount_base_out(
+            protocol_params.coin_reserve,
+            protocol_params.pc_reserve,
+            is_base_in,
+            amount_out,
+        )
+        .ok_or_else(|| anyhow!("Pool cannot pay out {}", amount_out))?;
+        if required_amount_in > amount_in {
+            return Err(anyhow!(
+                "Receiving {} needs {} in, above the {} cap",
+                amount_out,
+                required_amount_in,
+                amount_in
+            ));
+        }
+        return Ok(SwapQuote {
+            amount_in: required_amount_in,
+            max_amount_in: amount_in,
+            amount_out,
+            min_amount_out: amount_out,
+        });
+    }
+
+    let swap_result = compute_swap_amount(
+        protocol_params.coin_reserve,
+        protocol_params.pc_reserve,
+        is_base_in,
+        amount_in,
+        slippage_basis_points.unwrap_or(DEFAULT_SLIPPAGE),
+    );
+    Ok(SwapQuote {
+        amount_in,
+        max_amount_in: amount_in,
+        amount_out: swap_result.amount_out,
+        min_amount_out: swap_result.min_amount_out,
+    })
+}
+
+/// Instruction data of `swap_base_in`, or of `swap_base_out` when `exact_amount_out` is set
+fn swap_data(protocol_params: &RaydiumAmmV4Params, quote: &SwapQuote) -> Vec<u8> {
+    let mut data = vec![];
+    if protocol_params.exact_amount_out.is_some() {
+        data.extend_from_slice(SWAP_BASE_OUT_DISCRIMINATOR);
+        data.extend_from_slice(&quote.max_amount_in.to_le_bytes());
+        data.extend_from_slice(&quote.amount_out.to_le_bytes());
+    } else {
+        data.extend_from_slice(SWAP_BASE_IN_DISCRIMINATOR);
+        data.extend_from_slice(&quote.amount_in.to_le_bytes());
+        data.extend_from_slice(&quote.min_amount_out.to_le_bytes());
+    }
+    data
+}
+
 /// Instruction builder for RaydiumCpmm protocol
 pub struct RaydiumAmmV4InstructionBuilder;
 
@@ -31,6 +93,29 @@ impl InstructionBuilder for RaydiumAmmV4InstructionBuilder {
     async fn build_sell_instructions(&self, params: &SellParams) -> Result<Vec<Instruction>> {
         self.build_sell_instructions_with_accounts(params).await
     }
+
+    async fn quote_buy(&self, params: &BuyParams) -> Result<SwapQuote> {
+        let protocol_params = params
+            .protocol_params
+            .as_any()
+            .downcast_ref::<RaydiumAmmV4Params>()
+            .ok_or_else(|| anyhow!("Invalid protocol params for RaydiumAmmV4"))?;
+
+        let is_base_in = protocol_params.coin_mint == accounts::WSOL_TOKEN_ACCOUNT;
+        quote_swap(protocol_params, is_base_in, params.sol_amount, params.slippage_basis_points)
+    }
+
+    async fn quote_sell(&self, params: &SellParams) -> Result<SwapQuote> {
+        let protocol_params = params
+            .protocol_params
+            .as_any()
+            .downcast_ref::<RaydiumAmmV4Params>()
+            .ok_or_else(|| anyhow!("Invalid protocol params for RaydiumAmmV4"))?;
+
+        let token_amount = params.token_amount.unwrap_or(0);
+        let is_base_in = protocol_params.pc_mint == accounts::WSOL_TOKEN_ACCOUNT;
+        quote_swap(protocol_params, is_base_in, token_amount, params.slippage_basis_points)
+    }
 }
 
 impl RaydiumAmmV4InstructionBuilder {
@@ -53,14 +138,8 @@ impl RaydiumAmmV4InstructionBuilder {
         let is_base_in = protocol_params.coin_mint == accounts::WSOL_TOKEN_ACCOUNT;
 
         let amount_in: u64 = params.sol_amount;
-        let swap_result = compute_swap_amount(
-            protocol_params.coin_reserve,
-            protocol_params.pc_reserve,
-            is_base_in,
-            amount_in,
-            params.slippage_basis_points.unwrap_or(DEFAULT_SLIPPAGE),
-        );
-        let minimum_amount_out = swap_result.min_amount_out;
+        let quote =
+            quote_swap(protocol_params, is_base_in, amount_in, params.slippage_basis_points)?;
 
         let mut instructions = vec![];
 
@@ -125,10 +204,7 @@ impl RaydiumAmmV4InstructionBuilder {
             solana_sdk::instruction::AccountMeta::new(params.payer.pubkey(), true), // User Source Owner
         ];
         // Create instruction data
-        let mut data = vec![];
-        data.extend_from_slice(&SWAP_BASE_IN_DISCRIMINATOR);
-        data.extend_from_slice(&amount_in.to_le_bytes());
-        data.extend_from_slice(&minimum_amount_out.to_le_bytes());
+        let data = swap_data(protocol_params, &quote);
 
         instructions.push(Instruction { program_id: accounts::RAYDIUM_AMM_V4, accounts, data });
 
@@ -170,14 +246,12 @@ impl RaydiumAmmV4InstructionBuilder {
         );
 
         let is_base_in = protocol_params.pc_mint == accounts::WSOL_TOKEN_ACCOUNT;
-        let swap_result = compute_swap_amount(
-            protocol_params.coin_reserve,
-            protocol_params.pc_reserve,
+        let quote = quote_swap(
+            protocol_params,
             is_base_in,
             params.token_amount.unwrap_or(0),
-            params.slippage_basis_points.unwrap_or(DEFAULT_SLIPPAGE),
-        );
-        let minimum_amount_out = swap_result.min_amount_out;
+            params.slippage_basis_points,
+        )?;
 
         let mut instructions = vec![];
 
@@ -223,10 +297,7 @@ impl RaydiumAmmV4InstructionBuilder {
             solana_sdk::instruction::AccountMeta::new(params.payer.pubkey(), true), // User Source Owner
         ];
         // Create instruction data
-        let mut data = vec![];
-        data.extend_from_slice(&SWAP_BASE_IN_DISCRIMINATOR);
-        data.extend_from_slice(&params.token_amount.unwrap_or(0).to_le_bytes());
-        data.extend_from_slice(&minimum_amount_out.to_le_bytes());
+        let data = swap_data(protocol_params, &quote);
 
         instructions.push(Instruction { program_id: accounts::RAYDIUM_AMM_V4, accounts, data });
 
@@ -1,22 +1,34 @@
 use anyhow::{anyhow, Result};
 use solana_sdk::{instruction::Instruction, signer::Signer};
+use solana_streamer_sdk::streaming::event_parser::protocols::raydium_cpmm::types::PoolState;
 use solana_system_interface::instruction::transfer;
 use spl_associated_token_account::instruction::create_associated_token_account_idempotent;
 use spl_token::instruction::close_account;
 
 use crate::{
     constants::{
-        raydium_cpmm::{accounts, SWAP_BASE_IN_DISCRIMINATOR},
+        raydium_cpmm::{
+            accounts, DEPOSIT_DISCRIMINATOR, INITIALIZE_DISCRIMINATOR, SWAP_BASE_IN_DISCRIMINATOR,
+            WITHDRAW_DISCRIMINATOR,
+        },
         trade::trade::DEFAULT_SLIPPAGE,
     },
     trading::{
         core::{
-            params::{BuyParams, RaydiumCpmmParams, SellParams},
+            params::{
+                BuyParams, RaydiumCpmmInitializeParams, RaydiumCpmmLiquidityParams,
+                RaydiumCpmmParams, SellParams, SwapQuote,
+            },
             traits::InstructionBuilder,
         },
-        raydium_cpmm::common::{get_observation_state_pda, get_pool_pda, get_vault_pda},
+        raydium_cpmm::common::{
+            get_lp_mint_pda, get_observation_state_pda, get_pool_pda, get_vault_pda,
+        },
+    },
+    utils::calc::raydium_cpmm::{
+        compute_deposit_amount, compute_swap_amount, compute_withdraw_amount,
+        is_creator_fee_on_input,
     },
-    utils::calc::raydium_cpmm::compute_swap_amount,
 };
 
 /// Instruction builder for RaydiumCpmm protocol
@@ -34,9 +46,384 @@ impl InstructionBuilder for RaydiumCpmmInstructionBuilder {
     async fn build_sell_instructions(&self, params: &SellParams) -> Result<Vec<Instruction>> {
         self.build_sell_instructions_with_accounts(params).await
     }
+
+    async fn quote_buy(&self, params: &BuyParams) -> Result<SwapQuote> {
+        let protocol_params = params
+            .protocol_params
+            .as_any()
+            .downcast_ref::<RaydiumCpmmParams>()
+            .ok_or_else(|| anyhow!("Invalid protocol params for RaydiumCpmm"))?;
+
+        let is_base_in = protocol_params.base_mint == accounts::WSOL_TOKEN_ACCOUNT;
+        let result = compute_swap_amount(
+            protocol_params.base_reserve,
+            protocol_params.quote_reserve,
+            is_base_in,
+            params.sol_amount,
+            params.slippage_basis_points.unwrap_or(DEFAULT_SLIPPAGE),
+            &protocol_params.fee_rates(),
+            is_creator_fee_on_input(protocol_params.creator_fee_on, is_base_in),
+        );
+        Ok(SwapQuote {
+            amount_in: params.sol_amount,
+            max_amount_in: params.sol_amount,
+            amount_out: result.amount_out,
+            min_amount_out: result.min_amount_out,
+        })
+    }
+
+    async fn quote_sell(&self, params: &SellParams) -> Result<SwapQuote> {
+        let protocol_params = params
+            .protocol_params
+            .as_any()
+            .downcast_ref::<RaydiumCpmmParams>()
+            .ok_or_else(|| anyhow!("Invalid protocol params for RaydiumCpmm"))?;
+
+        let token_amount = params.token_amount.unwrap_or(0);
+        let is_base_in = protocol_params.base_mint == params.mint;
+        let result = compute_swap_amount(
+            protocol_params.base_reserve,
+            protocol_params.quote_reserve,
+            is_base_in,
+            token_amount,
+            params.slippage_basis_points.unwrap_or(DEFAULT_SLIPPAGE),
+            &protocol_params.fee_rates(),
+            is_creator_fee_on_input(protocol_params.creator_fee_on, is_base_in),
+        );
+        Ok(SwapQuote {
+            amount_in: token_amount,
+            max_amount_in: token_amount,
+            amount_out: result.amount_out,
+            min_amount_out: result.min_amount_out,
+        })
+    }
 }
 
 impl RaydiumCpmmInstructionBuilder {
+    /// Build initialize instructions
+    ///
+    /// Sorts the mints into token0/token1, derives the pool, LP mint, vault and observation
+    /// addresses, wraps the WSOL side of the deposit when `auto_handle_wsol` is set, and returns
+    /// the instructions together with `RaydiumCpmmParams` describing the new pool.
+    pub fn build_initialize_instructions(
+        &self,
+        params: &RaydiumCpmmInitializeParams,
+    ) -> Result<(Vec<Instruction>, RaydiumCpmmParams)> {
+        if params.init_amount_0 == 0 || params.init_amount_1 == 0 {
+            return Err(anyhow!("Initial deposit amounts cannot be zero"));
+        }
+        if params.mint_0 == params.mint_1 {
+            return Err(anyhow!("Token mints must be different"));
+        }
+
+        // The program requires token0 < token1
+        let (
+            token_0_mint,
+            token_0_program,
+            init_amount_0,
+            token_1_mint,
+            token_1_program,
+            init_amount_1,
+        ) = if params.mint_0 < params.mint_1 {
+            (
+                params.mint_0,
+                params.token_0_program,
+                params.init_amount_0,
+                params.mint_1,
+                params.token_1_program,
+                params.init_amount_1,
+            )
+        } else {
+            (
+                params.mint_1,
+                params.token_1_program,
+                params.init_amount_1,
+                params.mint_0,
+                params.token_0_program,
+                params.init_amount_0,
+            )
+        };
+
+        let creator = params.payer.pubkey();
+        let pool_state = get_pool_pda(&params.amm_config, &token_0_mint, &token_1_mint)
+            .ok_or_else(|| anyhow!("Failed to derive pool state address"))?;
+        let lp_mint = get_lp_mint_pda(&pool_state)
+            .ok_or_else(|| anyhow!("Failed to derive LP mint address"))?;
+        let token_0_vault = get_vault_pda(&pool_state, &token_0_mint)
+            .ok_or_else(|| anyhow!("Failed to derive token0 vault address"))?;
+        let token_1_vault = get_vault_pda(&pool_state, &token_1_mint)
+            .ok_or_else(|| anyhow!("Failed to derive token1 vault address"))?;
+        let observation_state = get_observation_state_pda(&pool_state)
+            .ok_or_else(|| anyhow!("Failed to derive observation state address"))?;
+
+        let creator_token_0 =
+            spl_associated_token_account::get_associated_token_address_with_program_id(
+                &creator,
+                &token_0_mint,
+                &token_0_program,
+            );
+        let creator_token_1 =
+            spl_associated_token_account::get_associated_token_address_with_program_id(
+                &creator,
+                &token_1_mint,
+                &token_1_program,
+            );
+        let creator_lp_token =
+            spl_associated_token_account::get_associated_token_address_with_program_id(
+                &creator,
+                &lp_mint,
+                &accounts::TOKEN_PROGRAM,
+            );
+
+        // Which side of the deposit, if any, has to be wrapped from SOL
+        let wsol_deposit = if token_0_mint == accounts::WSOL_TOKEN_ACCOUNT {
+            Some((creator_token_0, init_amount_0))
+        } else if token_1_mint == accounts::WSOL_TOKEN_ACCOUNT {
+            Some((creator_token_1, init_amount_1))
+        } else {
+            None
+        };
+
+        let mut instructions = vec![];
+
+        if params.auto_handle_wsol {
+            if let Some((wsol_token_account, wsol_amount)) = wsol_deposit {
+                instructions.extend(wrap_wsol_instructions(
+                    &creator,
+                    &wsol_token_account,
+                    wsol_amount,
+                ));
+            }
+        }
+
+        // Create initialize instruction
+        let accounts = vec![
+            solana_sdk::instruction::AccountMeta::new(creator, true), // Creator (signer)
+            solana_sdk::instruction::AccountMeta::new_readonly(params.amm_config, false), // Amm Config (readonly)
+            solana_sdk::instruction::AccountMeta::new_readonly(accounts::AUTHORITY, false), // Authority (readonly)
+            solana_sdk::instruction::AccountMeta::new(pool_state, false), // Pool State
+            solana_sdk::instruction::AccountMeta::new_readonly(token_0_mint, false), // Token0 Mint (readonly)
+            solana_sdk::instruction::AccountMeta::new_readonly(token_1_mint, false), // Token1 Mint (readonly)
+            solana_sdk::instruction::AccountMeta::new(lp_mint, false),               // LP Mint
+            solana_sdk::instruction::AccountMeta::new(creator_token_0, false), // Creator Token0 Account
+            solana_sdk::instruction::AccountMeta::new(creator_token_1, false), // Creator Token1 Account
+            solana_sdk::instruction::AccountMeta::new(creator_lp_token, false), // Creator LP Token Account
+            solana_sdk::instruction::AccountMeta::new(token_0_vault, false),    // Token0 Vault
+            solana_sdk::instruction::AccountMeta::new(token_1_vault, false),    // Token1 Vault
+            solana_sdk::instruction::AccountMeta::new(accounts::CREATE_POOL_FEE_RECEIVER, false), // Create Pool Fee Receiver
+            solana_sdk::instruction::AccountMeta::new(observation_state, false), // Observation State Account
+            solana_sdk::instruction::AccountMeta::new_readonly(accounts::TOKEN_PROGRAM, false), // Token Program (readonly)
+            solana_sdk::instruction::AccountMeta::new_readonly(token_0_program, false), // Token0 Program (readonly)
+            solana_sdk::instruction::AccountMeta::new_readonly(token_1_program, false), // Token1 Program (readonly)
+            solana_sdk::instruction::AccountMeta::new_readonly(
+                accounts::ASSOCIATED_TOKEN_PROGRAM,
+                false,
+            ), // Associated Token Program (readonly)
+            solana_sdk::instruction::AccountMeta::new_readonly(accounts::SYSTEM_PROGRAM, false), // System Program (readonly)
+            solana_sdk::instruction::AccountMeta::new_readonly(accounts::RENT, false), // Rent Sysvar (readonly)
+        ];
+        // Create instruction data
+        let mut data = Vec::with_capacity(8 + 8 + 8 + 8);
+        data.extend_from_slice(INITIALIZE_DISCRIMINATOR);
+        data.extend_from_slice(&init_amount_0.to_le_bytes());
+        data.extend_from_slice(&init_amount_1.to_le_bytes());
+        data.extend_from_slice(&params.open_time.to_le_bytes());
+
+        instructions.push(Instruction { program_id: accounts::RAYDIUM_CPMM, accounts, data });
+
+        if params.auto_handle_wsol {
+            if let Some((wsol_token_account, _)) = wsol_deposit {
+                instructions.push(close_wsol_instruction(&creator, &wsol_token_account));
+            }
+        }
+
+        let fee_rates = params.fee_rates;
+        let pool_params = RaydiumCpmmParams {
+            base_mint: token_0_mint,
+            quote_mint: token_1_mint,
+            base_reserve: init_amount_0,
+            quote_reserve: init_amount_1,
+            base_token_program: token_0_program,
+            quote_token_program: token_1_program,
+            amm_config: params.amm_config,
+            trade_fee_rate: fee_rates.trade_fee_rate,
+            protocol_fee_rate: fee_rates.protocol_fee_rate,
+            fund_fee_rate: fee_rates.fund_fee_rate,
+            creator_fee_rate: fee_rates.creator_fee_rate,
+            creator_fee_on: 0,
+            auto_handle_wsol: params.auto_handle_wsol,
+        };
+
+        Ok((instructions, pool_params))
+    }
+
+    /// Build deposit instructions
+    ///
+    /// Mints `lp_token_amount` LP tokens. The maximum token amounts are derived from the pool
+    /// reserves rounded up, plus Token-2022 transfer fees and slippage.
+    pub fn build_deposit_instructions(
+        &self,
+        params: &RaydiumCpmmLiquidityParams,
+    ) -> Result<Vec<Instruction>> {
+        if params.lp_token_amount == 0 {
+            return Err(anyhow!("LP token amount cannot be zero"));
+        }
+        let pool = &params.pool;
+        let amounts = compute_deposit_amount(
+            params.lp_token_amount,
+            pool.lp_supply,
+            params.token_0_vault_amount,
+            params.token_1_vault_amount,
+            params.token_0_transfer_fee.as_ref(),
+            params.token_1_transfer_fee.as_ref(),
+            params.slippage_basis_points.unwrap_or(DEFAULT_SLIPPAGE),
+        )
+        .ok_or_else(|| anyhow!("Failed to compute deposit amounts"))?;
+
+        let owner = params.payer.pubkey();
+        let (owner_token_0, owner_token_1, owner_lp_token) = owner_liquidity_accounts(&owner, pool);
+
+        // Which side of the deposit, if any, has to be wrapped from SOL
+        let wsol_deposit = if pool.token0_mint == accounts::WSOL_TOKEN_ACCOUNT {
+            Some((owner_token_0, amounts.token_0_limit))
+        } else if pool.token1_mint == accounts::WSOL_TOKEN_ACCOUNT {
+            Some((owner_token_1, amounts.token_1_limit))
+        } else {
+            None
+        };
+
+        let mut instructions = vec![];
+
+        if params.auto_handle_wsol {
+            if let Some((wsol_token_account, wsol_amount)) = wsol_deposit {
+                instructions.extend(wrap_wsol_instructions(
+                    &owner,
+                    &wsol_token_account,
+                    wsol_amount,
+                ));
+            }
+        }
+
+        instructions.push(create_associated_token_account_idempotent(
+            &owner,
+            &owner,
+            &pool.lp_mint,
+            &accounts::TOKEN_PROGRAM,
+        ));
+
+        // Create deposit instruction
+        let accounts = vec![
+            solana_sdk::instruction::AccountMeta::new_readonly(owner, true), // Owner (signer)
+            solana_sdk::instruction::AccountMeta::new_readonly(accounts::AUTHORITY, false), // Authority (readonly)
+            solana_sdk::instruction::AccountMeta::new(params.pool_state, false), // Pool State
+            solana_sdk::instruction::AccountMeta::new(owner_lp_token, false), // Owner LP Token Account
+            solana_sdk::instruction::AccountMeta::new(owner_token_0, false),  // Token0 Account
+            solana_sdk::instruction::AccountMeta::new(owner_token_1, false),  // Token1 Account
+            solana_sdk::instruction::AccountMeta::new(pool.token0_vault, false), // Token0 Vault
+            solana_sdk::instruction::AccountMeta::new(pool.token1_vault, false), // Token1 Vault
+            solana_sdk::instruction::AccountMeta::new_readonly(accounts::TOKEN_PROGRAM, false), // Token Program (readonly)
+            solana_sdk::instruction::AccountMeta::new_readonly(accounts::TOKEN_PROGRAM_2022, false), // Token Program 2022 (readonly)
+            solana_sdk::instruction::AccountMeta::new_readonly(pool.token0_mint, false), // Vault0 Mint (readonly)
+            solana_sdk::instruction::AccountMeta::new_readonly(pool.token1_mint, false), // Vault1 Mint (readonly)
+            solana_sdk::instruction::AccountMeta::new(pool.lp_mint, false),              // LP Mint
+        ];
+        // Create instruction data
+        let mut data = Vec::with_capacity(8 + 8 + 8 + 8);
+        data.extend_from_slice(DEPOSIT_DISCRIMINATOR);
+        data.extend_from_slice(&params.lp_token_amount.to_le_bytes());
+        data.extend_from_slice(&amounts.token_0_limit.to_le_bytes());
+        data.extend_from_slice(&amounts.token_1_limit.to_le_bytes());
+
+        instructions.push(Instruction { program_id: accounts::RAYDIUM_CPMM, accounts, data });
+
+        if params.auto_handle_wsol {
+            if let Some((wsol_token_account, _)) = wsol_deposit {
+                instructions.push(close_wsol_instruction(&owner, &wsol_token_account));
+            }
+        }
+
+        Ok(instructions)
+    }
+
+    /// Build withdraw instructions
+    ///
+    /// Burns `lp_token_amount` LP tokens. The minimum token amounts are derived from the pool
+    /// reserves rounded down, minus Token-2022 transfer fees and slippage.
+    pub fn build_withdraw_instructions(
+        &self,
+        params: &RaydiumCpmmLiquidityParams,
+    ) -> Result<Vec<Instruction>> {
+        if params.lp_token_amount == 0 {
+            return Err(anyhow!("LP token amount cannot be zero"));
+        }
+        let pool = &params.pool;
+        let amounts = compute_withdraw_amount(
+            params.lp_token_amount,
+            pool.lp_supply,
+            params.token_0_vault_amount,
+            params.token_1_vault_amount,
+            params.token_0_transfer_fee.as_ref(),
+            params.token_1_transfer_fee.as_ref(),
+            params.slippage_basis_points.unwrap_or(DEFAULT_SLIPPAGE),
+        )
+        .ok_or_else(|| anyhow!("Failed to compute withdraw amounts"))?;
+
+        let owner = params.payer.pubkey();
+        let (owner_token_0, owner_token_1, owner_lp_token) = owner_liquidity_accounts(&owner, pool);
+
+        let mut instructions = vec![
+            create_associated_token_account_idempotent(
+                &owner,
+                &owner,
+                &pool.token0_mint,
+                &pool.token0_program,
+            ),
+            create_associated_token_account_idempotent(
+                &owner,
+                &owner,
+                &pool.token1_mint,
+                &pool.token1_program,
+            ),
+        ];
+
+        // Create withdraw instruction
+        let accounts = vec![
+            solana_sdk::instruction::AccountMeta::new_readonly(owner, true), // Owner (signer)
+            solana_sdk::instruction::AccountMeta::new_readonly(accounts::AUTHORITY, false), // Authority (readonly)
+            solana_sdk::instruction::AccountMeta::new(params.pool_state, false), // Pool State
+            solana_sdk::instruction::AccountMeta::new(owner_lp_token, false), // Owner LP Token Account
+            solana_sdk::instruction::AccountMeta::new(owner_token_0, false),  // Token0 Account
+            solana_sdk::instruction::AccountMeta::new(owner_token_1, false),  // Token1 Account
+            solana_sdk::instruction::AccountMeta::new(pool.token0_vault, false), // Token0 Vault
+            solana_sdk::instruction::AccountMeta::new(pool.token1_vault, false), // Token1 Vault
+            solana_sdk::instruction::AccountMeta::new_readonly(accounts::TOKEN_PROGRAM, false), // Token Program (readonly)
+            solana_sdk::instruction::AccountMeta::new_readonly(accounts::TOKEN_PROGRAM_2022, false), // Token Program 2022 (readonly)
+            solana_sdk::instruction::AccountMeta::new_readonly(pool.token0_mint, false), // Vault0 Mint (readonly)
+            solana_sdk::instruction::AccountMeta::new_readonly(pool.token1_mint, false), // Vault1 Mint (readonly)
+            solana_sdk::instruction::AccountMeta::new(pool.lp_mint, false),              // LP Mint
+            solana_sdk::instruction::AccountMeta::new_readonly(accounts::MEMO_PROGRAM, false), // Memo Program (readonly)
+        ];
+        // Create instruction data
+        let mut data = Vec::with_capacity(8 + 8 + 8 + 8);
+        data.extend_from_slice(WITHDRAW_DISCRIMINATOR);
+        data.extend_from_slice(&params.lp_token_amount.to_le_bytes());
+        data.extend_from_slice(&amounts.token_0_limit.to_le_bytes());
+        data.extend_from_slice(&amounts.token_1_limit.to_le_bytes());
+
+        instructions.push(Instruction { program_id: accounts::RAYDIUM_CPMM, accounts, data });
+
+        if params.auto_handle_wsol {
+            // Unwrap the WSOL side of the withdrawal
+            if pool.token0_mint == accounts::WSOL_TOKEN_ACCOUNT {
+                instructions.push(close_wsol_instruction(&owner, &owner_token_0));
+            } else if pool.token1_mint == accounts::WSOL_TOKEN_ACCOUNT {
+                instructions.push(close_wsol_instruction(&owner, &owner_token_1));
+            }
+        }
+
+        Ok(instructions)
+    }
+
     /// Build buy instructions with provided account information
     async fn build_buy_instructions_with_accounts(
         &self,
@@ -49,7 +436,7 @@ impl RaydiumCpmmInstructionBuilder {
             .ok_or_else(|| anyhow!("Invalid protocol params for RaydiumCpmm"))?;
 
         let pool_state = get_pool_pda(
-            &accounts::AMM_CONFIG,
+            &protocol_params.amm_config,
             &protocol_params.base_mint,
             &protocol_params.quote_mint,
         )
@@ -86,6 +473,8 @@ impl RaydiumCpmmInstructionBuilder {
             is_base_in,
             amount_in,
             params.slippage_basis_points.unwrap_or(DEFAULT_SLIPPAGE),
+            &protocol_params.fee_rates(),
+            is_creator_fee_on_input(protocol_params.creator_fee_on, is_base_in),
         );
         let minimum_amount_out = result.min_amount_out;
 
@@ -125,7 +514,7 @@ impl RaydiumCpmmInstructionBuilder {
         let accounts = vec![
             solana_sdk::instruction::AccountMeta::new(params.payer.pubkey(), true), // Payer (signer)
             solana_sdk::instruction::AccountMeta::new_readonly(accounts::AUTHORITY, false), // Authority (readonly)
-            solana_sdk::instruction::AccountMeta::new_readonly(accounts::AMM_CONFIG, false), // Amm Config (readonly)
+            solana_sdk::instruction::AccountMeta::new_readonly(protocol_params.amm_config, false), // Amm Config (readonly)
             solana_sdk::instruction::AccountMeta::new(pool_state, false), // Pool State
             solana_sdk::instruction::AccountMeta::new(wsol_token_account, false), // Input Token Account
             solana_sdk::instruction::AccountMeta::new(mint_token_account, false), // Output Token Account
@@ -190,11 +579,13 @@ impl RaydiumCpmmInstructionBuilder {
             is_base_in,
             params.token_amount.unwrap_or(0),
             params.slippage_basis_points.unwrap_or(DEFAULT_SLIPPAGE),
+            &protocol_params.fee_rates(),
+            is_creator_fee_on_input(protocol_params.creator_fee_on, is_base_in),
         )
         .min_amount_out;
 
         let pool_state = get_pool_pda(
-            &accounts::AMM_CONFIG,
+            &protocol_params.amm_config,
             &protocol_params.base_mint,
             &protocol_params.quote_mint,
         )
@@ -234,7 +625,7 @@ impl RaydiumCpmmInstructionBuilder {
         let accounts = vec![
             solana_sdk::instruction::AccountMeta::new(params.payer.pubkey(), true), // Payer (signer)
             solana_sdk::instruction::AccountMeta::new_readonly(accounts::AUTHORITY, false), // Authority (readonly)
-            solana_sdk::instruction::AccountMeta::new_readonly(accounts::AMM_CONFIG, false), // Amm Config (readonly)
+            solana_sdk::instruction::AccountMeta::new_readonly(protocol_params.amm_config, false), // Amm Config (readonly)
             solana_sdk::instruction::AccountMeta::new(pool_state, false), // Pool State
             solana_sdk::instruction::AccountMeta::new(mint_token_account, false), // Input Token Account
             solana_sdk::instruction::AccountMeta::new(wsol_token_account, false), // Output Token Account
@@ -270,3 +661,52 @@ impl RaydiumCpmmInstructionBuilder {
         Ok(instructions)
     }
 }
+
+/// Owner token0, token1 and LP token accounts for a pool
+fn owner_liquidity_accounts(
+    owner: &solana_sdk::pubkey::Pubkey,
+    pool: &PoolState,
+) -> (solana_sdk::pubkey::Pubkey, solana_sdk::pubkey::Pubkey, solana_sdk::pubkey::Pubkey) {
+    let owner_token_0 = spl_associated_token_account::get_associated_token_address_with_program_id(
+        owner,
+        &pool.token0_mint,
+        &pool.token0_program,
+    );
+    let owner_token_1 = spl_associated_token_account::get_associated_token_address_with_program_id(
+        owner,
+        &pool.token1_mint,
+        &pool.token1_program,
+    );
+    let owner_lp_token = spl_associated_token_account::get_associated_token_address_with_program_id(
+        owner,
+        &pool.lp_mint,
+        &accounts::TOKEN_PROGRAM,
+    );
+    (owner_token_0, owner_token_1, owner_lp_token)
+}
+
+/// Create the wSOL ATA if needed, transfer SOL into it and sync its balance
+fn wrap_wsol_instructions(
+    owner: &solana_sdk::pubkey::Pubkey,
+    wsol_token_account: &solana_sdk::pubkey::Pubkey,
+    amount: u64,
+) -> Vec<Instruction> {
+    vec![
+        create_associated_token_account_idempotent(
+            owner,
+            owner,
+            &accounts::WSOL_TOKEN_ACCOUNT,
+            &accounts::TOKEN_PROGRAM,
+        ),
+        transfer(owner, wsol_token_account, amount),
+        spl_token::instruction::sync_native(&accounts::TOKEN_PROGRAM, wsol_token_account).unwrap(),
+    ]
+}
+
+/// Close the wSOL ATA, reclaim rent and unwrap the remaining balance
+fn close_wsol_instruction(
+    owner: &solana_sdk::pubkey::Pubkey,
+    wsol_token_account: &solana_sdk::pubkey::Pubkey,
+) -> Instruction {
+    close_account(&accounts::TOKEN_PROGRAM, wsol_token_account, owner, owner, &[owner]).unwrap()
+}
@@ -7,26 +7,76 @@ pub mod trading;
 pub mod utils;
 pub use solana_streamer_sdk;
 
+use crate::instruction::pumpswap::PumpSwapInstructionBuilder;
+use crate::instruction::raydium_cpmm::RaydiumCpmmInstructionBuilder;
+use crate::swqos::confirmation::{ConfirmationConfig, ConfirmationTracker};
+use crate::swqos::health::{SwqosHealthConfig, SwqosHealthMonitor};
+use crate::swqos::yellowstone_landing::YellowstoneLandingStream;
 use crate::swqos::SwqosConfig;
+use crate::trading::common::build_rpc_transaction;
+use crate::swqos::TradeType;
+use crate::trading::core::arbitrage::{
+    build_arbitrage_instructions, ArbitrageDetector, ArbitrageOpportunity,
+};
+use crate::trading::core::bundle::BundleBuilder;
+use crate::trading::core::executor::MAX_LOADED_ACCOUNTS_DATA_SIZE_LIMIT;
+use crate::trading::core::parallel::parallel_execute_with_tips;
 use crate::trading::core::params::BonkParams;
 use crate::trading::core::params::PumpFunParams;
+use crate::trading::core::params::PumpSwapCreatePoolParams;
 use crate::trading::core::params::PumpSwapParams;
 use crate::trading::core::params::RaydiumAmmV4Params;
+use crate::trading::core::params::RaydiumCpmmInitializeParams;
+use crate::trading::core::params::RaydiumCpmmLiquidityParams;
 use crate::trading::core::params::RaydiumCpmmParams;
+use crate::trading::core::router::{
+    build_route_instructions, discover_pools, find_best_route, Route, RouteParams,
+};
+use crate::trading::core::swap_path::{
+    build_swap_path_instructions, SwapContext, SwapDirection, SwapLeg, SwapPath, SwapPathParams,
+    SwapPathQuote,
+};
 use crate::trading::core::traits::ProtocolParams;
 use crate::trading::factory::DexType;
 use crate::trading::BuyParams;
 use crate::trading::MiddlewareManager;
 use crate::trading::SellParams;
+use crate::trading::TradeOutcome;
 use crate::trading::TradeFactory;
+use crate::utils::{CreatorFees, UnclaimedIncentives};
+use common::compute_unit_resolver::{ComputeUnitResolver, ComputeUnitResolverConfig};
+use common::priority_fee_estimator::{PriorityFeeEstimator, PriorityFeeEstimatorConfig};
+use common::tip_strategy::TipStrategy;
 use common::{PriorityFee, SolanaRpcClient, TradeConfig};
 use rustls::crypto::{ring::default_provider, CryptoProvider};
 use solana_sdk::hash::Hash;
-use solana_sdk::{pubkey::Pubkey, signature::Keypair};
+use solana_sdk::{pubkey::Pubkey, signature::Keypair, signer::Signer};
 use std::sync::Arc;
 use std::sync::Mutex;
+use std::time::Duration;
 use swqos::SwqosClient;
 
+/// How often the PumpFun and PumpSwap fee accounts are re-read so fee tier changes take effect
+const FEE_ACCOUNTS_REFRESH_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Refresh the fee account caches every `FEE_ACCOUNTS_REFRESH_INTERVAL` until `rpc` is dropped
+fn spawn_fee_account_refresh(rpc: &Arc<SolanaRpcClient>) {
+    let rpc = Arc::downgrade(rpc);
+    tokio::spawn(async move {
+        loop {
+            let Some(rpc) = rpc.upgrade() else { break };
+            if let Err(e) = trading::pumpfun::common::refresh_fee_accounts(&rpc).await {
+                log::warn!("Failed to load PumpFun fee accounts, keeping cached fees: {}", e);
+            }
+            if let Err(e) = trading::pumpswap::common::refresh_fee_accounts(&rpc).await {
+                log::warn!("Failed to load PumpSwap fee accounts, keeping cached fees: {}", e);
+            }
+            drop(rpc);
+            tokio::time::sleep(FEE_ACCOUNTS_REFRESH_INTERVAL).await;
+        }
+    });
+}
+
 pub struct SolanaTrade {
     pub payer: Arc<Keypair>,
     pub rpc: Arc<SolanaRpcClient>,
@@ -34,6 +84,9 @@ pub struct SolanaTrade {
     pub priority_fee: PriorityFee,
     pub trade_config: TradeConfig,
     pub middleware_manager: Option<Arc<MiddlewareManager>>,
+    pub tip_strategy: Option<Arc<TipStrategy>>,
+    pub swqos_health: Option<Arc<SwqosHealthMonitor>>,
+    pub confirmation_tracker: Arc<ConfirmationTracker>,
 }
 
 static INSTANCE: Mutex<Option<Arc<SolanaTrade>>> = Mutex::new(None);
@@ -47,13 +100,24 @@ impl Clone for SolanaTrade {
             priority_fee: self.priority_fee.clone(),
             trade_config: self.trade_config.clone(),
             middleware_manager: self.middleware_manager.clone(),
+            tip_strategy: self.tip_strategy.clone(),
+            swqos_health: self.swqos_health.clone(),
+            confirmation_tracker: self.confirmation_tracker.clone(),
         }
     }
 }
 
 impl SolanaTrade {
+    /// Create the client and one SWQOS client per entry of `trade_config.swqos_configs`
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a SWQOS config carries a malformed auth keypair or API token.
     #[inline]
-    pub async fn new(payer: Arc<Keypair>, mut trade_config: TradeConfig) -> Self {
+    pub async fn new(
+        payer: Arc<Keypair>,
+        mut trade_config: TradeConfig,
+    ) -> Result<Self, anyhow::Error> {
         if CryptoProvider::get_default().is_none() {
             let _ = default_provider()
                 .install_default()
@@ -79,15 +143,23 @@ impl SolanaTrade {
             trade_config.priority_fee = priority_fee.clone();
         }
 
+        let rpc = Arc::new(SolanaRpcClient::new_with_commitment(rpc_url.clone(), commitment));
+        let confirmation_tracker =
+            Arc::new(ConfirmationTracker::new(rpc.clone(), ConfirmationConfig::default()));
+
         let mut swqos_clients: Vec<Arc<SwqosClient>> = vec![];
 
         for swqos in swqos_configs {
-            let swqos_client =
-                SwqosConfig::get_swqos_client(rpc_url.clone(), commitment.clone(), swqos.clone());
+            let swqos_client = SwqosConfig::get_swqos_client(
+                confirmation_tracker.clone(),
+                commitment.clone(),
+                swqos.clone(),
+            )?;
             swqos_clients.push(swqos_client);
         }
 
-        let rpc = Arc::new(SolanaRpcClient::new_with_commitment(rpc_url.clone(), commitment));
+        // Load the on-chain fee config cache and keep it fresh; trades fall back to constants until it is loaded
+        spawn_fee_account_refresh(&rpc);
 
         let instance = Self {
             payer,
@@ -96,12 +168,15 @@ impl SolanaTrade {
             priority_fee,
             trade_config: trade_config.clone(),
             middleware_manager: None,
+            tip_strategy: None,
+            swqos_health: None,
+            confirmation_tracker,
         };
 
         let mut current = INSTANCE.lock().unwrap();
         *current = Some(Arc::new(instance.clone()));
 
-        instance
+        Ok(instance)
     }
 
     pub fn with_middleware_manager(mut self, middleware_manager: MiddlewareManager) -> Self {
@@ -109,6 +184,97 @@ impl SolanaTrade {
         self
     }
 
+    /// Price compute units from recent prioritization fees of the accounts each transaction
+    /// writes, e.g. the bonding curve or pool vaults, instead of the fixed unit prices
+    ///
+    /// Prices are cached per account set and refreshed in the background, so sending never
+    /// waits on RPC; the fixed prices are used until an account set has been priced once.
+    /// The estimator belongs to this instance, other `SolanaTrade` instances are unaffected.
+    pub fn with_priority_fee_estimator(mut self, config: PriorityFeeEstimatorConfig) -> Self {
+        let estimator = Arc::new(PriorityFeeEstimator::new(self.rpc.clone(), config));
+        self.priority_fee.estimator = Some(estimator.clone());
+        self.trade_config.priority_fee.estimator = Some(estimator);
+        self
+    }
+
+    /// Size the compute unit limit of each transaction from a simulation instead of the fixed
+    /// `rpc_unit_limit` / `tip_unit_limit`
+    ///
+    /// Each shape (protocol, direction, whether the ATAs exist, whether SOL is wrapped) is
+    /// simulated once in the background and cached with a safety margin; the fixed limits are
+    /// used until a shape has been simulated once.
+    pub fn with_compute_unit_resolver(self, config: ComputeUnitResolverConfig) -> Self {
+        ComputeUnitResolver::get_instance().init(self.rpc.clone(), config);
+        self
+    }
+
+    /// Pick tips from the landed-tip distribution instead of the fixed `PriorityFee` tips
+    ///
+    /// The distribution is read from `TipCache`, keep it fresh with
+    /// `common::tip_strategy::spawn_tip_floor_updater`. Trades fall back to the fixed tips
+    /// while it is stale, and a custom tip passed to a trade always wins.
+    pub fn with_tip_strategy(mut self, tip_strategy: TipStrategy) -> Self {
+        self.tip_strategy = Some(Arc::new(tip_strategy));
+        self
+    }
+
+    /// Register a user-defined SWQOS client, e.g. a private relay, alongside the configured ones
+    ///
+    /// The client gets its tip account from its own `get_tip_account` and is tipped with
+    /// `buy_tip_fee` unless `buy_tip_fees` already has an entry for it. Return a
+    /// `SwqosType::Custom` from `get_swqos_type` so it is tipped like the other providers.
+    pub fn with_swqos_client(mut self, swqos_client: Arc<SwqosClient>) -> Self {
+        self.swqos_clients.push(swqos_client);
+        for priority_fee in [&mut self.priority_fee, &mut self.trade_config.priority_fee] {
+            while priority_fee.buy_tip_fees.len() < self.swqos_clients.len() {
+                priority_fee.buy_tip_fees.push(priority_fee.buy_tip_fee);
+            }
+        }
+        self
+    }
+
+    /// Probe every configured SWQOS provider in the background and leave the unhealthy ones
+    /// out of the fan-out until they recover
+    ///
+    /// A provider is unhealthy when its recent probe error rate or average RTT is over the
+    /// configured limits. Trades still go to every provider when none is healthy. To start on
+    /// the fastest region of each provider, pass the configs through
+    /// `swqos::health::select_best_regions` before building the `TradeConfig`.
+    pub fn with_swqos_health(mut self, config: SwqosHealthConfig) -> Self {
+        match SwqosHealthMonitor::new(&self.trade_config.swqos_configs, config) {
+            Ok(monitor) => {
+                let monitor = Arc::new(monitor);
+                monitor.start();
+                self.swqos_health = Some(monitor);
+            }
+            Err(e) => println!("Failed to start SWQOS health monitor: {}", e),
+        }
+        self
+    }
+
+    /// Configure how every SWQOS client waits for its transactions to land
+    ///
+    /// All pending signatures share one tracker that batches them into `getSignatureStatuses`
+    /// every `poll_interval`, optionally alongside websocket `signatureSubscribe`. A transaction
+    /// not seen within `timeout` fails, one already processed keeps waiting for `commitment`,
+    /// and one whose blockhash expired before landing fails right away.
+    pub fn with_confirmation_config(self, config: ConfirmationConfig) -> Self {
+        self.confirmation_tracker.configure(config);
+        self
+    }
+
+    /// Detect landed trades from a Yellowstone gRPC transaction stream of the payer's account
+    ///
+    /// Trades are resolved as soon as they show up at processed commitment, usually hundreds of
+    /// milliseconds before `getSignatureStatuses` reports them, without waiting for the configured
+    /// commitment. Polling keeps running as a fallback while the stream reconnects. Subscribe to
+    /// `confirmation_tracker.landings()` for the landed slot and result of every payer transaction.
+    pub fn with_yellowstone_landing(self, endpoint: String, x_token: Option<String>) -> Self {
+        YellowstoneLandingStream::new(endpoint, x_token, vec![self.payer.pubkey()])
+            .start(&self.confirmation_tracker);
+        self
+    }
+
     /// Get the RPC client instance
     pub fn get_rpc(&self) -> &Arc<SolanaRpcClient> {
         &self.rpc
@@ -139,7 +305,9 @@ impl SolanaTrade {
     ///
     /// # Returns
     ///
-    /// Returns `Ok(())` if the buy order is successfully executed, or an error if the transaction fails.
+    /// Returns the `TradeOutcome` if the buy order is successfully executed, listing the providers
+    /// skipped by their rate limit and the results of providers that send it as a Jito bundle,
+    /// or an error if the transaction fails.
     ///
     /// # Errors
     ///
@@ -159,35 +327,28 @@ impl SolanaTrade {
         extension_params: Box<dyn ProtocolParams>,
         lookup_table_key: Option<Pubkey>,
         wait_transaction_confirmed: bool,
-    ) -> Result<(), anyhow::Error> {
+    ) -> Result<TradeOutcome, anyhow::Error> {
         let executor = TradeFactory::create_executor(dex_type.clone());
         let protocol_params = extension_params;
 
         let final_lookup_table_key = lookup_table_key.or(self.trade_config.lookup_table_key);
 
-        let mut buy_params = BuyParams {
+        let (swqos_clients, priority_fee) =
+            self.healthy_swqos(self.trade_priority_fee(TradeType::Buy, custom_buy_tip_fee));
+        let buy_params = BuyParams {
             rpc: Some(self.rpc.clone()),
             payer: self.payer.clone(),
             mint: mint,
             sol_amount: sol_amount,
             slippage_basis_points: slippage_basis_points,
-            priority_fee: self.trade_config.priority_fee.clone(),
+            priority_fee,
             lookup_table_key: final_lookup_table_key,
             recent_blockhash,
             data_size_limit: 0,
             wait_transaction_confirmed: wait_transaction_confirmed,
             protocol_params: protocol_params.clone(),
         };
-        if custom_buy_tip_fee.is_some() {
-            buy_params.priority_fee.buy_tip_fee = custom_buy_tip_fee.unwrap();
-            buy_params.priority_fee.buy_tip_fees = buy_params
-                .priority_fee
-                .buy_tip_fees
-                .iter()
-                .map(|_| custom_buy_tip_fee.unwrap())
-                .collect();
-        }
-        let buy_with_tip_params = buy_params.clone().with_tip(self.swqos_clients.clone());
+        let buy_with_tip_params = buy_params.clone().with_tip(swqos_clients);
 
         // Validate protocol params
         let is_valid_params = match dex_type {
@@ -228,7 +389,9 @@ impl SolanaTrade {
     ///
     /// # Returns
     ///
-    /// Returns `Ok(())` if the sell order is successfully executed, or an error if the transaction fails.
+    /// Returns the `TradeOutcome` if the sell order is successfully executed, listing the providers
+    /// skipped by their rate limit and the results of providers that send it as a Jito bundle,
+    /// or an error if the transaction fails.
     ///
     /// # Errors
     ///
@@ -250,34 +413,27 @@ impl SolanaTrade {
         extension_params: Box<dyn ProtocolParams>,
         lookup_table_key: Option<Pubkey>,
         wait_transaction_confirmed: bool,
-    ) -> Result<(), anyhow::Error> {
+    ) -> Result<TradeOutcome, anyhow::Error> {
         let executor = TradeFactory::create_executor(dex_type.clone());
         let protocol_params = extension_params;
 
         let final_lookup_table_key = lookup_table_key.or(self.trade_config.lookup_table_key);
 
-        let mut sell_params = SellParams {
+        let (swqos_clients, priority_fee) =
+            self.healthy_swqos(self.trade_priority_fee(TradeType::Sell, custom_buy_tip_fee));
+        let sell_params = SellParams {
             rpc: Some(self.rpc.clone()),
             payer: self.payer.clone(),
             mint: mint,
             token_amount: Some(token_amount),
             slippage_basis_points: slippage_basis_points,
-            priority_fee: self.trade_config.priority_fee.clone(),
+            priority_fee,
             lookup_table_key: final_lookup_table_key,
             recent_blockhash,
             wait_transaction_confirmed: wait_transaction_confirmed,
             protocol_params: protocol_params.clone(),
         };
-        if custom_buy_tip_fee.is_some() {
-            sell_params.priority_fee.buy_tip_fee = custom_buy_tip_fee.unwrap();
-            sell_params.priority_fee.buy_tip_fees = sell_params
-                .priority_fee
-                .buy_tip_fees
-                .iter()
-                .map(|_| custom_buy_tip_fee.unwrap())
-                .collect();
-        }
-        let sell_with_tip_params = sell_params.clone().with_tip(self.swqos_clients.clone());
+        let sell_with_tip_params = sell_params.clone().with_tip(swqos_clients);
 
         // Validate protocol params
         let is_valid_params = match dex_type {
@@ -327,7 +483,9 @@ impl SolanaTrade {
     ///
     /// # Returns
     ///
-    /// Returns `Ok(())` if the sell order is successfully executed, or an error if the transaction fails.
+    /// Returns the `TradeOutcome` if the sell order is successfully executed, listing the providers
+    /// skipped by their rate limit and the results of providers that send it as a Jito bundle,
+    /// or an error if the transaction fails.
     ///
     /// # Errors
     ///
@@ -351,7 +509,7 @@ impl SolanaTrade {
         extension_params: Box<dyn ProtocolParams>,
         lookup_table_key: Option<Pubkey>,
         wait_transaction_confirmed: bool,
-    ) -> Result<(), anyhow::Error> {
+    ) -> Result<TradeOutcome, anyhow::Error> {
         if percent == 0 || percent > 100 {
             return Err(anyhow::anyhow!("Percentage must be between 1 and 100"));
         }
@@ -370,4 +528,662 @@ impl SolanaTrade {
         )
         .await
     }
+
+    /// Swap along a path of legs in a single transaction
+    ///
+    /// Every leg spends the previous leg's expected output, e.g. selling a Bonk token for SOL and
+    /// buying a PumpSwap token with it. The transaction pays one priority fee and one tip, the
+    /// sell tip when the path spends a token.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - Legs to execute in order, see `SwapPath`
+    /// * `amount_in` - Maximum amount of the path's input mint to spend (in smallest units)
+    /// * `slippage_basis_points` - Optional slippage tolerance, bounding the path end to end
+    /// * `min_amount_out` - Optional end-to-end minimum output of the last leg
+    /// * `recent_blockhash` - Recent blockhash for transaction validity
+    /// * `custom_buy_tip_fee` - Optional custom tip fee for priority processing (in SOL)
+    /// * `lookup_table_key` - Optional address lookup table key for transaction optimization
+    /// * `wait_transaction_confirmed` - Whether to wait for the transaction to be confirmed
+    ///
+    /// # Returns
+    ///
+    /// Returns the `SwapPathQuote` the transaction was built from and the `TradeOutcome`.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if:
+    /// - The path is empty or a leg does not spend the previous leg's output
+    /// - The last leg cannot guarantee `min_amount_out`
+    /// - Invalid protocol parameters are provided
+    /// - The transaction fails to execute
+    pub async fn swap_path(
+        &self,
+        path: SwapPath,
+        amount_in: u64,
+        slippage_basis_points: Option<u64>,
+        min_amount_out: Option<u64>,
+        recent_blockhash: Hash,
+        custom_buy_tip_fee: Option<f64>,
+        lookup_table_key: Option<Pubkey>,
+        wait_transaction_confirmed: bool,
+    ) -> Result<(SwapPathQuote, TradeOutcome), anyhow::Error> {
+        let direction = path.direction();
+        let context = self.swap_context(
+            direction,
+            slippage_basis_points,
+            recent_blockhash,
+            custom_buy_tip_fee,
+            lookup_table_key,
+            wait_transaction_confirmed,
+        );
+        let params = SwapPathParams { context, path, amount_in, min_amount_out };
+
+        let (instructions, quote) =
+            build_swap_path_instructions(&params, self.middleware_manager.clone()).await?;
+        let outcome =
+            self.send_swap_instructions(&params.context, direction, instructions, "SwapPath").await?;
+
+        Ok((quote, outcome))
+    }
+
+    /// Buy or sell a token through the pools giving the best output after fees
+    ///
+    /// The PumpFun bonding curve, the Bonk pool, the PumpSwap pool and the deepest Raydium
+    /// CPMM and AMM v4 pools paired with WSOL are found automatically; other pools can be
+    /// passed in `extra_pools`. With
+    /// `split_parts` above 1 the order is split across pools when that yields more output,
+    /// and every part is sent in one transaction. Sells pay the sell tip.
+    ///
+    /// # Arguments
+    ///
+    /// * `mint` - Token to buy or sell against SOL
+    /// * `direction` - `SwapDirection::Buy` spends SOL, `SwapDirection::Sell` spends tokens
+    /// * `amount_in` - Amount to spend (in smallest units)
+    /// * `split_parts` - Number of equal parts the order may be split into, 1 disables splitting
+    /// * `extra_pools` - Additional candidate pools for `mint` in the same direction
+    /// * `slippage_basis_points` - Optional slippage tolerance applied to every pool
+    /// * `recent_blockhash` - Recent blockhash for transaction validity
+    /// * `custom_buy_tip_fee` - Optional custom tip fee for priority processing (in SOL)
+    /// * `lookup_table_key` - Optional address lookup table key for transaction optimization
+    /// * `wait_transaction_confirmed` - Whether to wait for the transaction to be confirmed
+    ///
+    /// # Returns
+    ///
+    /// Returns the `Route` the transaction was built from and the `TradeOutcome`.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if:
+    /// - No pool is found or none can quote the order
+    /// - An extra pool trades another mint or direction
+    /// - The transaction fails to execute
+    pub async fn route_swap(
+        &self,
+        mint: Pubkey,
+        direction: SwapDirection,
+        amount_in: u64,
+        split_parts: u32,
+        extra_pools: Vec<SwapLeg>,
+        slippage_basis_points: Option<u64>,
+        recent_blockhash: Hash,
+        custom_buy_tip_fee: Option<f64>,
+        lookup_table_key: Option<Pubkey>,
+        wait_transaction_confirmed: bool,
+    ) -> Result<(Route, TradeOutcome), anyhow::Error> {
+        if let Some(pool) =
+            extra_pools.iter().find(|pool| pool.mint != mint || pool.direction != direction)
+        {
+            return Err(anyhow::anyhow!(
+                "Extra pool {} ({:?} {}) does not match the routed order",
+                pool.dex_type,
+                pool.direction,
+                pool.mint
+            ));
+        }
+        let mut pools = discover_pools(&self.rpc, &mint, direction).await;
+        pools.extend(extra_pools);
+
+        let context = self.swap_context(
+            direction,
+            slippage_basis_points,
+            recent_blockhash,
+            custom_buy_tip_fee,
+            lookup_table_key,
+            wait_transaction_confirmed,
+        );
+        let params = RouteParams { context, mint, direction, amount_in, split_parts };
+        let route = find_best_route(&params, &pools).await?;
+
+        let instructions =
+            build_route_instructions(&params.context, &route, self.middleware_manager.clone())
+                .await?;
+        let outcome =
+            self.send_swap_instructions(&params.context, direction, instructions, "Router").await?;
+
+        Ok((route, outcome))
+    }
+
+    /// Look for a price gap between the pools watched by `detector` and trade it atomically
+    ///
+    /// The transaction buys on the cheaper pool and sells on the more expensive one. The sell
+    /// leg's minimum output covers the buy leg's maximum spend, the priority fee, the tip and
+    /// the detector's `min_profit`, so the transaction fails instead of losing money.
+    ///
+    /// # Arguments
+    ///
+    /// * `detector` - Pools of one mint to compare, see `ArbitrageDetector`
+    /// * `slippage_basis_points` - Optional slippage tolerance for the buy leg
+    /// * `recent_blockhash` - Recent blockhash for transaction validity
+    /// * `custom_buy_tip_fee` - Optional custom tip fee for priority processing (in SOL)
+    /// * `lookup_table_key` - Optional address lookup table key for transaction optimization
+    /// * `wait_transaction_confirmed` - Whether to wait for the transaction to be confirmed
+    ///
+    /// # Returns
+    ///
+    /// Returns the executed `ArbitrageOpportunity` and the `TradeOutcome`, or `None` when no
+    /// pool pair is profitable after fees, tip and priority fee.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if:
+    /// - A pool cannot be quoted
+    /// - The transaction fails to execute
+    pub async fn arbitrage(
+        &self,
+        detector: &ArbitrageDetector,
+        slippage_basis_points: Option<u64>,
+        recent_blockhash: Hash,
+        custom_buy_tip_fee: Option<f64>,
+        lookup_table_key: Option<Pubkey>,
+        wait_transaction_confirmed: bool,
+    ) -> Result<Option<(ArbitrageOpportunity, TradeOutcome)>, anyhow::Error> {
+        // Arbitrage spends SOL on its buy leg first, so it is tipped as a buy
+        let context = self.swap_context(
+            SwapDirection::Buy,
+            slippage_basis_points,
+            recent_blockhash,
+            custom_buy_tip_fee,
+            lookup_table_key,
+            wait_transaction_confirmed,
+        );
+        let Some(opportunity) = detector.detect(&context).await? else {
+            return Ok(None);
+        };
+
+        let instructions =
+            build_arbitrage_instructions(&context, &opportunity, self.middleware_manager.clone())
+                .await?;
+        let outcome = self
+            .send_swap_instructions(&context, SwapDirection::Buy, instructions, "Arbitrage")
+            .await?;
+
+        Ok(Some((opportunity, outcome)))
+    }
+
+    /// Create a new PumpSwap pool with initial liquidity
+    ///
+    /// # Arguments
+    ///
+    /// * `create_pool_params` - Pool index, mints, initial deposits and coin creator
+    /// * `recent_blockhash` - Recent blockhash for transaction validity
+    /// * `wait_transaction_confirmed` - Whether to wait for the transaction to be confirmed
+    ///
+    /// # Returns
+    ///
+    /// Returns `PumpSwapParams` for the new pool, ready to be passed to `buy`/`sell`.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if:
+    /// - Either initial deposit is zero or both mints are the same
+    /// - The transaction fails to execute
+    /// - Network or RPC errors occur
+    pub async fn create_pumpswap_pool(
+        &self,
+        create_pool_params: PumpSwapCreatePoolParams,
+        recent_blockhash: Hash,
+        wait_transaction_confirmed: bool,
+    ) -> Result<PumpSwapParams, anyhow::Error> {
+        let (instructions, pool_params) =
+            PumpSwapInstructionBuilder.build_create_pool_instructions(&create_pool_params)?;
+
+        self.send_rpc_instructions(
+            create_pool_params.payer.clone(),
+            instructions,
+            constants::pumpswap::accounts::CREATE_POOL_UNIT_LIMIT,
+            "PumpSwap",
+            recent_blockhash,
+            wait_transaction_confirmed,
+        )
+        .await?;
+
+        Ok(pool_params)
+    }
+
+    /// Initialize a new Raydium CPMM pool with initial liquidity
+    ///
+    /// # Arguments
+    ///
+    /// * `initialize_params` - AMM config, mints, initial deposits and open time
+    /// * `recent_blockhash` - Recent blockhash for transaction validity
+    /// * `wait_transaction_confirmed` - Whether to wait for the transaction to be confirmed
+    ///
+    /// # Returns
+    ///
+    /// Returns `RaydiumCpmmParams` for the new pool, ready to be passed to `buy`/`sell`.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if:
+    /// - Either initial deposit is zero or both mints are the same
+    /// - The transaction fails to execute
+    /// - Network or RPC errors occur
+    pub async fn raydium_cpmm_initialize(
+        &self,
+        initialize_params: RaydiumCpmmInitializeParams,
+        recent_blockhash: Hash,
+        wait_transaction_confirmed: bool,
+    ) -> Result<RaydiumCpmmParams, anyhow::Error> {
+        let (instructions, pool_params) =
+            RaydiumCpmmInstructionBuilder.build_initialize_instructions(&initialize_params)?;
+        self.send_rpc_instructions(
+            initialize_params.payer.clone(),
+            instructions,
+            constants::raydium_cpmm::accounts::INITIALIZE_UNIT_LIMIT,
+            "RaydiumCpmm",
+            recent_blockhash,
+            wait_transaction_confirmed,
+        )
+        .await?;
+        Ok(pool_params)
+    }
+
+    /// Deposit liquidity into a Raydium CPMM pool, minting `lp_token_amount` LP tokens
+    ///
+    /// # Arguments
+    ///
+    /// * `liquidity_params` - Pool state, reserves, transfer fees, LP amount and slippage
+    /// * `recent_blockhash` - Recent blockhash for transaction validity
+    /// * `wait_transaction_confirmed` - Whether to wait for the transaction to be confirmed
+    pub async fn raydium_cpmm_deposit(
+        &self,
+        liquidity_params: RaydiumCpmmLiquidityParams,
+        recent_blockhash: Hash,
+        wait_transaction_confirmed: bool,
+    ) -> Result<(), anyhow::Error> {
+        let instructions =
+            RaydiumCpmmInstructionBuilder.build_deposit_instructions(&liquidity_params)?;
+        self.send_rpc_instructions(
+            liquidity_params.payer.clone(),
+            instructions,
+            constants::raydium_cpmm::accounts::LIQUIDITY_UNIT_LIMIT,
+            "RaydiumCpmm",
+            recent_blockhash,
+            wait_transaction_confirmed,
+        )
+        .await
+    }
+
+    /// Withdraw liquidity from a Raydium CPMM pool, burning `lp_token_amount` LP tokens
+    ///
+    /// # Arguments
+    ///
+    /// * `liquidity_params` - Pool state, reserves, transfer fees, LP amount and slippage
+    /// * `recent_blockhash` - Recent blockhash for transaction validity
+    /// * `wait_transaction_confirmed` - Whether to wait for the transaction to be confirmed
+    pub async fn raydium_cpmm_withdraw(
+        &self,
+        liquidity_params: RaydiumCpmmLiquidityParams,
+        recent_blockhash: Hash,
+        wait_transaction_confirmed: bool,
+    ) -> Result<(), anyhow::Error> {
+        let instructions =
+            RaydiumCpmmInstructionBuilder.build_withdraw_instructions(&liquidity_params)?;
+        self.send_rpc_instructions(
+            liquidity_params.payer.clone(),
+            instructions,
+            constants::raydium_cpmm::accounts::LIQUIDITY_UNIT_LIMIT,
+            "RaydiumCpmm",
+            recent_blockhash,
+            wait_transaction_confirmed,
+        )
+        .await
+    }
+
+    /// Collect the PumpFun and PumpSwap creator fees of every coin `creator` launched
+    ///
+    /// Empty vaults are skipped and PumpSwap's WSOL fees are unwrapped, so everything lands
+    /// in the creator wallet as SOL. No transaction is sent when there is nothing to collect.
+    ///
+    /// # Arguments
+    ///
+    /// * `creator` - Creator wallet, signs and pays for the transaction
+    /// * `recent_blockhash` - Recent blockhash for transaction validity
+    /// * `wait_transaction_confirmed` - Whether to wait for the transaction to be confirmed
+    ///
+    /// # Returns
+    ///
+    /// Returns the `CreatorFees` that were collected.
+    pub async fn collect_creator_fees(
+        &self,
+        creator: Arc<Keypair>,
+        recent_blockhash: Hash,
+        wait_transaction_confirmed: bool,
+    ) -> Result<CreatorFees, anyhow::Error> {
+        let creator_pubkey = creator.pubkey();
+        let fees = self.get_creator_fees(&creator_pubkey).await?;
+
+        let mut instructions = vec![];
+        if fees.pumpfun > 0 {
+            instructions.push(instruction::pumpfun::collect_creator_fee(&creator_pubkey));
+        }
+        if fees.pumpswap > 0 {
+            instructions.extend(
+                PumpSwapInstructionBuilder.build_collect_coin_creator_fee_instructions(
+                    &creator_pubkey,
+                    &constants::pumpswap::accounts::WSOL_TOKEN_ACCOUNT,
+                    &constants::pumpswap::accounts::TOKEN_PROGRAM,
+                ),
+            );
+        }
+        if instructions.is_empty() {
+            return Ok(fees);
+        }
+
+        self.send_rpc_instructions(
+            creator,
+            instructions,
+            0,
+            "CreatorFee",
+            recent_blockhash,
+            wait_transaction_confirmed,
+        )
+        .await?;
+        Ok(fees)
+    }
+
+    /// Claim the PumpFun and PumpSwap trading incentives `user` earned
+    ///
+    /// Programs with nothing to claim are skipped; no transaction is sent when there is
+    /// nothing to claim at all.
+    ///
+    /// # Arguments
+    ///
+    /// * `user` - Wallet that traded, signs and pays for the transaction
+    /// * `recent_blockhash` - Recent blockhash for transaction validity
+    /// * `wait_transaction_confirmed` - Whether to wait for the transaction to be confirmed
+    ///
+    /// # Returns
+    ///
+    /// Returns the `UnclaimedIncentives` read before claiming.
+    pub async fn claim_token_incentives(
+        &self,
+        user: Arc<Keypair>,
+        recent_blockhash: Hash,
+        wait_transaction_confirmed: bool,
+    ) -> Result<UnclaimedIncentives, anyhow::Error> {
+        let user_pubkey = user.pubkey();
+        let incentives = self.get_token_incentives(&user_pubkey).await?;
+
+        let mut instructions = vec![];
+        if let Some(pumpfun) = incentives.pumpfun.filter(|i| i.unclaimed_tokens > 0) {
+            let token_program = self.rpc.get_account(&pumpfun.mint).await?.owner;
+            instructions.push(instruction::pumpfun::claim_token_incentives(
+                &user_pubkey,
+                &user_pubkey,
+                &pumpfun.mint,
+                &token_program,
+            ));
+        }
+        if let Some(pumpswap) = incentives.pumpswap.filter(|i| i.unclaimed_tokens > 0) {
+            let token_program = self.rpc.get_account(&pumpswap.mint).await?.owner;
+            instructions.push(PumpSwapInstructionBuilder.build_claim_token_incentives_instruction(
+                &user_pubkey,
+                &user_pubkey,
+                &pumpswap.mint,
+                &token_program,
+            ));
+        }
+        if instructions.is_empty() {
+            return Ok(incentives);
+        }
+
+        self.send_rpc_instructions(
+            user,
+            instructions,
+            0,
+            "TokenIncentives",
+            recent_blockhash,
+            wait_transaction_confirmed,
+        )
+        .await?;
+        Ok(incentives)
+    }
+
+    /// Send up to 5 transactions as one atomic bundle
+    ///
+    /// The bundle goes only to SWQOS clients that support bundles (Jito, or NextBlock gRPC
+    /// with revert protection). Each gets the tip in its own tip account, added to the last
+    /// transaction of the bundle.
+    ///
+    /// # Arguments
+    ///
+    /// * `bundle` - Transactions and tip, see `BundleBuilder`
+    /// * `trade_type` - Trade type used in logs
+    /// * `recent_blockhash` - Recent blockhash for transaction validity, pre-signed
+    ///   transactions must use the same one
+    ///
+    /// # Returns
+    ///
+    /// Returns the `TradeOutcome` with the first bundle result of the provider that took the
+    /// bundle, e.g. `BundleOutcome::Accepted`, when the provider tracks bundle results.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if:
+    /// - No configured SWQOS client supports bundles
+    /// - The bundle has more than 5 transactions including the tip
+    /// - Every bundle-capable client fails to send it
+    pub async fn send_bundle(
+        &self,
+        bundle: &BundleBuilder,
+        trade_type: TradeType,
+        recent_blockhash: Hash,
+    ) -> Result<TradeOutcome, anyhow::Error> {
+        let (swqos_clients, _) = self.healthy_swqos(self.trade_config.priority_fee.clone());
+        bundle
+            .send(&swqos_clients, &self.trade_config.priority_fee, trade_type, recent_blockhash)
+            .await
+    }
+
+    /// Priority fee of one trade
+    ///
+    /// A custom tip replaces every provider's buy tip. Otherwise the tip strategy, when set
+    /// and its tip floor is fresh, fills the tips of each SWQOS client.
+    fn trade_priority_fee(
+        &self,
+        trade_type: TradeType,
+        custom_buy_tip_fee: Option<f64>,
+    ) -> PriorityFee {
+        let mut priority_fee = self.trade_config.priority_fee.clone();
+        if let Some(tip_fee) = custom_buy_tip_fee {
+            priority_fee.buy_tip_fee = tip_fee;
+            priority_fee.buy_tip_fees = priority_fee.buy_tip_fees.iter().map(|_| tip_fee).collect();
+        } else if let Some(tip_strategy) = &self.tip_strategy {
+            let swqos_types: Vec<_> =
+                self.swqos_clients.iter().map(|client| client.get_swqos_type()).collect();
+            tip_strategy.apply(&mut priority_fee, trade_type, &swqos_types);
+        }
+        priority_fee
+    }
+
+    /// SWQOS clients to fan a trade out to, with `buy_tip_fees` kept aligned to them
+    ///
+    /// Without a health monitor every client is used. Clients registered through
+    /// `with_swqos_client` are not monitored and always used.
+    fn healthy_swqos(&self, mut priority_fee: PriorityFee) -> (Vec<Arc<SwqosClient>>, PriorityFee) {
+        let Some(swqos_health) = &self.swqos_health else {
+            return (self.swqos_clients.clone(), priority_fee);
+        };
+        let indices = swqos_health.fan_out_indices(self.swqos_clients.len());
+        if !priority_fee.buy_tip_fees.is_empty() {
+            priority_fee.buy_tip_fees = indices
+                .iter()
+                .map(|index| priority_fee.buy_tip_fees[index % priority_fee.buy_tip_fees.len()])
+                .collect();
+        }
+        let swqos_clients =
+            indices.iter().filter_map(|index| self.swqos_clients.get(*index).cloned()).collect();
+        (swqos_clients, priority_fee)
+    }
+
+    /// Shared leg settings for `swap_path` and the router, tipped as a `direction` trade
+    fn swap_context(
+        &self,
+        direction: SwapDirection,
+        slippage_basis_points: Option<u64>,
+        recent_blockhash: Hash,
+        custom_buy_tip_fee: Option<f64>,
+        lookup_table_key: Option<Pubkey>,
+        wait_transaction_confirmed: bool,
+    ) -> SwapContext {
+        SwapContext {
+            rpc: Some(self.rpc.clone()),
+            payer: self.payer.clone(),
+            slippage_basis_points,
+            priority_fee: self.trade_priority_fee(direction.trade_type(), custom_buy_tip_fee),
+            lookup_table_key: lookup_table_key.or(self.trade_config.lookup_table_key),
+            recent_blockhash,
+            wait_transaction_confirmed,
+        }
+    }
+
+    /// Send swap instructions through every SWQOS client with one priority fee and tip
+    async fn send_swap_instructions(
+        &self,
+        context: &SwapContext,
+        direction: SwapDirection,
+        instructions: Vec<solana_sdk::instruction::Instruction>,
+        protocol_name: &str,
+    ) -> Result<TradeOutcome, anyhow::Error> {
+        let (swqos_clients, priority_fee) = self.healthy_swqos(context.priority_fee.clone());
+        parallel_execute_with_tips(
+            swqos_clients,
+            context.payer.clone(),
+            instructions,
+            priority_fee,
+            context.lookup_table_key,
+            context.recent_blockhash,
+            MAX_LOADED_ACCOUNTS_DATA_SIZE_LIMIT,
+            direction.trade_type(),
+            self.middleware_manager.clone(),
+            protocol_name.to_string(),
+            direction == SwapDirection::Buy,
+            context.wait_transaction_confirmed,
+        )
+        .await
+    }
+
+    /// Build and send a non-trade transaction (pool creation, liquidity) through RPC,
+    /// raising the configured compute unit limit to at least `min_unit_limit`
+    async fn send_rpc_instructions(
+        &self,
+        payer: Arc<Keypair>,
+        instructions: Vec<solana_sdk::instruction::Instruction>,
+        min_unit_limit: u32,
+        protocol_name: &str,
+        recent_blockhash: Hash,
+        wait_transaction_confirmed: bool,
+    ) -> Result<(), anyhow::Error> {
+        let mut priority_fee = self.trade_config.priority_fee.clone();
+        priority_fee.rpc_unit_limit = priority_fee.rpc_unit_limit.max(min_unit_limit);
+
+        let transaction = build_rpc_transaction(
+            payer,
+            &priority_fee,
+            instructions,
+            self.trade_config.lookup_table_key,
+            recent_blockhash,
+            MAX_LOADED_ACCOUNTS_DATA_SIZE_LIMIT,
+            self.middleware_manager.clone(),
+            protocol_name.to_string(),
+            false,
+        )
+        .await?;
+
+        if wait_transaction_confirmed {
+            self.rpc.send_and_confirm_transaction(&transaction).await?;
+        } else {
+            self.rpc.send_transaction(&transaction).await?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::swqos::{SwqosClientTrait, SwqosRegion, SwqosType};
+    use solana_sdk::transaction::VersionedTransaction;
+
+    struct RelayClient;
+
+    #[async_trait::async_trait]
+    impl SwqosClientTrait for RelayClient {
+        async fn send_transaction(&self, _: TradeType, _: &VersionedTransaction) -> anyhow::Result<()> {
+            Ok(())
+        }
+
+        async fn send_transactions(
+            &self,
+            _: TradeType,
+            _: &Vec<VersionedTransaction>,
+        ) -> anyhow::Result<()> {
+            Ok(())
+        }
+
+        fn get_tip_account(&self) -> anyhow::Result<String> {
+            Ok(Pubkey::new_unique().to_string())
+        }
+
+        fn get_swqos_type(&self) -> SwqosType {
+            SwqosType::Custom("relay".to_string())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_appended_swqos_client_is_tipped_and_unmonitored() {
+        let swqos_configs = vec![
+            SwqosConfig::Jito("".to_string(), SwqosRegion::Frankfurt),
+            SwqosConfig::NextBlock("".to_string(), SwqosRegion::Frankfurt),
+        ];
+        let priority_fee =
+            PriorityFee { buy_tip_fee: 0.5, buy_tip_fees: vec![0.1, 0.2], ..Default::default() };
+        let trade_config = TradeConfig::new(
+            "http://127.0.0.1:8899".to_string(),
+            swqos_configs.clone(),
+            priority_fee,
+            Default::default(),
+            None,
+        );
+        let mut client = SolanaTrade::new(Arc::new(Keypair::new()), trade_config)
+            .await
+            .unwrap()
+            .with_swqos_client(Arc::new(RelayClient));
+        assert_eq!(client.priority_fee.buy_tip_fees, vec![0.1, 0.2, 0.5]);
+        assert_eq!(client.trade_config.priority_fee.buy_tip_fees, vec![0.1, 0.2, 0.5]);
+
+        // 监控只覆盖配置中的两个服务，追加的客户端序号为 2
+        let monitor =
+            SwqosHealthMonitor::new(&swqos_configs, SwqosHealthConfig::default()).unwrap();
+        monitor.record(1, None);
+        client.swqos_health = Some(Arc::new(monitor));
+
+        let (swqos_clients, priority_fee) = client.healthy_swqos(client.priority_fee.clone());
+        let swqos_types: Vec<SwqosType> =
+            swqos_clients.iter().map(|client| client.get_swqos_type()).collect();
+        assert_eq!(swqos_types, vec![SwqosType::Jito, SwqosType::Custom("relay".to_string())]);
+        assert_eq!(priority_fee.buy_tip_fees, vec![0.1, 0.5]);
+    }
 }
@@ -67,7 +67,7 @@ async fn test_create_solana_trade_client() -> AnyResult<SolanaTrade> {
     let swqos_configs = create_swqos_configs(&rpc_url);
     let trade_config = create_trade_config(rpc_url, swqos_configs);
 
-    let solana_trade_client = SolanaTrade::new(Arc::new(payer), trade_config).await;
+    let solana_trade_client = SolanaTrade::new(Arc::new(payer), trade_config).await?;
     println!("SolanaTrade client created successfully!");
 
     Ok(solana_trade_client)
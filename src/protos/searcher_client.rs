@@ -35,7 +35,7 @@ pub enum BlockEngineConnectionError {
     ClientError(#[from] Status),
 }
 
-#[derive(Debug, Error)]
+#[derive(Debug, Clone, PartialEq, Error)]
 pub enum BundleRejectionError {
     #[error("bundle lost state auction, auction: {0}, tip {1} lamports")]
     StateAuctionBidRejected(String, u64),
@@ -45,6 +45,8 @@ pub enum BundleRejectionError {
     SimulationFailure(String, Option<String>),
     #[error("internal error {0}")]
     InternalError(String),
+    #[error("bundle dropped, message: {0}")]
+    DroppedBundle(String),
 }
 
 pub type BlockEngineConnectionResult<T> = Result<T, BlockEngineConnectionError>;
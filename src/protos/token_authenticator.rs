@@ -49,6 +49,11 @@ impl ClientInterceptor {
         Ok(Self { bearer_token })
     }
 
+    /// An interceptor that sends requests without an authorization header.
+    pub fn without_auth() -> Self {
+        Self { bearer_token: Arc::new(RwLock::new(String::new())) }
+    }
+
     async fn auth(
         auth_service_client: &mut AuthServiceClient<Channel>,
         keypair: &Keypair,
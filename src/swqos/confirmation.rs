@@ -0,0 +1,581 @@
+use std::{
+    collections::{HashMap, HashSet},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex, RwLock, Weak,
+    },
+    time::{Duration, Instant},
+};
+
+use anyhow::Result;
+use futures::StreamExt;
+use solana_client::{
+    nonblocking::pubsub_client::PubsubClient, rpc_config::RpcSignatureSubscribeConfig,
+    rpc_response::RpcSignatureResult,
+};
+use solana_hash::Hash;
+use solana_sdk::{
+    commitment_config::CommitmentConfig,
+    signature::Signature,
+    transaction::{TransactionError, VersionedTransaction},
+};
+use solana_transaction_status::TransactionStatus;
+use tokio::sync::{broadcast, oneshot, Notify};
+
+use crate::common::SolanaRpcClient;
+
+/// 一次 getSignatureStatuses 最多查询的签名数
+const MAX_SIGNATURES_PER_REQUEST: usize = 256;
+
+/// 检查 blockhash 是否过期的间隔
+const BLOCKHASH_CHECK_INTERVAL: Duration = Duration::from_secs(2);
+
+/// 交易至少等待多久才检查 blockhash 是否过期，刚从 processed 来源取得的 blockhash 可能还没被 RPC 节点看到
+const MIN_EXPIRY_CHECK_AGE: Duration = Duration::from_secs(2);
+
+/// 已处理 (processed) 的交易超过超时时间后最多再等待多久
+const MAX_PROCESSED_WAIT: Duration = Duration::from_secs(60);
+
+/// 交易流推送的上链结果在开始等待前保留多久，避免提交请求返回前已经上链的交易错过
+const RECENT_LANDING_TTL: Duration = Duration::from_secs(30);
+
+/// 上链结果广播的缓冲区大小
+const LANDING_CHANNEL_CAPACITY: usize = 1024;
+
+/// 交易确认配置
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConfirmationConfig {
+    /// 达到该承诺级别视为确认
+    pub commitment: CommitmentConfig,
+    /// 交易在该时间内没有上链则放弃，已处理的交易继续等待确认，直到分叉被丢弃且 blockhash 过期
+    pub timeout: Duration,
+    /// 批量查询签名状态的间隔
+    pub poll_interval: Duration,
+    /// 设置后同时通过 websocket signatureSubscribe 接收确认，轮询仍作为兜底
+    pub websocket_url: Option<String>,
+}
+
+impl Default for ConfirmationConfig {
+    fn default() -> Self {
+        Self {
+            commitment: CommitmentConfig::confirmed(),
+            timeout: Duration::from_secs(5),
+            poll_interval: Duration::from_millis(400),
+            websocket_url: None,
+        }
+    }
+}
+
+/// 交易未能确认的原因
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
+pub enum ConfirmationError {
+    #[error("Transaction {0} failed: {1}")]
+    Failed(Signature, TransactionError),
+    #[error("Transaction {0}'s blockhash expired before it landed")]
+    BlockhashExpired(Signature),
+    #[error("Transaction {0}'s confirmation timed out")]
+    Timeout(Signature),
+}
+
+/// 交易上链结果
+#[derive(Debug, Clone, PartialEq)]
+pub struct TransactionLanding {
+    pub signature: Signature,
+    /// 上链的 slot
+    pub slot: u64,
+    /// 执行失败的原因，成功为 None
+    pub err: Option<TransactionError>,
+}
+
+impl TransactionLanding {
+    pub fn is_success(&self) -> bool {
+        self.err.is_none()
+    }
+}
+
+struct PendingSignature {
+    /// 为 None 时 (durable nonce 交易) 不检查过期
+    blockhash: Option<Hash>,
+    added: Instant,
+    deadline: Instant,
+    processed: bool,
+    waiters: Vec<oneshot::Sender<Result<TransactionLanding, ConfirmationError>>>,
+    /// 签名的 websocket 订阅，结束等待时随条目释放，通知订阅任务退订
+    _subscription: Option<oneshot::Sender<()>>,
+}
+
+impl PendingSignature {
+    /// 等待时间足够长时返回需要检查是否过期的 blockhash
+    fn expiry_blockhash(&self, now: Instant) -> Option<Hash> {
+        if now.saturating_duration_since(self.added) < MIN_EXPIRY_CHECK_AGE {
+            return None;
+        }
+        self.blockhash
+    }
+}
+
+/// 每个 `SolanaTrade` 共享的交易确认跟踪器
+///
+/// 所有 SWQOS 客户端等待的签名由一个后台任务批量查询 getSignatureStatuses，
+/// 不再为每个服务单独轮询。交易流等外部来源可以通过 `landed` 提前结束等待。
+pub struct ConfirmationTracker {
+    rpc: Arc<SolanaRpcClient>,
+    config: RwLock<ConfirmationConfig>,
+    pending: Mutex<HashMap<Signature, PendingSignature>>,
+    recent: Mutex<HashMap<Signature, (TransactionLanding, Instant)>>,
+    landings: broadcast::Sender<TransactionLanding>,
+    wake: Arc<Notify>,
+    started: AtomicBool,
+    /// websocket 连接及其地址，订阅失败或连接断开后重建
+    pubsub: tokio::sync::Mutex<Option<(String, Arc<PubsubClient>)>>,
+}
+
+impl ConfirmationTracker {
+    pub fn new(rpc: Arc<SolanaRpcClient>, config: ConfirmationConfig) -> Self {
+        Self {
+            rpc,
+            config: RwLock::new(config),
+            pending: Mutex::new(HashMap::new()),
+            recent: Mutex::new(HashMap::new()),
+            landings: broadcast::channel(LANDING_CHANNEL_CAPACITY).0,
+            wake: Arc::new(Notify::new()),
+            started: AtomicBool::new(false),
+            pubsub: tokio::sync::Mutex::new(None),
+        }
+    }
+
+    pub fn rpc(&self) -> &Arc<SolanaRpcClient> {
+        &self.rpc
+    }
+
+    pub fn config(&self) -> ConfirmationConfig {
+        self.config.read().unwrap().clone()
+    }
+
+    /// 替换配置，只影响之后开始等待的交易
+    pub fn configure(&self, config: ConfirmationConfig) {
+        *self.config.write().unwrap() = config;
+    }
+
+    /// 正在等待确认的签名数
+    pub fn len(&self) -> usize {
+        self.pending.lock().unwrap().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// 订阅上链结果，包括等待中的交易和交易流推送的所有交易
+    pub fn landings(&self) -> broadcast::Receiver<TransactionLanding> {
+        self.landings.subscribe()
+    }
+
+    /// 外部来源 (例如交易流) 发现交易已上链，立即结束对它的等待，不再等待配置的承诺级别
+    pub fn landed(&self, landing: TransactionLanding) {
+        let _ = self.landings.send(landing.clone());
+        // 持有 pending 锁写入，和 `wait` 的登记互斥
+        let mut pending = self.pending.lock().unwrap();
+        match pending.remove(&landing.signature) {
+            Some(mut entry) => resolve(&mut entry, landing_result(landing)),
+            None => {
+                let mut recent = self.recent.lock().unwrap();
+                recent.retain(|_, (_, landed_at)| landed_at.elapsed() < RECENT_LANDING_TTL);
+                recent.insert(landing.signature, (landing, Instant::now()));
+            }
+        }
+    }
+
+    /// 等待交易达到配置的承诺级别，返回上链的 slot
+    pub async fn wait(
+        self: &Arc<Self>,
+        transaction: &VersionedTransaction,
+    ) -> Result<TransactionLanding> {
+        let signature = transaction.signatures[0];
+        if let Some((landing, _)) = self.recent.lock().unwrap().remove(&signature) {
+            return landing_result(landing).map_err(Into::into);
+        }
+        let blockhash = if uses_durable_nonce(transaction) {
+            None
+        } else {
+            Some(*transaction.message.recent_blockhash())
+        };
+        let config = self.config();
+
+        let (sender, receiver) = oneshot::channel();
+        // 同一签名的多个等待者共享一个订阅，只在第一次登记时订阅
+        let mut subscription = None;
+        {
+            let mut pending = self.pending.lock().unwrap();
+            // 交易流可能在加锁前已经推送了结果
+            if let Some((landing, _)) = self.recent.lock().unwrap().remove(&signature) {
+                return landing_result(landing).map_err(Into::into);
+            }
+            let entry = pending.entry(signature).or_insert_with(|| {
+                let subscribed = config.websocket_url.clone().map(|websocket_url| {
+                    let (subscribed, unsubscribe) = oneshot::channel::<()>();
+                    subscription = Some((websocket_url, unsubscribe));
+                    subscribed
+                });
+                let now = Instant::now();
+                PendingSignature {
+                    blockhash,
+                    added: now,
+                    deadline: now + config.timeout,
+                    processed: false,
+                    waiters: vec![],
+                    _subscription: subscribed,
+                }
+            });
+            entry.waiters.push(sender);
+        }
+        self.start();
+        self.wake.notify_one();
+
+        if let Some((websocket_url, unsubscribe)) = subscription {
+            let tracker = self.clone();
+            tokio::spawn(async move {
+                if let Err(e) = tracker
+                    .subscribe_signature(signature, &websocket_url, config.commitment, unsubscribe)
+                    .await
+                {
+                    eprintln!(" 订阅交易确认失败: {}, {}", signature, e);
+                }
+            });
+        }
+
+        match receiver.await {
+            Ok(Ok(landing)) => Ok(landing),
+            Ok(Err(e)) => Err(e.into()),
+            Err(_) => Err(ConfirmationError::Timeout(signature).into()),
+        }
+    }
+
+    /// 启动后台批量查询任务，跟踪器释放后任务退出
+    fn start(self: &Arc<Self>) {
+        if self.started.swap(true, Ordering::SeqCst) {
+            return;
+        }
+        let tracker = Arc::downgrade(self);
+        tokio::spawn(async move {
+            let mut last_blockhash_check = Instant::now();
+            loop {
+                let Some(this) = Weak::upgrade(&tracker) else { break };
+                if this.is_empty() {
+                    let wake = this.wake.clone();
+                    drop(this);
+                    wake.notified().await;
+                    continue;
+                }
+                let check_blockhashes = last_blockhash_check.elapsed() >= BLOCKHASH_CHECK_INTERVAL;
+                if check_blockhashes {
+                    last_blockhash_check = Instant::now();
+                }
+                this.poll(check_blockhashes).await;
+                let poll_interval = this.config().poll_interval;
+                drop(this);
+                tokio::time::sleep(poll_interval).await;
+            }
+        });
+    }
+
+    /// 查询所有等待中签名的状态，并处理超时和过期
+    async fn poll(&self, check_blockhashes: bool) {
+        let commitment = self.config().commitment;
+        let signatures: Vec<Signature> = self.pending.lock().unwrap().keys().copied().collect();
+        let mut unseen = HashSet::new();
+        for chunk in signatures.chunks(MAX_SIGNATURES_PER_REQUEST) {
+            match self.rpc.get_signature_statuses(chunk).await {
+                Ok(response) => {
+                    for (signature, status) in chunk.iter().zip(response.value) {
+                        match status {
+                            Some(status) => self.update(signature, &status, commitment),
+                            None => {
+                                unseen.insert(*signature);
+                            }
+                        }
+                    }
+                }
+                Err(e) => eprintln!(" 查询交易状态失败: {}", e),
+            }
+        }
+
+        let now = Instant::now();
+        let mut expired_blockhashes = HashSet::new();
+        if check_blockhashes {
+            let blockhashes: HashSet<Hash> = {
+                let pending = self.pending.lock().unwrap();
+                unseen
+                    .iter()
+                    .filter_map(|signature| pending.get(signature)?.expiry_blockhash(now))
+                    .collect()
+            };
+            // blockhash 可能来自 processed 来源，按 processed 检查，避免把尚未 confirmed 的 blockhash 当作过期
+            for blockhash in blockhashes {
+                if let Ok(false) =
+                    self.rpc.is_blockhash_valid(&blockhash, CommitmentConfig::processed()).await
+                {
+                    expired_blockhashes.insert(blockhash);
+                }
+            }
+        }
+
+        let mut pending = self.pending.lock().unwrap();
+        pending.retain(|signature, entry| {
+            let error = if unseen.contains(signature)
+                && entry
+                    .expiry_blockhash(now)
+                    .is_some_and(|blockhash| expired_blockhashes.contains(&blockhash))
+            {
+                Some(ConfirmationError::BlockhashExpired(*signature))
+            } else if now >= entry.deadline
+                && (!entry.processed
+                    || entry.blockhash.is_none()
+                    || now >= entry.deadline + MAX_PROCESSED_WAIT)
+            {
+                Some(ConfirmationError::Timeout(*signature))
+            } else {
+                None
+            };
+            match error {
+                Some(error) => {
+                    resolve(entry, Err(error));
+                    false
+                }
+                None => true,
+            }
+        });
+    }
+
+    fn update(
+        &self,
+        signature: &Signature,
+        status: &TransactionStatus,
+        commitment: CommitmentConfig,
+    ) {
+        if status.err.is_none() && !status.satisfies_commitment(commitment) {
+            if let Some(entry) = self.pending.lock().unwrap().get_mut(signature) {
+                entry.processed = true;
+            }
+            return;
+        }
+        self.finish(TransactionLanding {
+            signature: *signature,
+            slot: status.slot,
+            err: status.err.clone(),
+        });
+    }
+
+    fn finish(&self, landing: TransactionLanding) {
+        let entry = self.pending.lock().unwrap().remove(&landing.signature);
+        if let Some(mut entry) = entry {
+            resolve(&mut entry, landing_result(landing.clone()));
+            let _ = self.landings.send(landing);
+        }
+    }
+
+    /// 复用的 websocket 连接，没有连接或地址变化时建立新连接
+    async fn pubsub_client(&self, websocket_url: &str) -> Result<Arc<PubsubClient>> {
+        let mut pubsub = self.pubsub.lock().await;
+        if let Some((url, client)) = pubsub.as_ref() {
+            if url == websocket_url {
+                return Ok(client.clone());
+            }
+        }
+        let client = Arc::new(PubsubClient::new(websocket_url).await?);
+        *pubsub = Some((websocket_url.to_string(), client.clone()));
+        Ok(client)
+    }
+
+    /// 丢弃失效的连接，下次订阅时重建；其他订阅已经换了新连接时保留新连接
+    async fn reset_pubsub_client(&self, client: &Arc<PubsubClient>) {
+        let mut pubsub = self.pubsub.lock().await;
+        if pubsub.as_ref().is_some_and(|(_, current)| Arc::ptr_eq(current, client)) {
+            *pubsub = None;
+        }
+    }
+
+    /// 通过 websocket 等待确认通知，订阅失败时重建连接并重试一次
+    async fn subscribe_signature(
+        &self,
+        signature: Signature,
+        websocket_url: &str,
+        commitment: CommitmentConfig,
+        mut done: oneshot::Receiver<()>,
+    ) -> Result<()> {
+        let config = RpcSignatureSubscribeConfig {
+            commitment: Some(commitment),
+            enable_received_notification: Some(false),
+        };
+        let mut retried = false;
+        loop {
+            let pubsub = self.pubsub_client(websocket_url).await?;
+            let (mut notifications, unsubscribe) =
+                match pubsub.signature_subscribe(&signature, Some(config.clone())).await {
+                    Ok(subscription) => subscription,
+                    Err(e) => {
+                        self.reset_pubsub_client(&pubsub).await;
+                        if retried {
+                            return Err(e.into());
+                        }
+                        retried = true;
+                        continue;
+                    }
+                };
+            loop {
+                let notification = tokio::select! {
+                    notification = notifications.next() => notification,
+                    _ = &mut done => {
+                        unsubscribe().await;
+                        return Ok(());
+                    }
+                };
+                let Some(notification) = notification else {
+                    // 连接断开，轮询继续兜底，之后的订阅使用新连接
+                    self.reset_pubsub_client(&pubsub).await;
+                    return Ok(());
+                };
+                if let RpcSignatureResult::ProcessedSignature(result) = notification.value {
+                    self.finish(TransactionLanding {
+                        signature,
+                        slot: notification.context.slot,
+                        err: result.err,
+                    });
+                    unsubscribe().await;
+                    return Ok(());
+                }
+            }
+        }
+    }
+}
+
+fn landing_result(landing: TransactionLanding) -> Result<TransactionLanding, ConfirmationError> {
+    match landing.err {
+        Some(err) => Err(ConfirmationError::Failed(landing.signature, err)),
+        None => Ok(landing),
+    }
+}
+
+fn resolve(entry: &mut PendingSignature, result: Result<TransactionLanding, ConfirmationError>) {
+    for waiter in entry.waiters.drain(..) {
+        let _ = waiter.send(result.clone());
+    }
+}
+
+/// durable nonce 交易的 blockhash 是 nonce 值，不会按区块高度过期
+fn uses_durable_nonce(transaction: &VersionedTransaction) -> bool {
+    let message = &transaction.message;
+    message.instructions().first().is_some_and(|instruction| {
+        message.static_account_keys().get(instruction.program_id_index as usize)
+            == Some(&solana_sdk::system_program::ID)
+            && instruction.data.starts_with(&[4, 0, 0, 0])
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_sdk::{
+        instruction::Instruction,
+        message::{v0, VersionedMessage},
+        pubkey::Pubkey,
+        signature::Keypair,
+        signer::Signer,
+    };
+    use solana_system_interface::instruction::{advance_nonce_account, transfer};
+
+    fn build(payer: &Keypair, instructions: &[Instruction]) -> VersionedTransaction {
+        let message =
+            v0::Message::try_compile(&payer.pubkey(), instructions, &[], Hash::new_unique())
+                .unwrap();
+        VersionedTransaction::try_new(VersionedMessage::V0(message), &[payer]).unwrap()
+    }
+
+    #[test]
+    fn test_uses_durable_nonce() {
+        let payer = Keypair::new();
+        let transfer = transfer(&payer.pubkey(), &Pubkey::new_unique(), 1);
+        assert!(!uses_durable_nonce(&build(&payer, std::slice::from_ref(&transfer))));
+        let advance = advance_nonce_account(&Pubkey::new_unique(), &payer.pubkey());
+        assert!(uses_durable_nonce(&build(&payer, &[advance, transfer])));
+    }
+
+    #[test]
+    fn test_expiry_blockhash_waits_for_min_age() {
+        let added = Instant::now();
+        let blockhash = Hash::new_unique();
+        let entry = PendingSignature {
+            blockhash: Some(blockhash),
+            added,
+            deadline: added + Duration::from_secs(5),
+            processed: false,
+            waiters: vec![],
+            _subscription: None,
+        };
+        // 刚提交的交易不检查过期
+        assert_eq!(entry.expiry_blockhash(added), None);
+        assert_eq!(entry.expiry_blockhash(added + MIN_EXPIRY_CHECK_AGE), Some(blockhash));
+
+        let nonce_entry = PendingSignature { blockhash: None, ..entry };
+        assert_eq!(nonce_entry.expiry_blockhash(added + MIN_EXPIRY_CHECK_AGE), None);
+    }
+
+    #[tokio::test]
+    async fn test_landed_before_wait() {
+        let rpc = Arc::new(SolanaRpcClient::new("http://127.0.0.1:8899".to_string()));
+        let tracker = Arc::new(ConfirmationTracker::new(rpc, ConfirmationConfig::default()));
+        let payer = Keypair::new();
+        let transaction = build(&payer, &[transfer(&payer.pubkey(), &Pubkey::new_unique(), 1)]);
+
+        // 提交请求返回前交易流已经推送了上链结果
+        tracker.landed(TransactionLanding {
+            signature: transaction.signatures[0],
+            slot: 7,
+            err: None,
+        });
+        assert_eq!(tracker.wait(&transaction).await.unwrap().slot, 7);
+        assert!(tracker.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_waiters_share_one_pending_signature() {
+        let rpc = Arc::new(SolanaRpcClient::new("http://127.0.0.1:8899".to_string()));
+        let config = ConfirmationConfig {
+            timeout: Duration::from_secs(60),
+            poll_interval: Duration::from_secs(60),
+            ..Default::default()
+        };
+        let tracker = Arc::new(ConfirmationTracker::new(rpc, config));
+        let payer = Keypair::new();
+        let transaction = build(&payer, &[transfer(&payer.pubkey(), &Pubkey::new_unique(), 1)]);
+
+        let waits: Vec<_> = (0..3)
+            .map(|_| {
+                let tracker = tracker.clone();
+                let transaction = transaction.clone();
+                tokio::spawn(async move { tracker.wait(&transaction).await })
+            })
+            .collect();
+        while tracker
+            .pending
+            .lock()
+            .unwrap()
+            .get(&transaction.signatures[0])
+            .map(|entry| entry.waiters.len())
+            != Some(3)
+        {
+            tokio::task::yield_now().await;
+        }
+        assert_eq!(tracker.len(), 1);
+
+        tracker.landed(TransactionLanding {
+            signature: transaction.signatures[0],
+            slot: 9,
+            err: None,
+        });
+        for wait in waits {
+            assert_eq!(wait.await.unwrap().unwrap().slot, 9);
+        }
+        assert!(tracker.is_empty());
+    }
+}
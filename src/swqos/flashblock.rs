@@ -1,4 +1,4 @@
-use crate::swqos::common::{poll_transaction_confirmation, serialize_transaction_and_encode};
+use crate::swqos::common::serialize_transaction_and_encode;
 use rand::seq::IndexedRandom;
 use reqwest::Client;
 use serde_json::json;
@@ -12,14 +12,14 @@ use solana_sdk::transaction::VersionedTransaction;
 use crate::swqos::{SwqosType, TradeType};
 use crate::swqos::SwqosClientTrait;
 
-use crate::{common::SolanaRpcClient, constants::swqos::FLASHBLOCK_TIP_ACCOUNTS};
+use crate::{constants::swqos::FLASHBLOCK_TIP_ACCOUNTS, swqos::confirmation::ConfirmationTracker};
 
 
 #[derive(Clone)]
 pub struct FlashBlockClient {
     pub endpoint: String,
     pub auth_token: String,
-    pub rpc_client: Arc<SolanaRpcClient>,
+    pub confirmation_tracker: Arc<ConfirmationTracker>,
     pub http_client: Client,
 }
 
@@ -44,8 +44,7 @@ impl SwqosClientTrait for FlashBlockClient {
 }
 
 impl FlashBlockClient {
-    pub fn new(rpc_url: String, endpoint: String, auth_token: String) -> Self {
-        let rpc_client = SolanaRpcClient::new(rpc_url);
+    pub fn new(confirmation_tracker: Arc<ConfirmationTracker>, endpoint: String, auth_token: String) -> Self {
         let http_client = Client::builder()
             .pool_idle_timeout(Duration::from_secs(60))
             .pool_max_idle_per_host(64)
@@ -55,12 +54,12 @@ impl FlashBlockClient {
             .connect_timeout(Duration::from_secs(5))
             .build()
             .unwrap();
-        Self { rpc_client: Arc::new(rpc_client), endpoint, auth_token, http_client }
+        Self { confirmation_tracker, endpoint, auth_token, http_client }
     }
 
     pub async fn send_transaction(&self, trade_type: TradeType, transaction: &VersionedTransaction) -> Result<()> {
         let start_time = Instant::now();
-        let (content, signature) = serialize_transaction_and_encode(transaction, UiTransactionEncoding::Base64).await?;
+        let (content, _) = serialize_transaction_and_encode(transaction, UiTransactionEncoding::Base64).await?;
         println!(" 交易编码base64: {:?}", start_time.elapsed());
 
         // FlashBlock API格式
@@ -90,7 +89,7 @@ impl FlashBlockClient {
         }
 
         let start_time: Instant = Instant::now();
-        match poll_transaction_confirmation(&self.rpc_client, signature).await {
+        match self.confirmation_tracker.wait(transaction).await {
             Ok(_) => (),
             Err(e) => {
                 println!(" FlashBlock{}确认失败: {:?}", trade_type, start_time.elapsed());
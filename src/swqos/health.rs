@@ -0,0 +1,283 @@
+use std::{
+    collections::VecDeque,
+    sync::{Arc, Mutex, Weak},
+    time::{Duration, Instant},
+};
+
+use anyhow::Result;
+use reqwest::Client;
+
+use crate::swqos::{SwqosConfig, SwqosRegion};
+
+/// 参与选择的区域
+const REGIONS: [SwqosRegion; 8] = [
+    SwqosRegion::NewYork,
+    SwqosRegion::Frankfurt,
+    SwqosRegion::Amsterdam,
+    SwqosRegion::SLC,
+    SwqosRegion::Tokyo,
+    SwqosRegion::London,
+    SwqosRegion::LosAngeles,
+    SwqosRegion::Default,
+];
+
+/// SWQOS 健康检查配置
+#[derive(Debug, Clone, PartialEq)]
+pub struct SwqosHealthConfig {
+    /// 探测间隔
+    pub probe_interval: Duration,
+    /// 单次探测超时，超时计为失败
+    pub probe_timeout: Duration,
+    /// 统计最近多少次探测
+    pub window: usize,
+    /// 失败率超过该值视为不健康
+    pub max_error_rate: f64,
+    /// 平均 RTT 超过该值视为不健康
+    pub max_rtt: Option<Duration>,
+}
+
+impl Default for SwqosHealthConfig {
+    fn default() -> Self {
+        Self {
+            probe_interval: Duration::from_secs(10),
+            probe_timeout: Duration::from_secs(2),
+            window: 12,
+            max_error_rate: 0.5,
+            max_rtt: None,
+        }
+    }
+}
+
+/// 一个服务最近的探测结果
+#[derive(Debug, Clone, Default)]
+pub struct ProviderHealth {
+    /// 每次探测的 RTT，失败为 None
+    samples: VecDeque<Option<Duration>>,
+}
+
+impl ProviderHealth {
+    pub fn record(&mut self, rtt: Option<Duration>, window: usize) {
+        self.samples.push_back(rtt);
+        while self.samples.len() > window.max(1) {
+            self.samples.pop_front();
+        }
+    }
+
+    /// 成功探测的平均 RTT
+    pub fn rtt(&self) -> Option<Duration> {
+        let rtts: Vec<Duration> = self.samples.iter().flatten().copied().collect();
+        if rtts.is_empty() {
+            return None;
+        }
+        Some(rtts.iter().sum::<Duration>() / rtts.len() as u32)
+    }
+
+    pub fn error_rate(&self) -> f64 {
+        if self.samples.is_empty() {
+            return 0.0;
+        }
+        self.samples.iter().filter(|rtt| rtt.is_none()).count() as f64 / self.samples.len() as f64
+    }
+
+    /// 尚未探测的服务视为健康
+    pub fn is_healthy(&self, config: &SwqosHealthConfig) -> bool {
+        if self.samples.is_empty() {
+            return true;
+        }
+        if self.error_rate() > config.max_error_rate {
+            return false;
+        }
+        match (self.rtt(), config.max_rtt) {
+            (Some(rtt), Some(max_rtt)) => rtt <= max_rtt,
+            _ => true,
+        }
+    }
+}
+
+/// 服务健康状态快照
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProviderHealthSnapshot {
+    pub endpoint: String,
+    pub rtt: Option<Duration>,
+    pub error_rate: f64,
+    pub healthy: bool,
+}
+
+/// 测量一次到 endpoint 的 RTT，收到任何 HTTP 响应都算可达
+pub async fn probe_endpoint(http_client: &Client, endpoint: &str) -> Result<Duration> {
+    let start_time = Instant::now();
+    http_client.get(endpoint).send().await?;
+    Ok(start_time.elapsed())
+}
+
+/// 探测服务所有区域的 endpoint，返回 RTT 最低的区域的配置
+///
+/// `Default` 配置、自定义 endpoint 或所有区域都不可达时原样返回
+pub async fn select_best_region(swqos_config: SwqosConfig, probe_timeout: Duration) -> SwqosConfig {
+    if matches!(swqos_config.region(), None | Some(SwqosRegion::Custom(_))) {
+        return swqos_config;
+    }
+    let Ok(http_client) = Client::builder().timeout(probe_timeout).build() else {
+        return swqos_config;
+    };
+    let candidates: Vec<SwqosConfig> =
+        REGIONS.iter().map(|region| swqos_config.clone().with_region(region.clone())).collect();
+    let endpoints: Vec<String> = candidates.iter().map(|candidate| candidate.endpoint()).collect();
+    let rtts = futures::future::join_all(
+        endpoints.iter().map(|endpoint| probe_endpoint(&http_client, endpoint)),
+    )
+    .await;
+    candidates
+        .into_iter()
+        .zip(rtts)
+        .filter_map(|(candidate, rtt)| rtt.ok().map(|rtt| (candidate, rtt)))
+        .min_by_key(|(_, rtt)| *rtt)
+        .map(|(candidate, _)| candidate)
+        .unwrap_or(swqos_config)
+}
+
+/// 选择每个服务 RTT 最低的区域
+pub async fn select_best_regions(
+    swqos_configs: Vec<SwqosConfig>,
+    probe_timeout: Duration,
+) -> Vec<SwqosConfig> {
+    futures::future::join_all(
+        swqos_configs.into_iter().map(|config| select_best_region(config, probe_timeout)),
+    )
+    .await
+}
+
+/// 定期探测每个已配置的服务，记录 RTT 和失败率
+///
+/// 交易只发送给健康的服务；全部不健康时仍发送给所有服务。
+pub struct SwqosHealthMonitor {
+    config: SwqosHealthConfig,
+    endpoints: Vec<String>,
+    health: Vec<Mutex<ProviderHealth>>,
+    http_client: Client,
+}
+
+impl SwqosHealthMonitor {
+    /// `swqos_configs` 与 SWQOS 客户端一一对应
+    pub fn new(swqos_configs: &[SwqosConfig], config: SwqosHealthConfig) -> Result<Self> {
+        let http_client = Client::builder()
+            .timeout(config.probe_timeout)
+            .pool_idle_timeout(config.probe_interval * 2)
+            .build()?;
+        let endpoints: Vec<String> = swqos_configs.iter().map(|config| config.endpoint()).collect();
+        let health = endpoints.iter().map(|_| Mutex::new(ProviderHealth::default())).collect();
+        Ok(Self { config, endpoints, health, http_client })
+    }
+
+    /// 启动后台探测，监控被释放后自动停止
+    pub fn start(self: &Arc<Self>) {
+        let monitor = Arc::downgrade(self);
+        let probe_interval = self.config.probe_interval;
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(probe_interval);
+            loop {
+                interval.tick().await;
+                let Some(monitor) = Weak::upgrade(&monitor) else {
+                    break;
+                };
+                monitor.probe().await;
+            }
+        });
+    }
+
+    /// 并发探测所有服务一次
+    pub async fn probe(&self) {
+        let rtts = futures::future::join_all(
+            self.endpoints.iter().map(|endpoint| probe_endpoint(&self.http_client, endpoint)),
+        )
+        .await;
+        for (index, (endpoint, rtt)) in self.endpoints.iter().zip(rtts).enumerate() {
+            // 没有固定 endpoint 的服务（如 TPU）不探测，始终视为健康
+            if !endpoint.is_empty() {
+                self.record(index, rtt.ok());
+            }
+        }
+    }
+
+    pub fn is_healthy(&self, index: usize) -> bool {
+        self.health
+            .get(index)
+            .map(|health| health.lock().unwrap().is_healthy(&self.config))
+            .unwrap_or(true)
+    }
+
+    /// 监控的服务数量
+    pub fn len(&self) -> usize {
+        self.health.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.health.is_empty()
+    }
+
+    /// 健康的服务序号，全部不健康时返回所有序号
+    pub fn healthy_indices(&self) -> Vec<usize> {
+        let healthy: Vec<usize> =
+            (0..self.health.len()).filter(|index| self.is_healthy(*index)).collect();
+        if healthy.is_empty() {
+            (0..self.health.len()).collect()
+        } else {
+            healthy
+        }
+    }
+
+    /// 一次交易要发送的客户端序号，`clients` 为客户端总数；创建监控之后追加的客户端不受监控，总是保留
+    pub fn fan_out_indices(&self, clients: usize) -> Vec<usize> {
+        self.healthy_indices().into_iter().chain(self.len()..clients).collect()
+    }
+
+    /// 记录一次探测结果
+    pub(crate) fn record(&self, index: usize, rtt: Option<Duration>) {
+        if let Some(health) = self.health.get(index) {
+            health.lock().unwrap().record(rtt, self.config.window);
+        }
+    }
+
+    pub fn snapshot(&self) -> Vec<ProviderHealthSnapshot> {
+        self.endpoints
+            .iter()
+            .zip(&self.health)
+            .map(|(endpoint, health)| {
+                let health = health.lock().unwrap();
+                ProviderHealthSnapshot {
+                    endpoint: endpoint.clone(),
+                    rtt: health.rtt(),
+                    error_rate: health.error_rate(),
+                    healthy: health.is_healthy(&self.config),
+                }
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unhealthy_providers_are_dropped() {
+        let swqos_configs = vec![
+            SwqosConfig::Jito("".to_string(), SwqosRegion::Frankfurt),
+            SwqosConfig::NextBlock("".to_string(), SwqosRegion::Frankfurt),
+        ];
+        let config =
+            SwqosHealthConfig { max_rtt: Some(Duration::from_millis(100)), ..Default::default() };
+        let monitor = SwqosHealthMonitor::new(&swqos_configs, config).unwrap();
+        assert_eq!(monitor.healthy_indices(), vec![0, 1]);
+
+        let window = monitor.config.window;
+        monitor.health[0].lock().unwrap().record(Some(Duration::from_millis(20)), window);
+        monitor.health[1].lock().unwrap().record(None, window);
+        assert_eq!(monitor.healthy_indices(), vec![0]);
+
+        monitor.health[0].lock().unwrap().record(Some(Duration::from_millis(400)), window);
+        assert_eq!(monitor.health[0].lock().unwrap().rtt(), Some(Duration::from_millis(210)));
+        // 全部不健康时仍发送给所有服务
+        assert_eq!(monitor.healthy_indices(), vec![0, 1]);
+    }
+}
@@ -1,5 +1,5 @@
 
-use crate::swqos::common::{poll_transaction_confirmation, serialize_transaction_and_encode, FormatBase64VersionedTransaction};
+use crate::swqos::common::{serialize_transaction_and_encode, FormatBase64VersionedTransaction};
 use rand::seq::IndexedRandom;
 use reqwest::Client;
 use serde_json::json;
@@ -10,16 +10,19 @@ use solana_transaction_status::UiTransactionEncoding;
 
 use anyhow::Result;
 use solana_sdk::transaction::VersionedTransaction;
-use crate::swqos::{SwqosType, TradeType};
+use crate::swqos::{jito_bundle_tracker::BundleOutcome, SwqosType, TradeType};
 use crate::swqos::SwqosClientTrait;
 
-use crate::{common::SolanaRpcClient, constants::swqos::JITO_TIP_ACCOUNTS};
-
+use crate::{constants::swqos::JITO_TIP_ACCOUNTS, swqos::confirmation::ConfirmationTracker};
 
+/// 通过 JSON-RPC 发送交易和 bundle 的 Jito 客户端
+///
+/// bundle 发送后轮询 getInflightBundleStatuses，上链时返回 `BundleOutcome::Landed`，失败时返回错误。
+/// 该接口不给出拒绝原因，需要时使用带认证密钥的 `JitoGrpcClient`
 pub struct JitoClient {
     pub endpoint: String,
     pub auth_token: String,
-    pub rpc_client: Arc<SolanaRpcClient>,
+    pub confirmation_tracker: Arc<ConfirmationTracker>,
     pub http_client: Client,
 }
 
@@ -30,6 +33,10 @@ impl SwqosClientTrait for JitoClient {
     }
 
     async fn send_transactions(&self, trade_type: TradeType, transactions: &Vec<VersionedTransaction>) -> Result<()> {
+        self.send_transactions(trade_type, transactions).await.map(|_| ())
+    }
+
+    async fn send_transactions_with_outcome(&self, trade_type: TradeType, transactions: &Vec<VersionedTransaction>) -> Result<Option<BundleOutcome>> {
         self.send_transactions(trade_type, transactions).await
     }
 
@@ -44,11 +51,14 @@ impl SwqosClientTrait for JitoClient {
     fn get_swqos_type(&self) -> SwqosType {
         SwqosType::Jito
     }
+
+    fn supports_bundles(&self) -> bool {
+        true
+    }
 }
 
 impl JitoClient {
-    pub fn new(rpc_url: String, endpoint: String, auth_token: String) -> Self {
-        let rpc_client = SolanaRpcClient::new(rpc_url);
+    pub fn new(confirmation_tracker: Arc<ConfirmationTracker>, endpoint: String, auth_token: String) -> Self {
         let http_client = Client::builder()
             .pool_idle_timeout(Duration::from_secs(60))
             .pool_max_idle_per_host(64)
@@ -58,12 +68,12 @@ impl JitoClient {
             .connect_timeout(Duration::from_secs(5))
             .build()
             .unwrap();
-        Self { rpc_client: Arc::new(rpc_client), endpoint, auth_token, http_client }
+        Self { confirmation_tracker, endpoint, auth_token, http_client }
     }
 
     pub async fn send_transaction(&self, trade_type: TradeType, transaction: &VersionedTransaction) -> Result<()> {
         let start_time = Instant::now();
-        let (content, signature) = serialize_transaction_and_encode(transaction, UiTransactionEncoding::Base64).await?;
+        let (content, _) = serialize_transaction_and_encode(transaction, UiTransactionEncoding::Base64).await?;
         println!(" 交易编码base64: {:?}", start_time.elapsed());
 
         let request_body = serde_json::to_string(&json!({
@@ -106,7 +116,7 @@ impl JitoClient {
         }
 
         let start_time: Instant = Instant::now();
-        match poll_transaction_confirmation(&self.rpc_client, signature).await {
+        match self.confirmation_tracker.wait(transaction).await {
             Ok(_) => (),
             Err(e) => {
                 println!(" jito{}确认失败: {:?}", trade_type, start_time.elapsed());
@@ -119,7 +129,8 @@ impl JitoClient {
         Ok(())
     }
 
-    pub async fn send_transactions(&self, trade_type: TradeType, transactions: &Vec<VersionedTransaction>) -> Result<()> {
+    /// 发送 bundle 并轮询它的状态，最多等待确认超时时间，期间没有结果时返回 None
+    pub async fn send_transactions(&self, trade_type: TradeType, transactions: &Vec<VersionedTransaction>) -> Result<Option<BundleOutcome>> {
         let start_time = Instant::now();
         let txs_base64 = transactions.iter().map(|tx| tx.to_base64_string()).collect::<Vec<String>>();
         let body = serde_json::json!({
@@ -151,14 +162,71 @@ impl JitoClient {
             .text()
             .await?;
 
-        if let Ok(response_json) = serde_json::from_str::<serde_json::Value>(&response_text) {
-            if response_json.get("result").is_some() {
-                println!(" jito{}提交: {:?}", trade_type, start_time.elapsed());
-            } else if let Some(_error) = response_json.get("error") {
-                eprintln!(" jito{}提交失败: {:?}", trade_type, _error);
+        let response_json = serde_json::from_str::<serde_json::Value>(&response_text)?;
+        if let Some(error) = response_json.get("error") {
+            eprintln!(" jito{}提交失败: {:?}", trade_type, error);
+            return Err(anyhow::anyhow!("Jito rejected bundle: {}", error));
+        }
+        let Some(bundle_id) = response_json["result"].as_str() else {
+            return Err(anyhow::anyhow!("Jito returned no bundle id: {}", response_text));
+        };
+        println!(" jito{}提交: {:?}", trade_type, start_time.elapsed());
+
+        let config = self.confirmation_tracker.config();
+        let deadline = Instant::now() + config.timeout;
+        while Instant::now() < deadline {
+            tokio::time::sleep(config.poll_interval).await;
+            match self.get_inflight_bundle_status(bundle_id).await {
+                Ok(Some(outcome)) => {
+                    if let Some(error) = outcome.clone().into_error() {
+                        eprintln!(" jito{}bundle失败: {:?}", trade_type, start_time.elapsed());
+                        return Err(error.into());
+                    }
+                    println!(" jito{}bundle上链: {:?}", trade_type, start_time.elapsed());
+                    return Ok(Some(outcome));
+                }
+                Ok(None) => (),
+                Err(e) => eprintln!(" jito查询bundle状态失败: {}", e),
             }
         }
 
-        Ok(())
+        Ok(None)
+    }
+
+    /// 查询 bundle 最近 5 分钟内的状态，尚未上链或失败时返回 None
+    pub async fn get_inflight_bundle_status(&self, bundle_id: &str) -> Result<Option<BundleOutcome>> {
+        let body = serde_json::json!({
+            "jsonrpc": "2.0",
+            "method": "getInflightBundleStatuses",
+            "params": [[bundle_id]],
+            "id": 1,
+        });
+
+        let endpoint = if self.auth_token.is_empty() {
+            format!("{}/api/v1/getInflightBundleStatuses", self.endpoint)
+        } else {
+            format!("{}/api/v1/getInflightBundleStatuses?uuid={}", self.endpoint, self.auth_token)
+        };
+        let response = if self.auth_token.is_empty() {
+            self.http_client.post(&endpoint)
+        } else {
+            self.http_client.post(&endpoint)
+                .header("x-jito-auth", &self.auth_token)
+        };
+        let response_json = response
+            .body(body.to_string())
+            .header("Content-Type", "application/json")
+            .send()
+            .await?
+            .json::<serde_json::Value>()
+            .await?;
+
+        if let Some(error) = response_json.get("error") {
+            return Err(anyhow::anyhow!("getInflightBundleStatuses failed: {}", error));
+        }
+        let status = &response_json["result"]["value"][0];
+        Ok(status["status"]
+            .as_str()
+            .and_then(|name| BundleOutcome::from_inflight_status(name, status["landed_slot"].as_u64())))
     }
 }
\ No newline at end of file
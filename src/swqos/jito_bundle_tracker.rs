@@ -0,0 +1,334 @@
+use std::{
+    collections::HashMap,
+    future::Future,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use anyhow::Result;
+use tokio::sync::mpsc;
+
+use crate::{
+    protos::{
+        bundle::{bundle_result, rejected::Reason, BundleResult, DroppedReason},
+        searcher::SubscribeBundleResultsRequest,
+        searcher_client::BundleRejectionError,
+    },
+    swqos::jito_grpc::AuthenticatedSearcherClient,
+};
+
+/// 结果保留时间，超时未被领取或仍在等待的 bundle 会被清理
+const BUNDLE_RESULT_TTL: Duration = Duration::from_secs(60);
+/// 结果流断开后重新订阅的间隔
+const RESUBSCRIBE_INTERVAL: Duration = Duration::from_secs(1);
+
+/// block engine 给出的 bundle 结果
+#[derive(Debug, Clone, PartialEq)]
+pub enum BundleOutcome {
+    /// 已转发给验证者
+    Accepted { slot: u64, validator_identity: String },
+    /// 已打包进区块
+    Processed { slot: u64, validator_identity: String, bundle_index: u64 },
+    /// 已上链，JSON-RPC getInflightBundleStatuses 只给出上链的 slot
+    Landed { slot: u64 },
+    /// 所在区块已 finalized
+    Finalized,
+    /// 被 block engine 拒绝
+    Rejected(BundleRejectionError),
+    /// 已转发但未上链
+    Dropped(String),
+}
+
+impl BundleOutcome {
+    pub fn from_result(result: bundle_result::Result) -> Option<Self> {
+        let outcome = match result {
+            bundle_result::Result::Accepted(accepted) => BundleOutcome::Accepted {
+                slot: accepted.slot,
+                validator_identity: accepted.validator_identity,
+            },
+            bundle_result::Result::Processed(processed) => BundleOutcome::Processed {
+                slot: processed.slot,
+                validator_identity: processed.validator_identity,
+                bundle_index: processed.bundle_index,
+            },
+            bundle_result::Result::Finalized(_) => BundleOutcome::Finalized,
+            bundle_result::Result::Rejected(rejected) => {
+                BundleOutcome::Rejected(match rejected.reason? {
+                    Reason::StateAuctionBidRejected(rejected) => {
+                        BundleRejectionError::StateAuctionBidRejected(
+                            rejected.auction_id,
+                            rejected.simulated_bid_lamports,
+                        )
+                    }
+                    Reason::WinningBatchBidRejected(rejected) => {
+                        BundleRejectionError::WinningBatchBidRejected(
+                            rejected.auction_id,
+                            rejected.simulated_bid_lamports,
+                        )
+                    }
+                    Reason::SimulationFailure(failure) => {
+                        BundleRejectionError::SimulationFailure(failure.tx_signature, failure.msg)
+                    }
+                    Reason::InternalError(error) => BundleRejectionError::InternalError(error.msg),
+                    Reason::DroppedBundle(dropped) => {
+                        BundleRejectionError::DroppedBundle(dropped.msg)
+                    }
+                })
+            }
+            bundle_result::Result::Dropped(dropped) => BundleOutcome::Dropped(
+                DroppedReason::try_from(dropped.reason)
+                    .map(|reason| reason.as_str_name().to_string())
+                    .unwrap_or_else(|_| dropped.reason.to_string()),
+            ),
+        };
+        Some(outcome)
+    }
+
+    /// getInflightBundleStatuses 返回的状态，`Pending` 和 `Invalid` (尚未被 block engine 看到) 没有结果
+    pub fn from_inflight_status(status: &str, landed_slot: Option<u64>) -> Option<Self> {
+        match (status, landed_slot) {
+            ("Landed", Some(slot)) => Some(BundleOutcome::Landed { slot }),
+            ("Failed", _) => Some(BundleOutcome::Dropped(status.to_string())),
+            _ => None,
+        }
+    }
+
+    /// 被拒绝或未上链时返回对应的错误
+    pub fn into_error(self) -> Option<BundleRejectionError> {
+        match self {
+            BundleOutcome::Rejected(error) => Some(error),
+            BundleOutcome::Dropped(reason) => Some(BundleRejectionError::DroppedBundle(reason)),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Default)]
+struct TrackedBundles {
+    /// 等待结果的 bundle
+    waiting: HashMap<String, (Instant, mpsc::UnboundedSender<BundleOutcome>)>,
+    /// 先于等待者到达的结果
+    unclaimed: HashMap<String, (Instant, Vec<BundleOutcome>)>,
+}
+
+impl TrackedBundles {
+    fn prune(&mut self) {
+        self.waiting.retain(|_, (created_at, sender)| {
+            created_at.elapsed() < BUNDLE_RESULT_TTL && !sender.is_closed()
+        });
+        self.unclaimed.retain(|_, (created_at, _)| created_at.elapsed() < BUNDLE_RESULT_TTL);
+    }
+}
+
+/// 通过 SubscribeBundleResults 跟踪 bundle 结果
+///
+/// 保持一条结果流，按 bundle UUID 把结果分发给等待者，断开后自动重新订阅。
+#[derive(Default)]
+pub struct BundleResultTracker {
+    bundles: Mutex<TrackedBundles>,
+}
+
+impl BundleResultTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 启动后台任务订阅结果流
+    pub fn start(self: &Arc<Self>, searcher_client: AuthenticatedSearcherClient) {
+        let tracker = self.clone();
+        tokio::spawn(async move {
+            loop {
+                let mut searcher_client = searcher_client.clone();
+                match searcher_client
+                    .subscribe_bundle_results(SubscribeBundleResultsRequest {})
+                    .await
+                {
+                    Ok(response) => {
+                        let mut stream = response.into_inner();
+                        loop {
+                            match stream.message().await {
+                                Ok(Some(result)) => tracker.record(result),
+                                Ok(None) => break,
+                                Err(e) => {
+                                    eprintln!(" jito bundle结果流断开: {:?}", e);
+                                    break;
+                                }
+                            }
+                        }
+                    }
+                    Err(e) => eprintln!(" jito bundle结果订阅失败: {:?}", e),
+                }
+                tokio::time::sleep(RESUBSCRIBE_INTERVAL).await;
+            }
+        });
+    }
+
+    /// 分发一条 bundle 结果
+    pub fn record(&self, result: BundleResult) {
+        let Some(outcome) = result.result.and_then(BundleOutcome::from_result) else {
+            return;
+        };
+        let mut bundles = self.bundles.lock().unwrap();
+        bundles.prune();
+        if let Some((_, sender)) = bundles.waiting.get(&result.bundle_id) {
+            if sender.send(outcome.clone()).is_ok() {
+                return;
+            }
+            bundles.waiting.remove(&result.bundle_id);
+        }
+        bundles
+            .unclaimed
+            .entry(result.bundle_id)
+            .or_insert_with(|| (Instant::now(), Vec::new()))
+            .1
+            .push(outcome);
+    }
+
+    /// 订阅一个 bundle 的结果，包括订阅前已收到的结果
+    pub fn subscribe(&self, bundle_id: &str) -> mpsc::UnboundedReceiver<BundleOutcome> {
+        let (sender, receiver) = mpsc::unbounded_channel();
+        let mut bundles = self.bundles.lock().unwrap();
+        bundles.prune();
+        if let Some((_, outcomes)) = bundles.unclaimed.remove(bundle_id) {
+            for outcome in outcomes {
+                let _ = sender.send(outcome);
+            }
+        }
+        bundles.waiting.insert(bundle_id.to_string(), (Instant::now(), sender));
+        receiver
+    }
+
+    /// 等待 `landed` 完成，期间 bundle 被拒绝或未上链时立即返回错误，成功时返回最后收到的结果
+    pub async fn track(
+        &self,
+        bundle_id: &str,
+        landed: impl Future<Output = Result<()>>,
+    ) -> Result<Option<BundleOutcome>> {
+        let mut receiver = self.subscribe(bundle_id);
+        let mut latest = None;
+        tokio::pin!(landed);
+        loop {
+            tokio::select! {
+                result = &mut landed => {
+                    result?;
+                    break;
+                }
+                // 超过保留时间被清理后不再有结果，只等待 `landed`
+                Some(outcome) = receiver.recv() => {
+                    if let Some(error) = outcome.clone().into_error() {
+                        return Err(error.into());
+                    }
+                    latest = Some(outcome);
+                }
+            }
+        }
+        while let Ok(outcome) = receiver.try_recv() {
+            if let Some(error) = outcome.clone().into_error() {
+                return Err(error.into());
+            }
+            latest = Some(outcome);
+        }
+        Ok(latest)
+    }
+
+    /// 等待 block engine 对 bundle 的第一个结果，被拒绝或未上链时返回错误，`timeout` 内没有结果时返回 None
+    pub async fn first_outcome(
+        &self,
+        bundle_id: &str,
+        timeout: Duration,
+    ) -> Result<Option<BundleOutcome>> {
+        let mut receiver = self.subscribe(bundle_id);
+        match tokio::time::timeout(timeout, receiver.recv()).await {
+            Ok(Some(outcome)) => match outcome.clone().into_error() {
+                Some(error) => Err(error.into()),
+                None => Ok(Some(outcome)),
+            },
+            _ => Ok(None),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protos::bundle::{Accepted, Rejected, SimulationFailure};
+
+    #[tokio::test]
+    async fn test_results_before_and_after_subscribe() {
+        let tracker = BundleResultTracker::new();
+        tracker.record(BundleResult {
+            bundle_id: "early".to_string(),
+            result: Some(bundle_result::Result::Accepted(Accepted {
+                slot: 1,
+                validator_identity: "validator".to_string(),
+            })),
+        });
+        let mut receiver = tracker.subscribe("early");
+        assert_eq!(
+            receiver.recv().await,
+            Some(BundleOutcome::Accepted { slot: 1, validator_identity: "validator".to_string() })
+        );
+
+        tracker.record(BundleResult {
+            bundle_id: "early".to_string(),
+            result: Some(bundle_result::Result::Rejected(Rejected {
+                reason: Some(Reason::SimulationFailure(SimulationFailure {
+                    tx_signature: "sig".to_string(),
+                    msg: None,
+                })),
+            })),
+        });
+        let error = receiver.recv().await.and_then(BundleOutcome::into_error);
+        assert_eq!(error, Some(BundleRejectionError::SimulationFailure("sig".to_string(), None)));
+    }
+
+    #[tokio::test]
+    async fn test_track_returns_latest_outcome_or_rejection() {
+        let tracker = BundleResultTracker::new();
+        let accepted =
+            BundleOutcome::Accepted { slot: 1, validator_identity: "validator".to_string() };
+        tracker.record(BundleResult {
+            bundle_id: "landed".to_string(),
+            result: Some(bundle_result::Result::Accepted(Accepted {
+                slot: 1,
+                validator_identity: "validator".to_string(),
+            })),
+        });
+        let outcome = tracker.track("landed", async { Ok(()) }).await.unwrap();
+        assert_eq!(outcome, Some(accepted.clone()));
+
+        tracker.record(BundleResult {
+            bundle_id: "rejected".to_string(),
+            result: Some(bundle_result::Result::Rejected(Rejected {
+                reason: Some(Reason::SimulationFailure(SimulationFailure {
+                    tx_signature: "sig".to_string(),
+                    msg: None,
+                })),
+            })),
+        });
+        // 不必等到确认超时
+        let error = tracker.track("rejected", std::future::pending()).await.unwrap_err();
+        assert_eq!(
+            error.downcast_ref::<BundleRejectionError>(),
+            Some(&BundleRejectionError::SimulationFailure("sig".to_string(), None))
+        );
+        assert_eq!(
+            tracker.first_outcome("unknown", Duration::from_millis(10)).await.unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn test_from_inflight_status() {
+        assert_eq!(
+            BundleOutcome::from_inflight_status("Landed", Some(7)),
+            Some(BundleOutcome::Landed { slot: 7 })
+        );
+        assert!(BundleOutcome::from_inflight_status("Failed", None)
+            .unwrap()
+            .into_error()
+            .is_some());
+        assert_eq!(BundleOutcome::from_inflight_status("Pending", None), None);
+        assert_eq!(BundleOutcome::from_inflight_status("Invalid", None), None);
+    }
+}
@@ -0,0 +1,198 @@
+use rand::seq::IndexedRandom;
+use std::{sync::Arc, time::Instant};
+
+use anyhow::Result;
+use solana_sdk::{signature::Keypair, transaction::VersionedTransaction};
+use tokio::sync::OnceCell;
+use tonic::{codegen::InterceptedService, transport::Channel};
+use crate::swqos::{jito_bundle_tracker::{BundleOutcome, BundleResultTracker}, SwqosType, TradeType};
+use crate::swqos::SwqosClientTrait;
+
+use crate::{
+    constants::swqos::JITO_TIP_ACCOUNTS,
+    protos::{
+        auth::{auth_service_client::AuthServiceClient, Role},
+        bundle::Bundle,
+        convert::proto_packet_from_versioned_tx,
+        searcher::{searcher_service_client::SearcherServiceClient, SendBundleRequest},
+        searcher_client::create_grpc_channel,
+        token_authenticator::ClientInterceptor,
+    },
+    swqos::confirmation::ConfirmationTracker,
+};
+
+pub type AuthenticatedSearcherClient = SearcherServiceClient<InterceptedService<Channel, ClientInterceptor>>;
+
+/// 通过 block engine gRPC 发送 bundle 的 Jito 客户端
+///
+/// 整个生命周期复用同一条认证后的 channel，token 由 `ClientInterceptor` 在后台刷新。
+/// 认证后同时订阅 bundle 结果，被拒绝或未上链的 bundle 立即返回 `BundleRejectionError`，
+/// 成功时返回收到的 `BundleOutcome`。
+pub struct JitoGrpcClient {
+    pub endpoint: String,
+    pub confirmation_tracker: Arc<ConfirmationTracker>,
+    /// 用于 block engine 挑战认证的密钥，为空时不认证
+    auth_keypair: Option<Arc<Keypair>>,
+    searcher_client: Arc<OnceCell<AuthenticatedSearcherClient>>,
+    bundle_tracker: Arc<BundleResultTracker>,
+}
+
+#[async_trait::async_trait]
+impl SwqosClientTrait for JitoGrpcClient {
+    async fn send_transaction(&self, trade_type: TradeType, transaction: &VersionedTransaction) -> Result<()> {
+        self.send_transaction(trade_type, transaction).await.map(|_| ())
+    }
+
+    async fn send_transactions(&self, trade_type: TradeType, transactions: &Vec<VersionedTransaction>) -> Result<()> {
+        self.send_transactions(trade_type, transactions).await.map(|_| ())
+    }
+
+    async fn send_transaction_with_outcome(&self, trade_type: TradeType, transaction: &VersionedTransaction) -> Result<Option<BundleOutcome>> {
+        self.send_transaction(trade_type, transaction).await
+    }
+
+    async fn send_transactions_with_outcome(&self, trade_type: TradeType, transactions: &Vec<VersionedTransaction>) -> Result<Option<BundleOutcome>> {
+        self.send_transactions(trade_type, transactions).await
+    }
+
+    fn get_tip_account(&self) -> Result<String> {
+        if let Some(acc) = JITO_TIP_ACCOUNTS.choose(&mut rand::rng()) {
+            Ok(acc.to_string())
+        } else {
+            Err(anyhow::anyhow!("no valid tip accounts found"))
+        }
+    }
+
+    fn get_swqos_type(&self) -> SwqosType {
+        SwqosType::Jito
+    }
+
+    fn supports_bundles(&self) -> bool {
+        true
+    }
+}
+
+impl JitoGrpcClient {
+    /// `auth_keypair` 为 base58 编码的 block engine 认证密钥，传空字符串则不认证
+    pub fn new(confirmation_tracker: Arc<ConfirmationTracker>, endpoint: String, auth_keypair: String) -> Result<Self> {
+        let auth_keypair = if auth_keypair.is_empty() {
+            None
+        } else {
+            let bytes = bs58::decode(&auth_keypair).into_vec()?;
+            Some(Arc::new(Keypair::try_from(bytes.as_slice())?))
+        };
+        let client = Self {
+            confirmation_tracker,
+            endpoint,
+            auth_keypair,
+            searcher_client: Arc::new(OnceCell::new()),
+            bundle_tracker: Arc::new(BundleResultTracker::new()),
+        };
+
+        // 提前建立连接并完成认证，避免第一笔交易等待握手
+        if let Ok(handle) = tokio::runtime::Handle::try_current() {
+            let searcher_client = client.searcher_client.clone();
+            let endpoint = client.endpoint.clone();
+            let auth_keypair = client.auth_keypair.clone();
+            let bundle_tracker = client.bundle_tracker.clone();
+            handle.spawn(async move {
+                if let Err(e) = searcher_client
+                    .get_or_try_init(|| {
+                        Self::connect(&endpoint, auth_keypair.as_ref(), &bundle_tracker)
+                    })
+                    .await
+                {
+                    eprintln!(" jito grpc连接失败: {:?}", e);
+                }
+            });
+        }
+
+        Ok(client)
+    }
+
+    async fn connect(
+        endpoint: &str,
+        auth_keypair: Option<&Arc<Keypair>>,
+        bundle_tracker: &Arc<BundleResultTracker>,
+    ) -> Result<AuthenticatedSearcherClient> {
+        let channel = create_grpc_channel(endpoint).await?;
+        let Some(keypair) = auth_keypair else {
+            return Ok(SearcherServiceClient::with_interceptor(channel, ClientInterceptor::without_auth()));
+        };
+        let interceptor =
+            ClientInterceptor::new(AuthServiceClient::new(channel.clone()), keypair, Role::Searcher).await?;
+        let searcher_client = SearcherServiceClient::with_interceptor(channel, interceptor);
+        // bundle 结果只推送给认证过的 searcher
+        bundle_tracker.start(searcher_client.clone());
+        Ok(searcher_client)
+    }
+
+    /// 已建立的 searcher 客户端，连接失败时下次调用重新连接
+    async fn searcher_client(&self) -> Result<AuthenticatedSearcherClient> {
+        let searcher_client = self
+            .searcher_client
+            .get_or_try_init(|| {
+                Self::connect(&self.endpoint, self.auth_keypair.as_ref(), &self.bundle_tracker)
+            })
+            .await?;
+        Ok(searcher_client.clone())
+    }
+
+    async fn send_bundle(&self, transactions: &[VersionedTransaction]) -> Result<String> {
+        let packets = transactions.iter().map(proto_packet_from_versioned_tx).collect();
+        let response = self
+            .searcher_client()
+            .await?
+            .send_bundle(SendBundleRequest { bundle: Some(Bundle { header: None, packets }) })
+            .await?;
+        Ok(response.into_inner().uuid)
+    }
+
+    /// 以单笔交易的 bundle 发送并等待确认，返回确认前收到的最后一个 bundle 结果
+    pub async fn send_transaction(&self, trade_type: TradeType, transaction: &VersionedTransaction) -> Result<Option<BundleOutcome>> {
+        let start_time = Instant::now();
+        let bundle_id = match self.send_bundle(std::slice::from_ref(transaction)).await {
+            Ok(bundle_id) => bundle_id,
+            Err(e) => {
+                eprintln!(" jito grpc{}提交失败: {:?}", trade_type, e);
+                return Err(e);
+            }
+        };
+        println!(" jito grpc{}提交: {:?}", trade_type, start_time.elapsed());
+
+        // bundle 被拒绝或未上链时不必等到确认超时
+        let start_time: Instant = Instant::now();
+        let confirmation = self.confirmation_tracker.wait(transaction);
+        let outcome = match self.bundle_tracker.track(&bundle_id, async { confirmation.await.map(|_| ()) }).await {
+            Ok(outcome) => outcome,
+            Err(e) => {
+                println!(" jito grpc{}确认失败: {:?}, {}", trade_type, start_time.elapsed(), e);
+                return Err(e);
+            },
+        };
+
+        println!(" jito grpc{}确认: {:?}", trade_type, start_time.elapsed());
+
+        Ok(outcome)
+    }
+
+    /// 发送 bundle，认证时等待 block engine 给出第一个结果，最多等待确认超时时间
+    pub async fn send_transactions(&self, trade_type: TradeType, transactions: &[VersionedTransaction]) -> Result<Option<BundleOutcome>> {
+        let start_time = Instant::now();
+        let bundle_id = match self.send_bundle(transactions).await {
+            Ok(bundle_id) => bundle_id,
+            Err(e) => {
+                eprintln!(" jito grpc{}提交失败: {:?}", trade_type, e);
+                return Err(e);
+            }
+        };
+        println!(" jito grpc{}提交: {:?}", trade_type, start_time.elapsed());
+
+        // 未认证时收不到 bundle 结果
+        if self.auth_keypair.is_none() {
+            return Ok(None);
+        }
+        let timeout = self.confirmation_tracker.config().timeout;
+        self.bundle_tracker.first_outcome(&bundle_id, timeout).await
+    }
+}
@@ -1,19 +1,27 @@
 pub mod common;
+pub mod confirmation;
 pub mod solana_rpc;
 pub mod jito;
+pub mod jito_grpc;
+pub mod jito_bundle_tracker;
 pub mod nextblock;
+pub mod nextblock_grpc;
 pub mod zeroslot;
 pub mod temporal;
 pub mod bloxroute;
 pub mod node1;
 pub mod flashblock;
+pub mod tpu;
+pub mod health;
+pub mod rate_limit;
+pub mod yellowstone_landing;
 
 use std::sync::Arc;
 
 use solana_sdk::{commitment_config::CommitmentConfig, transaction::VersionedTransaction};
 use tokio::sync::RwLock;
 
-use anyhow::Result;
+use anyhow::{anyhow, Result};
 
 use crate::{
     common::SolanaRpcClient, 
@@ -29,12 +37,18 @@ use crate::{
     swqos::{
         bloxroute::BloxrouteClient, 
         jito::JitoClient, 
+        jito_bundle_tracker::BundleOutcome, 
+        jito_grpc::JitoGrpcClient, 
         nextblock::NextBlockClient, 
+        nextblock_grpc::{NextBlockGrpcClient, NextBlockOptions}, 
+        confirmation::ConfirmationTracker, 
         solana_rpc::SolRpcClient, 
         temporal::TemporalClient, 
         zeroslot::ZeroSlotClient, 
         node1::Node1Client, 
-        flashblock::FlashBlockClient
+        flashblock::FlashBlockClient,
+        rate_limit::{RateLimit, RateLimitedClient},
+        tpu::TpuClient
     }
 };
 
@@ -72,6 +86,17 @@ pub enum SwqosType {
     Node1,
     FlashBlock,
     Default,
+    /// 直接发送到 leader 的 TPU 端口
+    Tpu,
+    /// 用户自定义的服务，参数为名称
+    Custom(String),
+}
+
+impl SwqosType {
+    /// 不需要小费转账的服务，交易按 RPC 方式构建
+    pub fn is_tip_free(&self) -> bool {
+        matches!(self, SwqosType::Default | SwqosType::Tpu)
+    }
 }
 
 pub type SwqosClient = dyn SwqosClientTrait + Send + Sync + 'static;
@@ -82,6 +107,24 @@ pub trait SwqosClientTrait {
     async fn send_transactions(&self, trade_type: TradeType, transactions: &Vec<VersionedTransaction>) -> Result<()>;
     fn get_tip_account(&self) -> Result<String>;
     fn get_swqos_type(&self) -> SwqosType;
+    /// 是否支持原子执行的多交易 bundle
+    fn supports_bundles(&self) -> bool {
+        false
+    }
+    /// 取得一次请求的速率预算，必要时排队等待，超出预算返回 `SwqosThrottledError`；未限流的服务总是成功
+    async fn acquire(&self, _trade_type: TradeType) -> Result<()> {
+        Ok(())
+    }
+    /// 发送并等待交易确认，交易以 bundle 发送时同时返回 block engine 给出的 bundle 结果
+    async fn send_transaction_with_outcome(&self, trade_type: TradeType, transaction: &VersionedTransaction) -> Result<Option<BundleOutcome>> {
+        self.send_transaction(trade_type, transaction).await?;
+        Ok(None)
+    }
+    /// 发送 bundle，跟踪 bundle 结果的服务返回 block engine 给出的第一个结果
+    async fn send_transactions_with_outcome(&self, trade_type: TradeType, transactions: &Vec<VersionedTransaction>) -> Result<Option<BundleOutcome>> {
+        self.send_transactions(trade_type, transactions).await?;
+        Ok(None)
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
@@ -94,58 +137,189 @@ pub enum SwqosRegion {
     London,
     LosAngeles,
     Default,
+    /// 自定义 endpoint，例如私有中继、测试环境或本地 mock
+    Custom(String),
+}
+
+impl SwqosRegion {
+    /// 在 `constants::swqos` endpoint 数组中的序号，`Custom` 不使用数组
+    fn index(&self) -> usize {
+        match self {
+            SwqosRegion::NewYork => 0,
+            SwqosRegion::Frankfurt => 1,
+            SwqosRegion::Amsterdam => 2,
+            SwqosRegion::SLC => 3,
+            SwqosRegion::Tokyo => 4,
+            SwqosRegion::London => 5,
+            SwqosRegion::LosAngeles => 6,
+            SwqosRegion::Default | SwqosRegion::Custom(_) => 7,
+        }
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum SwqosConfig {
     Default(String),
     Jito(String, SwqosRegion),
+    /// Jito block engine gRPC，参数为 base58 编码的认证密钥（为空则不认证）和区域
+    JitoGrpc(String, SwqosRegion),
     NextBlock(String, SwqosRegion),
+    /// NextBlock gRPC，参数为 API Token、区域和发送选项
+    NextBlockGrpc(String, SwqosRegion, NextBlockOptions),
     Bloxroute(String, SwqosRegion),
     Temporal(String, SwqosRegion),
     ZeroSlot(String, SwqosRegion),
     Node1(String, SwqosRegion),
     FlashBlock(String, SwqosRegion),
+    /// 通过 QUIC 直接发送到 leader，参数为 base58 编码的质押身份密钥（为空则不使用质押）
+    Tpu(String),
+    /// 按速率限制发送的服务，通过 `with_rate_limit` 创建
+    RateLimited(Box<SwqosConfig>, RateLimit),
 }
 
 impl SwqosConfig {
     pub fn get_endpoint(swqos_type: SwqosType, region: SwqosRegion) -> String {
+        let region = match region {
+            SwqosRegion::Custom(endpoint) => return endpoint,
+            region => region.index(),
+        };
         match swqos_type {
-            SwqosType::Jito => SWQOS_ENDPOINTS_JITO[region as usize].to_string(),
-            SwqosType::NextBlock => SWQOS_ENDPOINTS_NEXTBLOCK[region as usize].to_string(),
-            SwqosType::ZeroSlot => SWQOS_ENDPOINTS_ZERO_SLOT[region as usize].to_string(),
-            SwqosType::Temporal => SWQOS_ENDPOINTS_TEMPORAL[region as usize].to_string(),
-            SwqosType::Bloxroute => SWQOS_ENDPOINTS_BLOX[region as usize].to_string(),
-            SwqosType::Node1 => SWQOS_ENDPOINTS_NODE1[region as usize].to_string(),
-            SwqosType::FlashBlock => SWQOS_ENDPOINTS_FLASHBLOCK[region as usize].to_string(),
-            SwqosType::Default => "".to_string(),
+            SwqosType::Jito => SWQOS_ENDPOINTS_JITO[region].to_string(),
+            SwqosType::NextBlock => SWQOS_ENDPOINTS_NEXTBLOCK[region].to_string(),
+            SwqosType::ZeroSlot => SWQOS_ENDPOINTS_ZERO_SLOT[region].to_string(),
+            SwqosType::Temporal => SWQOS_ENDPOINTS_TEMPORAL[region].to_string(),
+            SwqosType::Bloxroute => SWQOS_ENDPOINTS_BLOX[region].to_string(),
+            SwqosType::Node1 => SWQOS_ENDPOINTS_NODE1[region].to_string(),
+            SwqosType::FlashBlock => SWQOS_ENDPOINTS_FLASHBLOCK[region].to_string(),
+            SwqosType::Default | SwqosType::Tpu | SwqosType::Custom(_) => "".to_string(),
+        }
+    }
+
+    pub fn swqos_type(&self) -> SwqosType {
+        match self {
+            SwqosConfig::Default(_) => SwqosType::Default,
+            SwqosConfig::Jito(..) | SwqosConfig::JitoGrpc(..) => SwqosType::Jito,
+            SwqosConfig::NextBlock(..) | SwqosConfig::NextBlockGrpc(..) => SwqosType::NextBlock,
+            SwqosConfig::Bloxroute(..) => SwqosType::Bloxroute,
+            SwqosConfig::Temporal(..) => SwqosType::Temporal,
+            SwqosConfig::ZeroSlot(..) => SwqosType::ZeroSlot,
+            SwqosConfig::Node1(..) => SwqosType::Node1,
+            SwqosConfig::FlashBlock(..) => SwqosType::FlashBlock,
+            SwqosConfig::Tpu(_) => SwqosType::Tpu,
+            SwqosConfig::RateLimited(config, _) => config.swqos_type(),
+        }
+    }
+
+    /// 配置的区域，`Default` 和 `Tpu` 没有区域
+    pub fn region(&self) -> Option<SwqosRegion> {
+        match self {
+            SwqosConfig::Default(_) | SwqosConfig::Tpu(_) => None,
+            SwqosConfig::Jito(_, region)
+            | SwqosConfig::JitoGrpc(_, region)
+            | SwqosConfig::NextBlock(_, region)
+            | SwqosConfig::NextBlockGrpc(_, region, _)
+            | SwqosConfig::Bloxroute(_, region)
+            | SwqosConfig::Temporal(_, region)
+            | SwqosConfig::ZeroSlot(_, region)
+            | SwqosConfig::Node1(_, region)
+            | SwqosConfig::FlashBlock(_, region) => Some(region.clone()),
+            SwqosConfig::RateLimited(config, _) => config.region(),
+        }
+    }
+
+    /// 替换区域，`Default` 和 `Tpu` 原样返回
+    pub fn with_region(mut self, new_region: SwqosRegion) -> Self {
+        match &mut self {
+            SwqosConfig::Default(_) | SwqosConfig::Tpu(_) => {}
+            SwqosConfig::Jito(_, region)
+            | SwqosConfig::JitoGrpc(_, region)
+            | SwqosConfig::NextBlock(_, region)
+            | SwqosConfig::NextBlockGrpc(_, region, _)
+            | SwqosConfig::Bloxroute(_, region)
+            | SwqosConfig::Temporal(_, region)
+            | SwqosConfig::ZeroSlot(_, region)
+            | SwqosConfig::Node1(_, region)
+            | SwqosConfig::FlashBlock(_, region) => *region = new_region,
+            SwqosConfig::RateLimited(config, _) => **config = config.as_ref().clone().with_region(new_region),
+        }
+        self
+    }
+
+    /// 限制发送速率，超出预算时按 `RateLimit::max_wait` 排队或跳过该服务
+    pub fn with_rate_limit(self, rate_limit: RateLimit) -> Self {
+        match self {
+            SwqosConfig::RateLimited(config, _) => SwqosConfig::RateLimited(config, rate_limit),
+            config => SwqosConfig::RateLimited(Box::new(config), rate_limit),
+        }
+    }
+
+    pub fn rate_limit(&self) -> Option<&RateLimit> {
+        match self {
+            SwqosConfig::RateLimited(_, rate_limit) => Some(rate_limit),
+            _ => None,
         }
     }
 
-    pub fn get_swqos_client(rpc_url: String, commitment: CommitmentConfig, swqos_config: SwqosConfig) -> Arc<SwqosClient> {
-        match swqos_config {
+    /// 配置对应的 endpoint，`Default` 为 RPC 地址，`Tpu` 随 leader 变化没有固定 endpoint
+    pub fn endpoint(&self) -> String {
+        match (self, self.region()) {
+            (SwqosConfig::Default(endpoint), _) => endpoint.clone(),
+            (SwqosConfig::RateLimited(config, _), _) => config.endpoint(),
+            (_, Some(region)) => SwqosConfig::get_endpoint(self.swqos_type(), region),
+            (_, None) => "".to_string(),
+        }
+    }
+
+    /// 创建配置对应的客户端，所有客户端通过 `confirmation_tracker` 等待确认
+    ///
+    /// 认证密钥或 API token 格式错误时返回错误
+    pub fn get_swqos_client(confirmation_tracker: Arc<ConfirmationTracker>, commitment: CommitmentConfig, swqos_config: SwqosConfig) -> Result<Arc<SwqosClient>> {
+        let client: Arc<SwqosClient> = match swqos_config {
+            SwqosConfig::RateLimited(config, rate_limit) => {
+                let client = SwqosConfig::get_swqos_client(confirmation_tracker, commitment, *config)?;
+                Arc::new(RateLimitedClient::new(client, rate_limit))
+            },
             SwqosConfig::Jito(auth_token, region) => {
                 let endpoint = SwqosConfig::get_endpoint(SwqosType::Jito, region);
                 let jito_client = JitoClient::new(
-                    rpc_url.clone(),
+                    confirmation_tracker,
                     endpoint,
                     auth_token
                 );
                 Arc::new(jito_client)
             }
+            SwqosConfig::JitoGrpc(auth_keypair, region) => {
+                let endpoint = SwqosConfig::get_endpoint(SwqosType::Jito, region);
+                let jito_grpc_client = JitoGrpcClient::new(
+                    confirmation_tracker,
+                    endpoint,
+                    auth_keypair
+                ).map_err(|e| anyhow!("Invalid Jito gRPC auth keypair: {}", e))?;
+                Arc::new(jito_grpc_client)
+            }
             SwqosConfig::NextBlock(auth_token, region) => {
                 let endpoint = SwqosConfig::get_endpoint(SwqosType::NextBlock, region);
                 let nextblock_client = NextBlockClient::new(
-                    rpc_url.clone(),
+                    confirmation_tracker,
                     endpoint.to_string(),
                     auth_token
                 );
                 Arc::new(nextblock_client)
             },
+            SwqosConfig::NextBlockGrpc(auth_token, region, options) => {
+                let endpoint = SwqosConfig::get_endpoint(SwqosType::NextBlock, region);
+                let nextblock_grpc_client = NextBlockGrpcClient::new(
+                    confirmation_tracker,
+                    endpoint,
+                    auth_token,
+                    options
+                ).map_err(|e| anyhow!("Invalid NextBlock gRPC endpoint or API token: {}", e))?;
+                Arc::new(nextblock_grpc_client)
+            },
             SwqosConfig::ZeroSlot(auth_token, region) => {
                 let endpoint = SwqosConfig::get_endpoint(SwqosType::ZeroSlot, region);
                 let zeroslot_client = ZeroSlotClient::new(
-                    rpc_url.clone(),
+                    confirmation_tracker,
                     endpoint.to_string(),
                     auth_token
                 );
@@ -154,7 +328,7 @@ impl SwqosConfig {
             SwqosConfig::Temporal(auth_token, region) => {  
                 let endpoint = SwqosConfig::get_endpoint(SwqosType::Temporal, region);
                 let temporal_client = TemporalClient::new(
-                    rpc_url.clone(),
+                    confirmation_tracker,
                     endpoint.to_string(),
                     auth_token
                 );
@@ -163,7 +337,7 @@ impl SwqosConfig {
             SwqosConfig::Bloxroute(auth_token, region) => { 
                 let endpoint = SwqosConfig::get_endpoint(SwqosType::Bloxroute, region);
                 let bloxroute_client = BloxrouteClient::new(
-                    rpc_url.clone(),
+                    confirmation_tracker,
                     endpoint.to_string(),
                     auth_token
                 );
@@ -172,7 +346,7 @@ impl SwqosConfig {
             SwqosConfig::Node1(auth_token, region) => {
                 let endpoint = SwqosConfig::get_endpoint(SwqosType::Node1, region);
                 let node1_client = Node1Client::new(
-                    rpc_url.clone(),
+                    confirmation_tracker,
                     endpoint.to_string(),
                     auth_token
                 );
@@ -181,20 +355,51 @@ impl SwqosConfig {
             SwqosConfig::FlashBlock(auth_token, region) => {
                 let endpoint = SwqosConfig::get_endpoint(SwqosType::FlashBlock, region);
                 let flashblock_client = FlashBlockClient::new(
-                    rpc_url.clone(),
+                    confirmation_tracker,
                     endpoint.to_string(),
                     auth_token
                 );
                 Arc::new(flashblock_client)
             },
+            SwqosConfig::Tpu(identity) => {
+                let rpc_url = confirmation_tracker.rpc().url();
+                let tpu_client = TpuClient::new(
+                    confirmation_tracker,
+                    rpc_url,
+                    identity
+                ).map_err(|e| anyhow!("Invalid TPU identity keypair: {}", e))?;
+                Arc::new(tpu_client)
+            },
             SwqosConfig::Default(endpoint) => {
                 let rpc = SolanaRpcClient::new_with_commitment(
                     endpoint,
                     commitment
                 );   
-                let rpc_client = SolRpcClient::new(Arc::new(rpc));
+                let rpc_client = SolRpcClient::new(Arc::new(rpc), confirmation_tracker);
                 Arc::new(rpc_client)
             }
+        };
+        Ok(client)
+    }
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_custom_region_endpoint() {
+        let endpoint = "http://127.0.0.1:8899".to_string();
+        for swqos_type in [SwqosType::Jito, SwqosType::NextBlock, SwqosType::Bloxroute, SwqosType::Custom("relay".to_string())] {
+            assert_eq!(SwqosConfig::get_endpoint(swqos_type, SwqosRegion::Custom(endpoint.clone())), endpoint);
         }
+        assert_eq!(SwqosConfig::get_endpoint(SwqosType::Jito, SwqosRegion::Frankfurt), SWQOS_ENDPOINTS_JITO[1]);
+
+        let config = SwqosConfig::Jito("".to_string(), SwqosRegion::Custom(endpoint.clone()));
+        assert_eq!(config.endpoint(), endpoint);
+        // 限流包装不改变 endpoint
+        let rate_limited = SwqosConfig::RateLimited(Box::new(config.clone()), rate_limit::RateLimit::per_second(5));
+        assert_eq!(rate_limited.endpoint(), endpoint);
+        // 切换区域后不再使用自定义 endpoint
+        assert_eq!(config.with_region(SwqosRegion::NewYork).endpoint(), SWQOS_ENDPOINTS_JITO[0]);
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,197 @@
+use crate::swqos::common::serialize_transaction_and_encode;
+use rand::seq::IndexedRandom;
+use std::{sync::Arc, time::{Duration, Instant}};
+
+use solana_transaction_status::UiTransactionEncoding;
+
+use anyhow::Result;
+use solana_sdk::transaction::VersionedTransaction;
+use tonic::{
+    codegen::InterceptedService,
+    metadata::{Ascii, MetadataValue},
+    service::Interceptor,
+    transport::{Channel, ClientTlsConfig, Endpoint},
+    Request, Status,
+};
+use crate::swqos::{SwqosType, TradeType};
+use crate::swqos::SwqosClientTrait;
+
+use crate::{
+    constants::swqos::NEXTBLOCK_TIP_ACCOUNTS,
+    protos::nextblock_grpc::{
+        api_client::ApiClient, PostSubmitBatchRequest, PostSubmitRequest, PostSubmitRequestEntry,
+        SubmitStrategy, TransactionMessage,
+    },
+    swqos::confirmation::ConfirmationTracker,
+};
+
+/// NextBlock 发送选项
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct NextBlockOptions {
+    /// 只通过防夹的验证者打包
+    pub front_running_protection: bool,
+    /// 以 bundle 提交，交易失败时不上链，批量交易遇到错误即停止
+    pub revert_protection: bool,
+}
+
+/// 在每个请求上添加 NextBlock 的 API Token
+#[derive(Clone)]
+pub struct NextBlockAuthInterceptor {
+    auth_token: MetadataValue<Ascii>,
+}
+
+impl Interceptor for NextBlockAuthInterceptor {
+    fn call(&mut self, mut request: Request<()>) -> Result<Request<()>, Status> {
+        request.metadata_mut().insert("authorization", self.auth_token.clone());
+        Ok(request)
+    }
+}
+
+type NextBlockApiClient = ApiClient<InterceptedService<Channel, NextBlockAuthInterceptor>>;
+
+/// 通过 gRPC 发送交易的 NextBlock 客户端，整个生命周期复用同一条 channel
+#[derive(Clone)]
+pub struct NextBlockGrpcClient {
+    pub endpoint: String,
+    pub options: NextBlockOptions,
+    pub confirmation_tracker: Arc<ConfirmationTracker>,
+    api_client: NextBlockApiClient,
+}
+
+#[async_trait::async_trait]
+impl SwqosClientTrait for NextBlockGrpcClient {
+    async fn send_transaction(&self, trade_type: TradeType, transaction: &VersionedTransaction) -> Result<()> {
+        self.send_transaction(trade_type, transaction).await
+    }
+
+    async fn send_transactions(&self, trade_type: TradeType, transactions: &Vec<VersionedTransaction>) -> Result<()> {
+        self.send_transactions(trade_type, transactions).await
+    }
+
+    fn get_tip_account(&self) -> Result<String> {
+        let tip_account = *NEXTBLOCK_TIP_ACCOUNTS.choose(&mut rand::rng()).or_else(|| NEXTBLOCK_TIP_ACCOUNTS.first()).unwrap();
+        Ok(tip_account.to_string())
+    }
+
+    fn get_swqos_type(&self) -> SwqosType {
+        SwqosType::NextBlock
+    }
+
+    fn supports_bundles(&self) -> bool {
+        self.options.revert_protection
+    }
+}
+
+impl NextBlockGrpcClient {
+    pub fn new(confirmation_tracker: Arc<ConfirmationTracker>, endpoint: String, auth_token: String, options: NextBlockOptions) -> Result<Self> {
+        let mut channel = Endpoint::from_shared(endpoint.clone())?
+            .tcp_nodelay(true)
+            .tcp_keepalive(Some(Duration::from_secs(10)))
+            .connect_timeout(Duration::from_secs(5))
+            .timeout(Duration::from_secs(10))
+            .http2_keep_alive_interval(Duration::from_secs(10))
+            .keep_alive_while_idle(true);
+        if endpoint.starts_with("https") {
+            channel = channel.tls_config(ClientTlsConfig::new().with_native_roots())?;
+        }
+        // 首次请求时建立连接，断开后自动重连
+        let interceptor = NextBlockAuthInterceptor { auth_token: auth_token.parse()? };
+        let api_client = ApiClient::with_interceptor(channel.connect_lazy(), interceptor);
+        Ok(Self { confirmation_tracker, endpoint, options, api_client })
+    }
+
+    pub async fn send_transaction(&self, trade_type: TradeType, transaction: &VersionedTransaction) -> Result<()> {
+        if self.options.revert_protection {
+            // 单笔交易也以 bundle 提交，失败时不上链
+            return self.submit_batch(trade_type, std::slice::from_ref(transaction), true).await;
+        }
+
+        let start_time = Instant::now();
+        let (content, _) = serialize_transaction_and_encode(transaction, UiTransactionEncoding::Base64).await?;
+        println!(" 交易编码base64: {:?}", start_time.elapsed());
+
+        let request = PostSubmitRequest {
+            transaction: Some(TransactionMessage { content, is_cleanup: false }),
+            skip_pre_flight: true,
+            front_running_protection: Some(self.options.front_running_protection),
+            experimental_front_running_protection: None,
+            snipe_transaction: None,
+        };
+        match self.api_client.clone().post_submit_v2(request).await {
+            Ok(_) => println!(" nextblock grpc{}提交: {:?}", trade_type, start_time.elapsed()),
+            Err(e) => {
+                eprintln!(" nextblock grpc{}提交失败: {:?}", trade_type, e);
+                return Err(e.into());
+            }
+        }
+
+        self.confirm(trade_type, transaction).await
+    }
+
+    pub async fn send_transactions(&self, trade_type: TradeType, transactions: &[VersionedTransaction]) -> Result<()> {
+        self.submit_batch(trade_type, transactions, false).await
+    }
+
+    /// 一次请求提交多笔交易，`wait_confirmation` 时等待第一笔交易确认
+    async fn submit_batch(&self, trade_type: TradeType, transactions: &[VersionedTransaction], wait_confirmation: bool) -> Result<()> {
+        let start_time = Instant::now();
+        let mut entries = Vec::with_capacity(transactions.len());
+        for transaction in transactions {
+            let (content, _) = serialize_transaction_and_encode(transaction, UiTransactionEncoding::Base64).await?;
+            entries.push(PostSubmitRequestEntry {
+                transaction: Some(TransactionMessage { content, is_cleanup: false }),
+                skip_pre_flight: true,
+            });
+        }
+
+        let submit_strategy = if self.options.revert_protection {
+            SubmitStrategy::PAbortOnFirstError
+        } else {
+            SubmitStrategy::PSubmitAll
+        };
+        let request = PostSubmitBatchRequest {
+            entries,
+            submit_strategy: submit_strategy as i32,
+            use_bundle: Some(self.options.revert_protection),
+            front_running_protection: Some(self.options.front_running_protection),
+        };
+        let response = match self.api_client.clone().post_submit_batch_v2(request).await {
+            Ok(response) => response.into_inner(),
+            Err(e) => {
+                eprintln!(" nextblock grpc{}提交失败: {:?}", trade_type, e);
+                return Err(e.into());
+            }
+        };
+        let errors: Vec<String> = response
+            .transactions
+            .iter()
+            .filter(|entry| !entry.submitted)
+            .map(|entry| format!("{}: {}", entry.signature, entry.error))
+            .collect();
+        if !errors.is_empty() {
+            eprintln!(" nextblock grpc{}提交失败: {:?}", trade_type, errors);
+            return Err(anyhow::anyhow!("NextBlock rejected transactions: {:?}", errors));
+        }
+        println!(" nextblock grpc{}提交: {:?}", trade_type, start_time.elapsed());
+
+        match transactions.first() {
+            Some(transaction) if wait_confirmation => self.confirm(trade_type, transaction).await,
+            _ => Ok(()),
+        }
+    }
+
+    async fn confirm(&self, trade_type: TradeType, transaction: &VersionedTransaction) -> Result<()> {
+        let start_time: Instant = Instant::now();
+        match self.confirmation_tracker.wait(transaction).await {
+            Ok(_) => (),
+            Err(e) => {
+                println!(" nextblock grpc{}确认失败: {:?}", trade_type, start_time.elapsed());
+                return Err(e);
+            },
+        }
+
+        println!(" nextblock grpc{}确认: {:?}", trade_type, start_time.elapsed());
+
+        Ok(())
+    }
+}
@@ -1,4 +1,4 @@
-use crate::swqos::common::{poll_transaction_confirmation, serialize_transaction_and_encode};
+use crate::swqos::common::serialize_transaction_and_encode;
 use rand::seq::IndexedRandom;
 use reqwest::Client;
 use serde_json::json;
@@ -12,7 +12,7 @@ use solana_sdk::transaction::VersionedTransaction;
 use crate::swqos::{SwqosType, TradeType};
 use crate::swqos::SwqosClientTrait;
 
-use crate::{common::SolanaRpcClient, constants::swqos::NODE1_TIP_ACCOUNTS};
+use crate::{constants::swqos::NODE1_TIP_ACCOUNTS, swqos::confirmation::ConfirmationTracker};
 
 use tokio::task::JoinHandle;
 use std::sync::atomic::{AtomicBool, Ordering};
@@ -21,7 +21,7 @@ use std::sync::atomic::{AtomicBool, Ordering};
 pub struct Node1Client {
     pub endpoint: String,
     pub auth_token: String,
-    pub rpc_client: Arc<SolanaRpcClient>,
+    pub confirmation_tracker: Arc<ConfirmationTracker>,
     pub http_client: Client,
     pub ping_handle: Arc<tokio::sync::Mutex<Option<JoinHandle<()>>>>,
     pub stop_ping: Arc<AtomicBool>,
@@ -48,8 +48,7 @@ impl SwqosClientTrait for Node1Client {
 }
 
 impl Node1Client {
-    pub fn new(rpc_url: String, endpoint: String, auth_token: String) -> Self {
-        let rpc_client = SolanaRpcClient::new(rpc_url);
+    pub fn new(confirmation_tracker: Arc<ConfirmationTracker>, endpoint: String, auth_token: String) -> Self {
         let http_client = Client::builder()
             // 由于有 ping 机制，可以延长连接池空闲超时
             .pool_idle_timeout(Duration::from_secs(300)) // 5分钟，比 ping 间隔更长
@@ -65,7 +64,7 @@ impl Node1Client {
             .unwrap();
         
         let client = Self { 
-            rpc_client: Arc::new(rpc_client), 
+            confirmation_tracker, 
             endpoint, 
             auth_token, 
             http_client,
@@ -142,7 +141,7 @@ impl Node1Client {
 
     pub async fn send_transaction(&self, trade_type: TradeType, transaction: &VersionedTransaction) -> Result<()> {
         let start_time = Instant::now();
-        let (content, signature) = serialize_transaction_and_encode(transaction, UiTransactionEncoding::Base64).await?;
+        let (content, _) = serialize_transaction_and_encode(transaction, UiTransactionEncoding::Base64).await?;
         println!(" 交易编码base64: {:?}", start_time.elapsed());
 
         let request_body = serde_json::to_string(&json!({
@@ -175,7 +174,7 @@ impl Node1Client {
         }
 
         let start_time: Instant = Instant::now();
-        match poll_transaction_confirmation(&self.rpc_client, signature).await {
+        match self.confirmation_tracker.wait(transaction).await {
             Ok(_) => (),
             Err(e) => {
                 println!(" node1{}确认失败: {:?}", trade_type, start_time.elapsed());
@@ -0,0 +1,247 @@
+use std::{
+    sync::{Arc, Mutex, OnceLock},
+    time::{Duration, Instant},
+};
+
+use anyhow::Result;
+use solana_sdk::transaction::VersionedTransaction;
+use tokio::sync::broadcast;
+
+use crate::swqos::{
+    jito_bundle_tracker::BundleOutcome, SwqosClient, SwqosClientTrait, SwqosType, TradeType,
+};
+
+/// 服务的请求速率限制
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct RateLimit {
+    /// 每补充一个令牌的间隔
+    pub refill_interval: Duration,
+    /// 令牌桶容量，即允许的突发请求数
+    pub burst: u32,
+    /// 超出预算时最多排队等待多久，为 0 时直接跳过该服务
+    pub max_wait: Duration,
+}
+
+impl RateLimit {
+    /// 每秒 `requests` 个请求，突发不超过 `requests` 个，超出预算直接跳过
+    pub fn per_second(requests: u32) -> Self {
+        let requests = requests.max(1);
+        Self {
+            refill_interval: Duration::from_secs(1) / requests,
+            burst: requests,
+            max_wait: Duration::ZERO,
+        }
+    }
+
+    pub fn with_burst(mut self, burst: u32) -> Self {
+        self.burst = burst.max(1);
+        self
+    }
+
+    pub fn with_max_wait(mut self, max_wait: Duration) -> Self {
+        self.max_wait = max_wait;
+        self
+    }
+}
+
+/// 服务超出速率限制，本次请求没有发送
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
+#[error("{swqos_type:?} is over its rate limit, request skipped")]
+pub struct SwqosThrottledError {
+    pub swqos_type: SwqosType,
+}
+
+/// 限流记录广播的缓冲区大小
+const THROTTLE_CHANNEL_CAPACITY: usize = 1024;
+
+static THROTTLES: OnceLock<broadcast::Sender<SwqosThrottledError>> = OnceLock::new();
+
+fn throttle_sender() -> &'static broadcast::Sender<SwqosThrottledError> {
+    THROTTLES.get_or_init(|| broadcast::channel(THROTTLE_CHANNEL_CAPACITY).0)
+}
+
+/// 订阅所有交易中被限流跳过的服务，单笔交易跳过的服务见 `TradeOutcome::throttled`
+pub fn throttled() -> broadcast::Receiver<SwqosThrottledError> {
+    throttle_sender().subscribe()
+}
+
+#[derive(Debug)]
+struct BucketState {
+    tokens: f64,
+    updated: Instant,
+}
+
+/// 令牌桶，超出预算的请求预约未来的令牌排队，等待时间不超过 `max_wait`
+#[derive(Debug)]
+pub struct TokenBucket {
+    limit: RateLimit,
+    state: Mutex<BucketState>,
+}
+
+impl TokenBucket {
+    pub fn new(limit: RateLimit) -> Self {
+        let tokens = limit.burst as f64;
+        Self { limit, state: Mutex::new(BucketState { tokens, updated: Instant::now() }) }
+    }
+
+    pub fn limit(&self) -> &RateLimit {
+        &self.limit
+    }
+
+    /// 预约一个令牌，返回需要等待的时间，超过 `max_wait` 时返回 None 且不消耗令牌
+    pub fn reserve(&self) -> Option<Duration> {
+        self.reserve_at(Instant::now())
+    }
+
+    fn reserve_at(&self, now: Instant) -> Option<Duration> {
+        let mut state = self.state.lock().unwrap();
+        let elapsed = now.saturating_duration_since(state.updated);
+        let refilled =
+            elapsed.as_secs_f64() / self.limit.refill_interval.as_secs_f64().max(f64::EPSILON);
+        state.tokens = (state.tokens + refilled).min(self.limit.burst as f64);
+        state.updated = now;
+
+        let wait = if state.tokens >= 1.0 {
+            Duration::ZERO
+        } else {
+            self.limit.refill_interval.mul_f64(1.0 - state.tokens)
+        };
+        if wait > self.limit.max_wait {
+            return None;
+        }
+        state.tokens -= 1.0;
+        Some(wait)
+    }
+
+    /// 取得一个令牌，必要时排队等待，超出预算返回 false
+    pub async fn acquire(&self) -> bool {
+        match self.reserve() {
+            Some(wait) => {
+                if !wait.is_zero() {
+                    tokio::time::sleep(wait).await;
+                }
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+/// 按 `RateLimit` 限制请求速率的客户端包装
+///
+/// 预算由发送方在发送前通过 `acquire` 取得，超出预算时返回 `SwqosThrottledError`，`send_*` 不再检查预算
+pub struct RateLimitedClient {
+    pub inner: Arc<SwqosClient>,
+    pub bucket: TokenBucket,
+}
+
+impl RateLimitedClient {
+    pub fn new(inner: Arc<SwqosClient>, limit: RateLimit) -> Self {
+        Self { inner, bucket: TokenBucket::new(limit) }
+    }
+}
+
+#[async_trait::async_trait]
+impl SwqosClientTrait for RateLimitedClient {
+    async fn send_transaction(
+        &self,
+        trade_type: TradeType,
+        transaction: &VersionedTransaction,
+    ) -> Result<()> {
+        self.inner.send_transaction(trade_type, transaction).await
+    }
+
+    async fn send_transactions(
+        &self,
+        trade_type: TradeType,
+        transactions: &Vec<VersionedTransaction>,
+    ) -> Result<()> {
+        self.inner.send_transactions(trade_type, transactions).await
+    }
+
+    async fn send_transaction_with_outcome(
+        &self,
+        trade_type: TradeType,
+        transaction: &VersionedTransaction,
+    ) -> Result<Option<BundleOutcome>> {
+        self.inner.send_transaction_with_outcome(trade_type, transaction).await
+    }
+
+    async fn send_transactions_with_outcome(
+        &self,
+        trade_type: TradeType,
+        transactions: &Vec<VersionedTransaction>,
+    ) -> Result<Option<BundleOutcome>> {
+        self.inner.send_transactions_with_outcome(trade_type, transactions).await
+    }
+
+    fn get_tip_account(&self) -> Result<String> {
+        self.inner.get_tip_account()
+    }
+
+    fn get_swqos_type(&self) -> SwqosType {
+        self.inner.get_swqos_type()
+    }
+
+    fn supports_bundles(&self) -> bool {
+        self.inner.supports_bundles()
+    }
+
+    async fn acquire(&self, trade_type: TradeType) -> Result<()> {
+        if self.bucket.acquire().await {
+            return Ok(());
+        }
+        let swqos_type = self.inner.get_swqos_type();
+        eprintln!(" {:?}{}限流跳过", swqos_type, trade_type);
+        let error = SwqosThrottledError { swqos_type };
+        let _ = throttle_sender().send(error.clone());
+        Err(error.into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        common::SolanaRpcClient,
+        swqos::{
+            confirmation::{ConfirmationConfig, ConfirmationTracker},
+            jito::JitoClient,
+        },
+    };
+
+    #[test]
+    fn test_token_bucket() {
+        let bucket = TokenBucket::new(RateLimit::per_second(2));
+        let now = Instant::now();
+        assert_eq!(bucket.reserve_at(now), Some(Duration::ZERO));
+        assert_eq!(bucket.reserve_at(now), Some(Duration::ZERO));
+        // 桶空且不排队
+        assert_eq!(bucket.reserve_at(now), None);
+        // 半秒补充一个令牌
+        assert_eq!(bucket.reserve_at(now + Duration::from_millis(500)), Some(Duration::ZERO));
+
+        let bucket = TokenBucket::new(
+            RateLimit::per_second(2).with_burst(1).with_max_wait(Duration::from_secs(1)),
+        );
+        assert_eq!(bucket.reserve_at(now), Some(Duration::ZERO));
+        assert_eq!(bucket.reserve_at(now), Some(Duration::from_millis(500)));
+        assert_eq!(bucket.reserve_at(now), Some(Duration::from_secs(1)));
+        assert_eq!(bucket.reserve_at(now), None);
+    }
+
+    #[tokio::test]
+    async fn test_throttled_is_recorded() {
+        let rpc = Arc::new(SolanaRpcClient::new("http://127.0.0.1:8899".to_string()));
+        let tracker = Arc::new(ConfirmationTracker::new(rpc, ConfirmationConfig::default()));
+        let inner: Arc<SwqosClient> =
+            Arc::new(JitoClient::new(tracker, "http://127.0.0.1".to_string(), "".to_string()));
+        let client = RateLimitedClient::new(inner, RateLimit::per_second(1));
+
+        let mut throttled = throttled();
+        assert!(client.acquire(TradeType::Buy).await.is_ok());
+        // 跳过的服务同样推送给订阅者
+        assert!(client.acquire(TradeType::Buy).await.is_err());
+        assert_eq!(throttled.recv().await.unwrap().swqos_type, SwqosType::Jito);
+    }
+}
@@ -7,13 +7,14 @@ use solana_sdk::{
 };
 use solana_transaction_status::UiTransactionEncoding;
 
-use crate::{common::SolanaRpcClient, swqos::{common::poll_transaction_confirmation, SwqosType, TradeType}};
+use crate::{common::SolanaRpcClient, swqos::{confirmation::ConfirmationTracker, SwqosType, TradeType}};
 use crate::swqos::SwqosClientTrait;
 use anyhow::Result;
 
 #[derive(Clone)]
 pub struct SolRpcClient {
     pub rpc_client: Arc<SolanaRpcClient>,
+    pub confirmation_tracker: Arc<ConfirmationTracker>,
 }
 
 #[async_trait::async_trait]
@@ -28,7 +29,7 @@ impl SwqosClientTrait for SolRpcClient {
         }).await?;
 
         let start_time = Instant::now();
-        match poll_transaction_confirmation(&self.rpc_client, signature).await {
+        match self.confirmation_tracker.wait(transaction).await {
             Ok(_) => (),
             Err(e) => {
                 println!(" rpc{}确认失败: {:?}", trade_type, start_time.elapsed());
@@ -58,7 +59,7 @@ impl SwqosClientTrait for SolRpcClient {
 }
 
 impl SolRpcClient {
-    pub fn new(rpc_client: Arc<SolanaRpcClient>) -> Self {
-        Self { rpc_client }
+    pub fn new(rpc_client: Arc<SolanaRpcClient>, confirmation_tracker: Arc<ConfirmationTracker>) -> Self {
+        Self { rpc_client, confirmation_tracker }
     }
 }
\ No newline at end of file
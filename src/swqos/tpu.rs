@@ -0,0 +1,175 @@
+use std::{
+    net::{IpAddr, Ipv4Addr},
+    sync::Arc,
+    time::Instant,
+};
+
+use anyhow::{anyhow, Result};
+use solana_client::{
+    connection_cache::ConnectionCache, nonblocking::tpu_client::TpuClient as QuicTpuClient,
+    tpu_client::TpuClientConfig,
+};
+use solana_quic_client::{QuicConfig, QuicConnectionManager, QuicPool};
+use solana_sdk::{signature::Keypair, transaction::VersionedTransaction};
+use tokio::sync::OnceCell;
+use crate::swqos::{SwqosType, TradeType};
+use crate::swqos::SwqosClientTrait;
+
+use crate::{common::SolanaRpcClient, swqos::confirmation::ConfirmationTracker};
+
+/// 连接池中每个 leader 保持的 QUIC 连接数
+const TPU_CONNECTION_POOL_SIZE: usize = 4;
+
+type LeaderTpuClient = QuicTpuClient<QuicPool, QuicConnectionManager, QuicConfig>;
+
+/// 通过 QUIC 直接发送交易到当前和接下来几个 leader 的 TPU 端口，不需要小费
+///
+/// leader 计划和节点信息来自配置的 RPC，连接由连接池复用。配置了质押身份时使用其证书握手，
+/// 享受按质押分配的 QoS 带宽。
+pub struct TpuClient {
+    pub rpc_url: String,
+    pub websocket_url: String,
+    pub rpc_client: Arc<SolanaRpcClient>,
+    pub confirmation_tracker: Arc<ConfirmationTracker>,
+    /// 握手使用的身份，为空时使用临时身份
+    identity: Option<Arc<Keypair>>,
+    tpu_client: Arc<OnceCell<LeaderTpuClient>>,
+}
+
+#[async_trait::async_trait]
+impl SwqosClientTrait for TpuClient {
+    async fn send_transaction(&self, trade_type: TradeType, transaction: &VersionedTransaction) -> Result<()> {
+        self.send_transaction(trade_type, transaction).await
+    }
+
+    async fn send_transactions(&self, trade_type: TradeType, transactions: &Vec<VersionedTransaction>) -> Result<()> {
+        self.send_transactions(trade_type, transactions).await
+    }
+
+    fn get_tip_account(&self) -> Result<String> {
+        Err(anyhow!("TPU submission does not take tips"))
+    }
+
+    fn get_swqos_type(&self) -> SwqosType {
+        SwqosType::Tpu
+    }
+}
+
+/// 由 RPC 地址推导 websocket 地址，默认端口 8899 对应 8900
+pub fn websocket_url(rpc_url: &str) -> String {
+    let url = if let Some(rest) = rpc_url.strip_prefix("https://") {
+        format!("wss://{}", rest)
+    } else if let Some(rest) = rpc_url.strip_prefix("http://") {
+        format!("ws://{}", rest)
+    } else {
+        rpc_url.to_string()
+    };
+    url.replacen(":8899", ":8900", 1)
+}
+
+impl TpuClient {
+    /// `identity` 为 base58 编码的质押身份密钥，传空字符串则不使用质押
+    pub fn new(confirmation_tracker: Arc<ConfirmationTracker>, rpc_url: String, identity: String) -> Result<Self> {
+        let identity = if identity.is_empty() {
+            None
+        } else {
+            let bytes = bs58::decode(&identity).into_vec()?;
+            Some(Arc::new(Keypair::try_from(bytes.as_slice())?))
+        };
+        let client = Self {
+            rpc_client: confirmation_tracker.rpc().clone(),
+            confirmation_tracker,
+            websocket_url: websocket_url(&rpc_url),
+            rpc_url,
+            identity,
+            tpu_client: Arc::new(OnceCell::new()),
+        };
+
+        // 提前加载 leader 计划，避免第一笔交易等待
+        if let Ok(handle) = tokio::runtime::Handle::try_current() {
+            let tpu_client = client.tpu_client.clone();
+            let rpc_client = client.rpc_client.clone();
+            let websocket_url = client.websocket_url.clone();
+            let identity = client.identity.clone();
+            handle.spawn(async move {
+                if let Err(e) = tpu_client
+                    .get_or_try_init(|| Self::connect(rpc_client, &websocket_url, identity.as_deref()))
+                    .await
+                {
+                    eprintln!(" tpu连接失败: {:?}", e);
+                }
+            });
+        }
+
+        Ok(client)
+    }
+
+    async fn connect(rpc_client: Arc<SolanaRpcClient>, websocket_url: &str, identity: Option<&Keypair>) -> Result<LeaderTpuClient> {
+        let ephemeral_identity = Keypair::new();
+        let identity = identity.unwrap_or(&ephemeral_identity);
+        let connection_cache = match ConnectionCache::new_with_client_options(
+            "sol-trade-sdk-tpu",
+            TPU_CONNECTION_POOL_SIZE,
+            None,
+            Some((identity, IpAddr::V4(Ipv4Addr::UNSPECIFIED))),
+            None,
+        ) {
+            ConnectionCache::Quic(connection_cache) => connection_cache,
+            ConnectionCache::Udp(_) => return Err(anyhow!("TPU connection cache is not QUIC")),
+        };
+        Ok(LeaderTpuClient::new_with_connection_cache(rpc_client, websocket_url, TpuClientConfig::default(), connection_cache).await?)
+    }
+
+    /// 已建立的 TPU 客户端，失败时下次调用重新建立
+    async fn tpu_client(&self) -> Result<&LeaderTpuClient> {
+        self.tpu_client
+            .get_or_try_init(|| Self::connect(self.rpc_client.clone(), &self.websocket_url, self.identity.as_deref()))
+            .await
+    }
+
+    pub async fn send_transaction(&self, trade_type: TradeType, transaction: &VersionedTransaction) -> Result<()> {
+        let start_time = Instant::now();
+        let wire_transaction = bincode::serialize(transaction)?;
+        if let Err(e) = self.tpu_client().await?.try_send_wire_transaction(wire_transaction).await {
+            eprintln!(" tpu{}提交失败: {:?}", trade_type, e);
+            return Err(e.into());
+        }
+        println!(" tpu{}提交: {:?}", trade_type, start_time.elapsed());
+
+        let start_time: Instant = Instant::now();
+        match self.confirmation_tracker.wait(transaction).await {
+            Ok(_) => (),
+            Err(e) => {
+                println!(" tpu{}确认失败: {:?}", trade_type, start_time.elapsed());
+                return Err(e);
+            },
+        }
+
+        println!(" tpu{}确认: {:?}", trade_type, start_time.elapsed());
+
+        Ok(())
+    }
+
+    pub async fn send_transactions(&self, trade_type: TradeType, transactions: &[VersionedTransaction]) -> Result<()> {
+        let start_time = Instant::now();
+        let wire_transactions = transactions.iter().map(bincode::serialize).collect::<Result<Vec<_>, _>>()?;
+        if let Err(e) = self.tpu_client().await?.try_send_wire_transaction_batch(wire_transactions).await {
+            eprintln!(" tpu{}提交失败: {:?}", trade_type, e);
+            return Err(e.into());
+        }
+        println!(" tpu{}提交: {:?}", trade_type, start_time.elapsed());
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_websocket_url() {
+        assert_eq!(websocket_url("http://127.0.0.1:8899"), "ws://127.0.0.1:8900");
+        assert_eq!(websocket_url("https://api.mainnet-beta.solana.com"), "wss://api.mainnet-beta.solana.com");
+    }
+}
@@ -0,0 +1,128 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Weak},
+    time::Duration,
+};
+
+use anyhow::{anyhow, Result};
+use futures::{SinkExt, StreamExt};
+use solana_sdk::{pubkey::Pubkey, signature::Signature};
+use yellowstone_grpc_client::{ClientTlsConfig, GeyserGrpcClient};
+use yellowstone_grpc_proto::geyser::{
+    subscribe_update::UpdateOneof, CommitmentLevel, SubscribeRequest,
+    SubscribeRequestFilterTransactions, SubscribeRequestPing, SubscribeUpdateTransactionStatus,
+};
+
+use crate::swqos::confirmation::{ConfirmationTracker, TransactionLanding};
+
+/// 交易流断开后重连的间隔
+const RECONNECT_INTERVAL: Duration = Duration::from_secs(1);
+
+/// 通过 Yellowstone gRPC 订阅指定账户的交易状态，在 processed 阶段发现交易上链
+///
+/// 比轮询 getSignatureStatuses 早几百毫秒拿到上链的 slot 和执行结果，
+/// 推送给 `ConfirmationTracker` 结束对应交易的等待。
+#[derive(Debug, Clone)]
+pub struct YellowstoneLandingStream {
+    pub endpoint: String,
+    pub x_token: Option<String>,
+    /// 订阅涉及这些账户的交易，通常是交易钱包
+    pub accounts: Vec<Pubkey>,
+}
+
+impl YellowstoneLandingStream {
+    pub fn new(endpoint: String, x_token: Option<String>, accounts: Vec<Pubkey>) -> Self {
+        Self { endpoint, x_token, accounts }
+    }
+
+    /// 在后台订阅并推送给 `tracker`，断开后自动重连，`tracker` 释放后退出
+    pub fn start(self, tracker: &Arc<ConfirmationTracker>) {
+        let tracker = Arc::downgrade(tracker);
+        tokio::spawn(async move {
+            loop {
+                if let Err(e) = self.run(&tracker).await {
+                    eprintln!(" yellowstone交易流断开: {}", e);
+                }
+                if tracker.strong_count() == 0 {
+                    break;
+                }
+                tokio::time::sleep(RECONNECT_INTERVAL).await;
+            }
+        });
+    }
+
+    async fn run(&self, tracker: &Weak<ConfirmationTracker>) -> Result<()> {
+        let mut client = GeyserGrpcClient::build_from_shared(self.endpoint.clone())?
+            .x_token(self.x_token.clone())?
+            .tls_config(ClientTlsConfig::new().with_native_roots())?
+            .connect_timeout(Duration::from_secs(10))
+            .tcp_nodelay(true)
+            .connect()
+            .await?;
+        let (mut sink, mut stream) = client.subscribe_with_request(Some(self.request())).await?;
+        while let Some(update) = stream.next().await {
+            match update?.update_oneof {
+                Some(UpdateOneof::TransactionStatus(status)) => {
+                    let Some(tracker) = tracker.upgrade() else { return Ok(()) };
+                    tracker.landed(landing_from_status(status)?);
+                }
+                Some(UpdateOneof::Ping(_)) => {
+                    sink.send(SubscribeRequest {
+                        ping: Some(SubscribeRequestPing { id: 1 }),
+                        ..Default::default()
+                    })
+                    .await?;
+                }
+                _ => {}
+            }
+        }
+        Err(anyhow!("Yellowstone stream closed"))
+    }
+
+    fn request(&self) -> SubscribeRequest {
+        let filter = SubscribeRequestFilterTransactions {
+            vote: Some(false),
+            account_include: self.accounts.iter().map(|account| account.to_string()).collect(),
+            ..Default::default()
+        };
+        SubscribeRequest {
+            transactions_status: HashMap::from([("landing".to_string(), filter)]),
+            commitment: Some(CommitmentLevel::Processed as i32),
+            ..Default::default()
+        }
+    }
+}
+
+fn landing_from_status(status: SubscribeUpdateTransactionStatus) -> Result<TransactionLanding> {
+    Ok(TransactionLanding {
+        signature: Signature::try_from(status.signature.as_slice())?,
+        slot: status.slot,
+        err: status.err.map(|err| bincode::deserialize(&err.err)).transpose()?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_sdk::transaction::TransactionError;
+    use yellowstone_grpc_proto::prelude::TransactionError as ProtoTransactionError;
+
+    #[test]
+    fn test_landing_from_status() {
+        let signature = Signature::new_unique();
+        let status = SubscribeUpdateTransactionStatus {
+            slot: 42,
+            signature: signature.as_ref().to_vec(),
+            is_vote: false,
+            index: 0,
+            err: Some(ProtoTransactionError {
+                err: bincode::serialize(&TransactionError::InsufficientFundsForFee).unwrap(),
+            }),
+        };
+        let landing = landing_from_status(status).unwrap();
+        assert_eq!(landing.signature, signature);
+        assert_eq!(landing.slot, 42);
+        assert_eq!(landing.err, Some(TransactionError::InsufficientFundsForFee));
+        assert!(!landing.is_success());
+    }
+}
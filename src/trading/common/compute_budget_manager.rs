@@ -1,6 +1,26 @@
 use solana_sdk::{compute_budget::ComputeBudgetInstruction, instruction::Instruction};
 
-use crate::common::PriorityFee;
+use crate::common::{compute_unit_resolver::ComputeUnitResolver, PriorityFee};
+
+/// 按业务指令确定计算预算，已启用时使用估算的单价和模拟得到的 compute unit 上限
+pub fn resolve_priority_fee(
+    priority_fee: &PriorityFee,
+    business_instructions: &[Instruction],
+    protocol_name: &str,
+    is_buy: bool,
+) -> PriorityFee {
+    let mut priority_fee = match &priority_fee.estimator {
+        Some(estimator) => estimator.apply(priority_fee, business_instructions),
+        None => priority_fee.clone(),
+    };
+    ComputeUnitResolver::get_instance().apply(
+        &mut priority_fee,
+        protocol_name,
+        is_buy,
+        business_instructions,
+    );
+    priority_fee
+}
 
 /// 为RPC交易添加计算预算指令
 pub fn add_rpc_compute_budget_instructions(
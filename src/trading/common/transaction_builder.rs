@@ -15,6 +15,7 @@ use super::{
     address_lookup_manager::get_address_lookup_table_accounts,
     compute_budget_manager::{
         add_rpc_compute_budget_instructions, add_tip_compute_budget_instructions,
+        resolve_priority_fee,
     },
     nonce_manager::{add_nonce_instruction, get_transaction_blockhash},
 };
@@ -45,7 +46,9 @@ pub async fn build_rpc_transaction(
         return Err(e);
     }
 
-    // 添加计算预算指令
+    // 添加计算预算指令，已启用估算时按业务指令定价和设置上限
+    let priority_fee =
+        &resolve_priority_fee(priority_fee, &business_instructions, &protocol_name, is_buy);
     add_rpc_compute_budget_instructions(&mut instructions, priority_fee, data_size_limit);
 
     // 添加业务指令
@@ -91,7 +94,9 @@ pub async fn build_tip_transaction(
         return Err(e);
     }
 
-    // 添加计算预算指令
+    // 添加计算预算指令，已启用估算时按业务指令定价和设置上限
+    let priority_fee =
+        &resolve_priority_fee(priority_fee, &business_instructions, &protocol_name, is_buy);
     add_tip_compute_budget_instructions(&mut instructions, priority_fee, data_size_limit);
 
     // 添加业务指令
@@ -193,7 +198,9 @@ pub async fn build_sell_transaction(
 ) -> Result<VersionedTransaction, anyhow::Error> {
     let mut instructions = vec![];
 
-    // 添加计算预算指令
+    // 添加计算预算指令，已启用估算时按业务指令定价和设置上限
+    let priority_fee =
+        &resolve_priority_fee(priority_fee, &business_instructions, &protocol_name, is_buy);
     add_sell_compute_budget_instructions(&mut instructions, priority_fee);
 
     // 添加业务指令
@@ -229,7 +236,9 @@ pub async fn build_sell_tip_transaction(
 ) -> Result<VersionedTransaction, anyhow::Error> {
     let mut instructions = vec![];
 
-    // 添加计算预算指令
+    // 添加计算预算指令，已启用估算时按业务指令定价和设置上限
+    let priority_fee =
+        &resolve_priority_fee(priority_fee, &business_instructions, &protocol_name, is_buy);
     add_sell_tip_compute_budget_instructions(&mut instructions, priority_fee);
 
     // 添加业务指令
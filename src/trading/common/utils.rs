@@ -1,4 +1,6 @@
-use solana_sdk::{pubkey::Pubkey, signature::Keypair, signer::Signer, transaction::Transaction};
+use solana_sdk::{
+    account::Account, pubkey::Pubkey, signature::Keypair, signer::Signer, transaction::Transaction,
+};
 use solana_system_interface::instruction::transfer;
 use spl_associated_token_account::get_associated_token_address;
 use spl_token::instruction::close_account;
@@ -38,6 +40,45 @@ pub async fn get_token_balance(
     Ok(balance_u64)
 }
 
+/// Maximum number of accounts `getMultipleAccounts` returns per request
+const MAX_MULTIPLE_ACCOUNTS: usize = 100;
+
+/// Fetch any number of accounts, batching `getMultipleAccounts` requests
+///
+/// # Returns
+/// Returns the accounts in the order of `addresses`, `None` for accounts that don't exist
+pub async fn get_multiple_accounts_batched(
+    rpc: &SolanaRpcClient,
+    addresses: &[Pubkey],
+) -> Result<Vec<Option<Account>>, anyhow::Error> {
+    let mut accounts = Vec::with_capacity(addresses.len());
+    for chunk in addresses.chunks(MAX_MULTIPLE_ACCOUNTS) {
+        accounts.extend(rpc.get_multiple_accounts(chunk).await?);
+    }
+    Ok(accounts)
+}
+
+/// Amount held by an SPL Token or Token-2022 token account
+#[inline]
+pub fn token_account_amount(data: &[u8]) -> Option<u64> {
+    data.get(64..72).map(|bytes| u64::from_le_bytes(bytes.try_into().unwrap()))
+}
+
+/// Balance of a token account, 0 when the account does not exist
+///
+/// RPC and decode errors are returned instead of being read as an empty account.
+pub async fn get_token_balance_or_zero(
+    rpc: &SolanaRpcClient,
+    account: &Pubkey,
+) -> Result<u64, anyhow::Error> {
+    let Some(account) = rpc.get_account_with_commitment(account, rpc.commitment()).await?.value
+    else {
+        return Ok(0);
+    };
+    token_account_amount(&account.data)
+        .ok_or_else(|| anyhow::anyhow!("Failed to decode token account"))
+}
+
 #[inline]
 pub async fn get_sol_balance(
     rpc: &SolanaRpcClient,
@@ -0,0 +1,363 @@
+use anyhow::Result;
+use solana_sdk::{instruction::Instruction, native_token::LAMPORTS_PER_SOL, pubkey::Pubkey};
+use std::sync::Arc;
+
+use super::{
+    params::{PumpSwapParams, RaydiumAmmV4Params, RaydiumCpmmParams, SwapQuote},
+    swap_path::{SwapContext, SwapDirection, SwapLeg},
+    traits::ProtocolParams,
+};
+use crate::{
+    common::PriorityFee,
+    constants::{
+        pumpswap::accounts::WSOL_TOKEN_ACCOUNT,
+        raydium_amm_v4::accounts::{TRADE_FEE_DENOMINATOR, TRADE_FEE_NUMERATOR},
+        raydium_cpmm::accounts::FEE_RATE_DENOMINATOR_VALUE,
+    },
+    instruction::pumpswap::pool_fees,
+    trading::{factory::DexType, raydium_cpmm::common::get_pool_pda, MiddlewareManager},
+};
+
+/// 每个签名的基础交易费用 (lamports)
+const SIGNATURE_FEE_LAMPORTS: u64 = 5_000;
+
+/// SOL and token reserves of a constant-product pool
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PoolReserves {
+    pub sol_reserve: u64,
+    pub token_reserve: u64,
+    /// Total fee charged on the input, in basis points
+    pub fee_basis_points: u64,
+}
+
+/// Reserves of a PumpSwap, Raydium CPMM or Raydium AMM v4 pool trading `mint` against SOL
+///
+/// Returns `None` for other protocols and for pools that do not pair `mint` with SOL.
+pub async fn pool_reserves(
+    dex_type: &DexType,
+    protocol_params: &dyn ProtocolParams,
+    mint: &Pubkey,
+) -> Option<PoolReserves> {
+    let params = protocol_params.as_any();
+    let (base_mint, quote_mint, base_reserve, quote_reserve, fee_basis_points) = match dex_type {
+        DexType::PumpSwap => {
+            let p = params.downcast_ref::<PumpSwapParams>()?;
+            let fees = pool_fees(p).await;
+            (
+                p.base_mint,
+                p.quote_mint,
+                p.pool_base_token_reserves,
+                p.pool_quote_token_reserves,
+                fees.lp_fee_basis_points
+                    + fees.protocol_fee_basis_points
+                    + fees.coin_creator_fee_basis_points,
+            )
+        }
+        DexType::RaydiumCpmm => {
+            let p = params.downcast_ref::<RaydiumCpmmParams>()?;
+            let fee_rate = (p.trade_fee_rate + p.creator_fee_rate) as u128;
+            let fee_basis_points = (fee_rate * 10_000).div_ceil(FEE_RATE_DENOMINATOR_VALUE);
+            (p.base_mint, p.quote_mint, p.base_reserve, p.quote_reserve, fee_basis_points as u64)
+        }
+        DexType::RaydiumAmmV4 => {
+            let p = params.downcast_ref::<RaydiumAmmV4Params>()?;
+            let fee_basis_points = (TRADE_FEE_NUMERATOR * 10_000).div_ceil(TRADE_FEE_DENOMINATOR);
+            (p.coin_mint, p.pc_mint, p.coin_reserve, p.pc_reserve, fee_basis_points)
+        }
+        _ => return None,
+    };
+
+    if base_mint == *mint && quote_mint == WSOL_TOKEN_ACCOUNT {
+        Some(PoolReserves {
+            sol_reserve: quote_reserve,
+            token_reserve: base_reserve,
+            fee_basis_points,
+        })
+    } else if base_mint == WSOL_TOKEN_ACCOUNT && quote_mint == *mint {
+        Some(PoolReserves {
+            sol_reserve: base_reserve,
+            token_reserve: quote_reserve,
+            fee_basis_points,
+        })
+    } else {
+        None
+    }
+}
+
+/// SOL input maximising the profit of buying on `buy` and selling on `sell`
+///
+/// Both pools are treated as constant-product pools charging their fee on the input. Chaining
+/// them gives `out(x) = K·x / (L + M·x)`, whose profit `out(x) − x` peaks at
+/// `x = (√(K·L) − L) / M`. Returns `None` when the price gap does not cover the fees.
+pub fn optimal_amount_in(buy: &PoolReserves, sell: &PoolReserves) -> Option<u64> {
+    let gamma_buy = 1.0 - buy.fee_basis_points as f64 / 10_000.0;
+    let gamma_sell = 1.0 - sell.fee_basis_points as f64 / 10_000.0;
+    let k = gamma_buy * gamma_sell * sell.sol_reserve as f64 * buy.token_reserve as f64;
+    let l = buy.sol_reserve as f64 * sell.token_reserve as f64;
+    let m = gamma_buy * (sell.token_reserve as f64 + gamma_sell * buy.token_reserve as f64);
+    if k <= l || m <= 0.0 {
+        return None;
+    }
+    let amount_in = ((k * l).sqrt() - l) / m;
+    if amount_in < 1.0 {
+        return None;
+    }
+    Some(amount_in as u64)
+}
+
+/// Worst-case lamports a transaction sent with `priority_fee` costs, whichever SWQOS lands it
+pub fn execution_cost(priority_fee: &PriorityFee) -> u64 {
+    let rpc_fee = priority_fee.rpc_unit_price * priority_fee.rpc_unit_limit as u64 / 1_000_000;
+    let tip_fee =
+        priority_fee.buy_tip_fees.iter().copied().fold(priority_fee.buy_tip_fee, f64::max);
+    let tip_fee = priority_fee.tip_unit_price * priority_fee.tip_unit_limit as u64 / 1_000_000
+        + (tip_fee * LAMPORTS_PER_SOL as f64) as u64;
+    rpc_fee.max(tip_fee) + SIGNATURE_FEE_LAMPORTS
+}
+
+/// Atomic buy on one pool and sell on another
+#[derive(Clone)]
+pub struct ArbitrageOpportunity {
+    pub buy_leg: SwapLeg,
+    pub sell_leg: SwapLeg,
+    pub buy_quote: SwapQuote,
+    /// Sell quote with the minimum-profit guard as its minimum output
+    pub sell_quote: SwapQuote,
+    /// Sell slippage that puts the guard into the sell instruction
+    pub sell_slippage_basis_points: u64,
+    /// Lamports paid for priority fee, tip and signature
+    pub execution_cost: u64,
+    /// Expected SOL profit after fees, tip and priority fee
+    pub expected_profit: u64,
+}
+
+/// Watches one mint across PumpSwap and Raydium pools for price gaps
+///
+/// ```ignore
+/// let mut detector = ArbitrageDetector::new(mint, 1_000_000)
+///     .with_pool(DexType::PumpSwap, Box::new(pumpswap_params))
+///     .with_pool(DexType::RaydiumAmmV4, Box::new(amm_params));
+/// // Refresh a pool from a stream event, then look again
+/// detector.update_pool(DexType::PumpSwap, Box::new(new_pumpswap_params));
+/// if let Some(opportunity) = detector.detect(&context).await? { ... }
+/// ```
+#[derive(Clone)]
+pub struct ArbitrageDetector {
+    pub mint: Pubkey,
+    pub pools: Vec<SwapLeg>,
+    /// Minimum SOL profit after costs, enforced on-chain by the sell leg
+    pub min_profit: u64,
+    /// Maximum SOL spent on the buy leg
+    pub max_amount_in: Option<u64>,
+}
+
+impl ArbitrageDetector {
+    pub fn new(mint: Pubkey, min_profit: u64) -> Self {
+        Self { mint, pools: vec![], min_profit, max_amount_in: None }
+    }
+
+    pub fn with_pool(
+        mut self,
+        dex_type: DexType,
+        protocol_params: Box<dyn ProtocolParams>,
+    ) -> Self {
+        self.update_pool(dex_type, protocol_params);
+        self
+    }
+
+    pub fn with_max_amount_in(mut self, max_amount_in: u64) -> Self {
+        self.max_amount_in = Some(max_amount_in);
+        self
+    }
+
+    /// Replaces the state of a watched pool, or starts watching it
+    pub fn update_pool(&mut self, dex_type: DexType, protocol_params: Box<dyn ProtocolParams>) {
+        let leg = SwapLeg::buy(dex_type, self.mint, protocol_params);
+        let address = pool_address(&leg);
+        match self.pools.iter_mut().find(|pool| address.is_some() && pool_address(pool) == address)
+        {
+            Some(pool) => *pool = leg,
+            None => self.pools.push(leg),
+        }
+    }
+
+    /// Finds the most profitable buy/sell pool pair
+    ///
+    /// The buy leg uses the context's slippage; the sell leg's minimum output is set so the
+    /// transaction fails unless it returns the buy leg's maximum spend plus costs and
+    /// `min_profit`. Pairs whose quotes fail are logged and skipped.
+    pub async fn detect(&self, context: &SwapContext) -> Result<Option<ArbitrageOpportunity>> {
+        let mut reserves = Vec::with_capacity(self.pools.len());
+        for pool in &self.pools {
+            reserves.push(
+                pool_reserves(&pool.dex_type, pool.protocol_params.as_ref(), &self.mint).await,
+            );
+        }
+        let execution_cost = execution_cost(&context.priority_fee);
+
+        let mut best: Option<ArbitrageOpportunity> = None;
+        for (i, buy_pool) in self.pools.iter().enumerate() {
+            for (j, sell_pool) in self.pools.iter().enumerate() {
+                let (Some(buy_reserves), Some(sell_reserves)) = (&reserves[i], &reserves[j]) else {
+                    continue;
+                };
+                if i == j {
+                    continue;
+                }
+                let Some(mut amount_in) = optimal_amount_in(buy_reserves, sell_reserves) else {
+                    continue;
+                };
+                if let Some(max_amount_in) = self.max_amount_in {
+                    amount_in = amount_in.min(max_amount_in);
+                }
+                // A pool that cannot be quoted only rules out its own pairs
+                let opportunity = match self
+                    .evaluate(context, buy_pool, sell_pool, amount_in, execution_cost)
+                    .await
+                {
+                    Ok(opportunity) => opportunity,
+                    Err(e) => {
+                        log::warn!(
+                            "Skipping arbitrage pair {} -> {}: {}",
+                            buy_pool.dex_type,
+                            sell_pool.dex_type,
+                            e
+                        );
+                        continue;
+                    }
+                };
+                if let Some(opportunity) = opportunity {
+                    if best
+                        .as_ref()
+                        .is_none_or(|best| opportunity.expected_profit > best.expected_profit)
+                    {
+                        best = Some(opportunity);
+                    }
+                }
+            }
+        }
+        Ok(best)
+    }
+
+    /// Quotes buying on `buy_pool` and selling on `sell_pool` with the protocols' exact math
+    async fn evaluate(
+        &self,
+        context: &SwapContext,
+        buy_pool: &SwapLeg,
+        sell_pool: &SwapLeg,
+        amount_in: u64,
+        execution_cost: u64,
+    ) -> Result<Option<ArbitrageOpportunity>> {
+        let buy_leg = SwapLeg { direction: SwapDirection::Buy, ..buy_pool.clone() };
+        let sell_leg = SwapLeg { direction: SwapDirection::Sell, ..sell_pool.clone() };
+
+        let Some(buy_quote) = context.quote_leg_within(&buy_leg, amount_in).await? else {
+            return Ok(None);
+        };
+        if buy_quote.min_amount_out == 0 {
+            return Ok(None);
+        }
+        let sell_quote = context.quote_leg(&sell_leg, buy_quote.min_amount_out).await?;
+        let required = buy_quote.max_amount_in + execution_cost + self.min_profit;
+        if sell_quote.amount_out <= required {
+            return Ok(None);
+        }
+
+        let sell_slippage_basis_points = ((sell_quote.amount_out - required) as u128 * 10_000
+            / sell_quote.amount_out as u128) as u64;
+        let sell_context = sell_context(context, sell_slippage_basis_points);
+        let sell_quote = sell_context.quote_leg(&sell_leg, buy_quote.min_amount_out).await?;
+        if sell_quote.min_amount_out < required {
+            return Ok(None);
+        }
+
+        Ok(Some(ArbitrageOpportunity {
+            expected_profit: sell_quote.amount_out - buy_quote.amount_in - execution_cost,
+            buy_leg,
+            sell_leg,
+            buy_quote,
+            sell_quote,
+            sell_slippage_basis_points,
+            execution_cost,
+        }))
+    }
+}
+
+/// Builds the buy and sell instructions of an opportunity for one transaction
+pub async fn build_arbitrage_instructions(
+    context: &SwapContext,
+    opportunity: &ArbitrageOpportunity,
+    middleware_manager: Option<Arc<MiddlewareManager>>,
+) -> Result<Vec<Instruction>> {
+    let mut instructions = context
+        .build_leg(
+            &opportunity.buy_leg,
+            opportunity.buy_quote.amount_in,
+            middleware_manager.as_ref(),
+        )
+        .await?;
+    instructions.extend(
+        sell_context(context, opportunity.sell_slippage_basis_points)
+            .build_leg(
+                &opportunity.sell_leg,
+                opportunity.buy_quote.min_amount_out,
+                middleware_manager.as_ref(),
+            )
+            .await?,
+    );
+    Ok(instructions)
+}
+
+fn sell_context(context: &SwapContext, slippage_basis_points: u64) -> SwapContext {
+    SwapContext { slippage_basis_points: Some(slippage_basis_points), ..context.clone() }
+}
+
+/// Address identifying the pool behind a leg
+fn pool_address(leg: &SwapLeg) -> Option<Pubkey> {
+    let params = leg.protocol_params.as_any();
+    match leg.dex_type {
+        DexType::PumpSwap => params.downcast_ref::<PumpSwapParams>().map(|p| p.pool),
+        DexType::RaydiumCpmm => params
+            .downcast_ref::<RaydiumCpmmParams>()
+            .and_then(|p| get_pool_pda(&p.amm_config, &p.base_mint, &p.quote_mint)),
+        DexType::RaydiumAmmV4 => params.downcast_ref::<RaydiumAmmV4Params>().map(|p| p.amm),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn profit(buy: &PoolReserves, sell: &PoolReserves, amount_in: u64) -> f64 {
+        let swap = |amount: f64, fee: u64, reserve_in: u64, reserve_out: u64| {
+            let amount = amount * (1.0 - fee as f64 / 10_000.0);
+            amount * reserve_out as f64 / (reserve_in as f64 + amount)
+        };
+        let tokens =
+            swap(amount_in as f64, buy.fee_basis_points, buy.sol_reserve, buy.token_reserve);
+        swap(tokens, sell.fee_basis_points, sell.token_reserve, sell.sol_reserve) - amount_in as f64
+    }
+
+    #[test]
+    fn test_optimal_amount_in() {
+        let cheap = PoolReserves {
+            sol_reserve: 100 * LAMPORTS_PER_SOL,
+            token_reserve: 1_000_000_000_000,
+            fee_basis_points: 25,
+        };
+        let expensive = PoolReserves { sol_reserve: 110 * LAMPORTS_PER_SOL, ..cheap };
+
+        let amount_in = optimal_amount_in(&cheap, &expensive).unwrap();
+        let best = profit(&cheap, &expensive, amount_in);
+        assert!(best > 0.0);
+        assert!(best >= profit(&cheap, &expensive, amount_in * 99 / 100));
+        assert!(best >= profit(&cheap, &expensive, amount_in * 101 / 100));
+
+        // No gap, or a gap smaller than the fees
+        assert_eq!(optimal_amount_in(&cheap, &cheap), None);
+        assert_eq!(optimal_amount_in(&expensive, &cheap), None);
+        let close = PoolReserves { sol_reserve: 100_300_000_000, ..cheap };
+        assert_eq!(optimal_amount_in(&cheap, &close), None);
+    }
+}
@@ -0,0 +1,280 @@
+use anyhow::{anyhow, Result};
+use solana_hash::Hash;
+use solana_sdk::{
+    compute_budget::ComputeBudgetInstruction,
+    instruction::Instruction,
+    message::{v0, VersionedMessage},
+    native_token::sol_str_to_lamports,
+    pubkey::Pubkey,
+    signature::Keypair,
+    signer::Signer,
+    transaction::VersionedTransaction,
+};
+use solana_system_interface::instruction::transfer;
+use std::{str::FromStr, sync::Arc};
+use tokio::sync::oneshot;
+
+use crate::{
+    common::PriorityFee,
+    protos::searcher_client::BundleRejectionError,
+    swqos::{jito_bundle_tracker::BundleOutcome, SwqosClient, TradeType},
+    trading::{
+        common::get_address_lookup_table_accounts,
+        core::outcome::{admit, throttled_types, TradeOutcome},
+    },
+};
+
+/// 一个 bundle 最多包含的交易数
+pub const MAX_BUNDLE_TRANSACTIONS: usize = 5;
+
+/// bundle 中的一笔交易
+#[derive(Clone)]
+pub enum BundleTransaction {
+    /// 发送时用 `payer` 和 `signers` 签名的指令
+    Instructions { payer: Arc<Keypair>, signers: Vec<Arc<Keypair>>, instructions: Vec<Instruction> },
+    /// 已签名的交易，原样发送
+    Signed(VersionedTransaction),
+}
+
+/// 组合最多 5 笔交易为一个原子执行的 bundle
+///
+/// 例如创建 + 开发者买入 + 多钱包买入，或者买入搭配预签名的卖出。小费加在最后一笔交易上，
+/// 由 `tip_payer` 一并签名；最后一笔是已签名交易时，额外追加一笔由 `tip_payer` 支付的小费交易。
+#[derive(Clone)]
+pub struct BundleBuilder {
+    transactions: Vec<BundleTransaction>,
+    tip_payer: Arc<Keypair>,
+    tip: f64,
+    lookup_table_key: Option<Pubkey>,
+}
+
+impl BundleBuilder {
+    /// `tip` 以 SOL 为单位，由 `tip_payer` 支付
+    pub fn new(tip_payer: Arc<Keypair>, tip: f64) -> Self {
+        Self { transactions: vec![], tip_payer, tip, lookup_table_key: None }
+    }
+
+    /// 添加一笔由 `payer` 签名并支付的交易
+    pub fn with_instructions(self, payer: Arc<Keypair>, instructions: Vec<Instruction>) -> Self {
+        self.with_signed_instructions(payer, vec![], instructions)
+    }
+
+    /// 添加一笔除 `payer` 外还需要 `signers` 签名的交易，例如创建代币时的 mint
+    pub fn with_signed_instructions(
+        mut self,
+        payer: Arc<Keypair>,
+        signers: Vec<Arc<Keypair>>,
+        instructions: Vec<Instruction>,
+    ) -> Self {
+        self.transactions.push(BundleTransaction::Instructions { payer, signers, instructions });
+        self
+    }
+
+    /// 添加一笔已签名的交易，需要使用与 bundle 相同的 blockhash
+    pub fn with_signed_transaction(mut self, transaction: VersionedTransaction) -> Self {
+        self.transactions.push(BundleTransaction::Signed(transaction));
+        self
+    }
+
+    pub fn with_lookup_table_key(mut self, lookup_table_key: Option<Pubkey>) -> Self {
+        self.lookup_table_key = lookup_table_key;
+        self
+    }
+
+    pub fn len(&self) -> usize {
+        self.transactions.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.transactions.is_empty()
+    }
+
+    /// 按 `tip_account` 构建并签名 bundle 中的全部交易
+    ///
+    /// 指令交易使用 `priority_fee` 的 `tip_unit_price`/`tip_unit_limit`
+    pub async fn build(
+        &self,
+        priority_fee: &PriorityFee,
+        tip_account: &Pubkey,
+        recent_blockhash: Hash,
+    ) -> Result<Vec<VersionedTransaction>> {
+        if self.transactions.is_empty() {
+            return Err(anyhow!("Bundle has no transactions"));
+        }
+        let mut transactions = self.transactions.clone();
+        let tip_instruction = transfer(
+            &self.tip_payer.pubkey(),
+            tip_account,
+            sol_str_to_lamports(self.tip.to_string().as_str()).unwrap_or(0),
+        );
+        match transactions.last_mut() {
+            Some(BundleTransaction::Instructions { payer, signers, instructions }) => {
+                instructions.push(tip_instruction);
+                // 小费支付者不是这笔交易的签名者时需要额外签名
+                let tip_payer = self.tip_payer.pubkey();
+                if payer.pubkey() != tip_payer
+                    && !signers.iter().any(|signer| signer.pubkey() == tip_payer)
+                {
+                    signers.push(self.tip_payer.clone());
+                }
+            }
+            _ => transactions.push(BundleTransaction::Instructions {
+                payer: self.tip_payer.clone(),
+                signers: vec![],
+                instructions: vec![tip_instruction],
+            }),
+        }
+        if transactions.len() > MAX_BUNDLE_TRANSACTIONS {
+            return Err(anyhow!(
+                "Bundle has {} transactions including the tip, at most {} are allowed",
+                transactions.len(),
+                MAX_BUNDLE_TRANSACTIONS
+            ));
+        }
+
+        let address_lookup_table_accounts =
+            get_address_lookup_table_accounts(self.lookup_table_key).await;
+        transactions
+            .into_iter()
+            .map(|transaction| match transaction {
+                BundleTransaction::Signed(transaction) => Ok(transaction),
+                BundleTransaction::Instructions { payer, signers, instructions } => {
+                    let mut full_instructions = vec![
+                        ComputeBudgetInstruction::set_compute_unit_price(
+                            priority_fee.tip_unit_price,
+                        ),
+                        ComputeBudgetInstruction::set_compute_unit_limit(
+                            priority_fee.tip_unit_limit,
+                        ),
+                    ];
+                    full_instructions.extend(instructions);
+                    let message = v0::Message::try_compile(
+                        &payer.pubkey(),
+                        &full_instructions,
+                        &address_lookup_table_accounts,
+                        recent_blockhash,
+                    )?;
+                    let mut keypairs: Vec<&Keypair> = vec![payer.as_ref()];
+                    keypairs.extend(signers.iter().map(|signer| signer.as_ref()));
+                    Ok(VersionedTransaction::try_new(VersionedMessage::V0(message), &keypairs)?)
+                }
+            })
+            .collect()
+    }
+
+    /// 发送给所有支持 bundle 的服务，任意一个成功即返回，结果中包括被限流跳过的服务和此前完成的服务给出的 bundle 结果
+    pub async fn send(
+        &self,
+        swqos_clients: &[Arc<SwqosClient>],
+        priority_fee: &PriorityFee,
+        trade_type: TradeType,
+        recent_blockhash: Hash,
+    ) -> Result<TradeOutcome> {
+        let bundle_clients: Vec<_> =
+            swqos_clients.iter().filter(|client| client.supports_bundles()).cloned().collect();
+        if bundle_clients.is_empty() {
+            return Err(anyhow!("No configured SWQOS service supports bundles"));
+        }
+
+        let mut handles = vec![];
+        let mut admissions = vec![];
+        for swqos_client in bundle_clients {
+            let tip_account = Pubkey::from_str(&swqos_client.get_tip_account()?)?;
+            let transactions = self.build(priority_fee, &tip_account, recent_blockhash).await?;
+            let swqos_type = swqos_client.get_swqos_type();
+            let (admission, admitted) = oneshot::channel();
+            admissions.push(admitted);
+            let handle = tokio::spawn(async move {
+                admit(swqos_client.as_ref(), trade_type, admission).await?;
+                swqos_client.send_transactions_with_outcome(trade_type, &transactions).await
+            });
+            handles.push((swqos_type, handle));
+        }
+
+        let mut errors = vec![];
+        let mut outcome = TradeOutcome::default();
+        for (swqos_type, handle) in handles {
+            match handle.await {
+                Ok(Ok(bundle)) => {
+                    outcome.bundles.extend(bundle.map(|bundle| (swqos_type, bundle)));
+                    outcome.throttled = throttled_types(admissions).await;
+                    return Ok(outcome);
+                }
+                Ok(Err(e)) => {
+                    errors.push(format!("Task error: {}", e));
+                    if let Some(rejection) = e.downcast_ref::<BundleRejectionError>() {
+                        outcome
+                            .bundles
+                            .push((swqos_type, BundleOutcome::Rejected(rejection.clone())));
+                    }
+                }
+                Err(e) => errors.push(format!("Join error: {}", e)),
+            }
+        }
+        Err(anyhow!("所有 bundle 都发送失败了: {:?}", errors))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_tip_goes_in_last_transaction() {
+        let payer = Arc::new(Keypair::new());
+        let tip_account = Pubkey::new_unique();
+        let recent_blockhash = Hash::new_unique();
+        let priority_fee = PriorityFee::default();
+        let noop = transfer(&payer.pubkey(), &Pubkey::new_unique(), 1);
+
+        let bundle = BundleBuilder::new(payer.clone(), 0.001)
+            .with_instructions(payer.clone(), vec![noop.clone()])
+            .with_instructions(payer.clone(), vec![noop.clone()]);
+        let transactions =
+            bundle.build(&priority_fee, &tip_account, recent_blockhash).await.unwrap();
+        assert_eq!(transactions.len(), 2);
+        let last_keys = transactions[1].message.static_account_keys();
+        assert!(last_keys.contains(&tip_account));
+        assert!(!transactions[0].message.static_account_keys().contains(&tip_account));
+
+        // 最后一笔已签名时追加小费交易
+        let signed = transactions[0].clone();
+        let bundle = bundle.with_signed_transaction(signed);
+        assert_eq!(
+            bundle.build(&priority_fee, &tip_account, recent_blockhash).await.unwrap().len(),
+            4
+        );
+        let bundle = bundle
+            .with_instructions(payer.clone(), vec![noop.clone()])
+            .with_instructions(payer.clone(), vec![noop.clone()]);
+        assert_eq!(
+            bundle.build(&priority_fee, &tip_account, recent_blockhash).await.unwrap().len(),
+            MAX_BUNDLE_TRANSACTIONS
+        );
+        let bundle = bundle.with_instructions(payer, vec![noop]);
+        assert!(bundle.build(&priority_fee, &tip_account, recent_blockhash).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_tip_payer_signs_last_transaction_of_another_wallet() {
+        let tip_payer = Arc::new(Keypair::new());
+        let wallet = Arc::new(Keypair::new());
+        let tip_account = Pubkey::new_unique();
+        let recent_blockhash = Hash::new_unique();
+        let dev_buy = transfer(&tip_payer.pubkey(), &Pubkey::new_unique(), 1);
+        let wallet_buy = transfer(&wallet.pubkey(), &Pubkey::new_unique(), 1);
+
+        let transactions = BundleBuilder::new(tip_payer.clone(), 0.001)
+            .with_instructions(tip_payer.clone(), vec![dev_buy])
+            .with_instructions(wallet.clone(), vec![wallet_buy])
+            .build(&PriorityFee::default(), &tip_account, recent_blockhash)
+            .await
+            .unwrap();
+        let last = &transactions[1];
+        let keys = last.message.static_account_keys();
+        assert_eq!(keys[0], wallet.pubkey());
+        assert!(keys.contains(&tip_payer.pubkey()));
+        assert_eq!(last.signatures.len(), 2);
+        assert!(last.verify_with_results().iter().all(|verified| *verified));
+    }
+}
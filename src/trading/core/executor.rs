@@ -2,6 +2,7 @@ use anyhow::{anyhow, Result};
 use std::sync::Arc;
 
 use super::{
+    outcome::TradeOutcome,
     parallel::parallel_execute_with_tips,
     params::{BuyParams, BuyWithTipParams, SellParams, SellWithTipParams},
     timer::TradeTimer,
@@ -15,7 +16,7 @@ use crate::{
     },
 };
 
-const MAX_LOADED_ACCOUNTS_DATA_SIZE_LIMIT: u32 = 256 * 1024;
+pub const MAX_LOADED_ACCOUNTS_DATA_SIZE_LIMIT: u32 = 256 * 1024;
 
 /// 通用交易执行器实现
 pub struct GenericTradeExecutor {
@@ -38,7 +39,7 @@ impl TradeExecutor for GenericTradeExecutor {
         &self,
         mut params: BuyParams,
         middleware_manager: Option<Arc<MiddlewareManager>>,
-    ) -> Result<()> {
+    ) -> Result<TradeOutcome> {
         if params.data_size_limit == 0 {
             params.data_size_limit = MAX_LOADED_ACCOUNTS_DATA_SIZE_LIMIT;
         }
@@ -84,14 +85,14 @@ impl TradeExecutor for GenericTradeExecutor {
         }
         timer.finish();
 
-        Ok(())
+        Ok(TradeOutcome::default())
     }
 
     async fn buy_with_tip(
         &self,
         mut params: BuyWithTipParams,
         middleware_manager: Option<Arc<MiddlewareManager>>,
-    ) -> Result<()> {
+    ) -> Result<TradeOutcome> {
         if params.data_size_limit == 0 {
             params.data_size_limit = MAX_LOADED_ACCOUNTS_DATA_SIZE_LIMIT;
         }
@@ -141,16 +142,14 @@ impl TradeExecutor for GenericTradeExecutor {
             true,
             params.wait_transaction_confirmed,
         )
-        .await?;
-
-        Ok(())
+        .await
     }
 
     async fn sell(
         &self,
         params: SellParams,
         middleware_manager: Option<Arc<MiddlewareManager>>,
-    ) -> Result<()> {
+    ) -> Result<TradeOutcome> {
         if params.rpc.is_none() {
             return Err(anyhow!("RPC is not set"));
         }
@@ -192,14 +191,14 @@ impl TradeExecutor for GenericTradeExecutor {
         }
         timer.finish();
 
-        Ok(())
+        Ok(TradeOutcome::default())
     }
 
     async fn sell_with_tip(
         &self,
         params: SellWithTipParams,
         middleware_manager: Option<Arc<MiddlewareManager>>,
-    ) -> Result<()> {
+    ) -> Result<TradeOutcome> {
         let timer = TradeTimer::new("构建卖出交易指令");
 
         // 转换为SellParams进行指令构建
@@ -245,9 +244,7 @@ impl TradeExecutor for GenericTradeExecutor {
             false,
             params.wait_transaction_confirmed,
         )
-        .await?;
-
-        Ok(())
+        .await
     }
 
     fn protocol_name(&self) -> &'static str {
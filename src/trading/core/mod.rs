@@ -2,4 +2,9 @@ pub mod params;
 pub mod traits;
 pub mod executor;
 pub mod parallel;
+pub mod outcome;
+pub mod swap_path;
+pub mod router;
+pub mod arbitrage;
+pub mod bundle;
 pub mod timer; 
\ No newline at end of file
@@ -0,0 +1,78 @@
+use anyhow::Result;
+use tokio::sync::oneshot;
+
+use crate::swqos::{
+    jito_bundle_tracker::BundleOutcome, rate_limit::SwqosThrottledError, SwqosClient, SwqosType,
+    TradeType,
+};
+
+/// 一次交易的发送结果
+///
+/// 通过 RPC 发送的交易没有 bundle 结果，也不会被限流。
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TradeOutcome {
+    /// 超出速率限制被跳过的服务
+    pub throttled: Vec<SwqosType>,
+    /// 以 bundle 发送的服务给出的结果，按完成顺序，包括被拒绝或未上链的 bundle
+    pub bundles: Vec<(SwqosType, BundleOutcome)>,
+}
+
+/// 取得服务的速率预算，并通过 `admission` 告知发送方是否被限流跳过
+pub(crate) async fn admit(
+    swqos_client: &SwqosClient,
+    trade_type: TradeType,
+    admission: oneshot::Sender<Option<SwqosType>>,
+) -> Result<()> {
+    let result = swqos_client.acquire(trade_type).await;
+    let throttled = result
+        .as_ref()
+        .err()
+        .and_then(|e| e.downcast_ref::<SwqosThrottledError>())
+        .map(|throttle| throttle.swqos_type.clone());
+    let _ = admission.send(throttled);
+    result
+}
+
+/// 等待每个服务取得速率预算，返回被限流跳过的服务；预算前就失败的服务不计入
+pub(crate) async fn throttled_types(
+    admissions: Vec<oneshot::Receiver<Option<SwqosType>>>,
+) -> Vec<SwqosType> {
+    futures::future::join_all(admissions)
+        .await
+        .into_iter()
+        .filter_map(|admission| admission.ok().flatten())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        common::SolanaRpcClient,
+        swqos::{
+            confirmation::{ConfirmationConfig, ConfirmationTracker},
+            jito::JitoClient,
+            rate_limit::{RateLimit, RateLimitedClient},
+        },
+    };
+    use std::sync::Arc;
+
+    #[tokio::test]
+    async fn test_throttled_types() {
+        let rpc = Arc::new(SolanaRpcClient::new("http://127.0.0.1:8899".to_string()));
+        let tracker = Arc::new(ConfirmationTracker::new(rpc, ConfirmationConfig::default()));
+        let inner: Arc<SwqosClient> =
+            Arc::new(JitoClient::new(tracker, "http://127.0.0.1".to_string(), "".to_string()));
+        let client = RateLimitedClient::new(inner, RateLimit::per_second(1));
+
+        let mut admissions = vec![];
+        for _ in 0..2 {
+            let (admission, admitted) = oneshot::channel();
+            admissions.push(admitted);
+            let _ = admit(&client, TradeType::Buy, admission).await;
+        }
+        // 预算前就失败的服务不计入
+        admissions.push(oneshot::channel().1);
+        assert_eq!(throttled_types(admissions).await, vec![SwqosType::Jito]);
+    }
+}
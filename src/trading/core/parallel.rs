@@ -2,23 +2,32 @@ use anyhow::{anyhow, Result};
 use solana_hash::Hash;
 use solana_sdk::{instruction::Instruction, pubkey::Pubkey, signature::Keypair};
 use std::{str::FromStr, sync::Arc};
-use tokio::sync::mpsc;
+use tokio::sync::{mpsc, oneshot};
 use tokio::task::JoinHandle;
 
 use crate::{
     common::PriorityFee,
-    swqos::{SwqosClient, SwqosType, TradeType},
+    protos::searcher_client::BundleRejectionError,
+    swqos::{
+        jito_bundle_tracker::BundleOutcome, rate_limit::SwqosThrottledError, SwqosClient, TradeType,
+    },
     trading::{
         common::{
             build_rpc_transaction, build_sell_tip_transaction_with_priority_fee,
             build_sell_transaction, build_tip_transaction_with_priority_fee,
         },
-        core::timer::TradeTimer,
+        core::{
+            outcome::{admit, throttled_types, TradeOutcome},
+            timer::TradeTimer,
+        },
         MiddlewareManager,
     },
 };
 
 /// 并行执行交易的通用函数
+///
+/// 任意一个服务成功即返回，结果中包括被限流跳过的服务和此前完成的服务给出的 bundle 结果。
+/// 不等待确认时在所有服务取得速率预算后返回
 pub async fn parallel_execute_with_tips(
     swqos_clients: Vec<Arc<SwqosClient>>,
     payer: Arc<Keypair>,
@@ -32,9 +41,10 @@ pub async fn parallel_execute_with_tips(
     protocol_name: String,
     is_buy: bool,
     wait_transaction_confirmed: bool,
-) -> Result<()> {
+) -> Result<TradeOutcome> {
     let cores = core_affinity::get_core_ids().unwrap();
-    let mut handles: Vec<JoinHandle<Result<()>>> = vec![];
+    let mut handles: Vec<JoinHandle<Result<Option<BundleOutcome>>>> = vec![];
+    let mut admissions = vec![];
 
     for i in 0..swqos_clients.len() {
         let swqos_client = swqos_clients[i].clone();
@@ -45,6 +55,8 @@ pub async fn parallel_execute_with_tips(
 
         let middleware_manager = middleware_manager.clone();
         let protocol_name = protocol_name.clone();
+        let (admission, admitted) = oneshot::channel();
+        admissions.push(admitted);
 
         let handle = tokio::spawn(async move {
             core_affinity::set_for_current(core_id);
@@ -53,7 +65,7 @@ pub async fn parallel_execute_with_tips(
                 TradeTimer::new(format!("构建交易指令: {:?}", swqos_client.get_swqos_type()));
 
             let transaction = if matches!(trade_type, TradeType::Sell)
-                && swqos_client.get_swqos_type() == SwqosType::Default
+                && swqos_client.get_swqos_type().is_tip_free()
             {
                 build_sell_transaction(
                     payer,
@@ -67,7 +79,7 @@ pub async fn parallel_execute_with_tips(
                 )
                 .await?
             } else if matches!(trade_type, TradeType::Sell)
-                && swqos_client.get_swqos_type() != SwqosType::Default
+                && !swqos_client.get_swqos_type().is_tip_free()
             {
                 let tip_account = swqos_client.get_tip_account()?;
                 let tip_account = Arc::new(Pubkey::from_str(&tip_account).map_err(|e| anyhow!(e))?);
@@ -83,7 +95,7 @@ pub async fn parallel_execute_with_tips(
                     is_buy,
                 )
                 .await?
-            } else if swqos_client.get_swqos_type() == SwqosType::Default {
+            } else if swqos_client.get_swqos_type().is_tip_free() {
                 build_rpc_transaction(
                     payer,
                     &priority_fee,
@@ -116,12 +128,14 @@ pub async fn parallel_execute_with_tips(
                 .await?
             };
 
+            admit(swqos_client.as_ref(), trade_type, admission).await?;
             timer.stage(format!("提交交易指令: {:?}", swqos_client.get_swqos_type()));
 
-            swqos_client.send_transaction(trade_type, &transaction).await?;
+            let bundle =
+                swqos_client.send_transaction_with_outcome(trade_type, &transaction).await?;
 
             timer.finish();
-            Ok::<(), anyhow::Error>(())
+            Ok::<Option<BundleOutcome>, anyhow::Error>(bundle)
         });
 
         handles.push(handle);
@@ -131,32 +145,63 @@ pub async fn parallel_execute_with_tips(
     let (tx, mut rx) = mpsc::channel(swqos_clients.len());
 
     // 启动监听任务
-    for handle in handles {
+    for (handle, swqos_client) in handles.into_iter().zip(&swqos_clients) {
         let tx = tx.clone();
+        let swqos_type = swqos_client.get_swqos_type();
         tokio::spawn(async move {
             let result = handle.await;
-            let _ = tx.send(result).await;
+            let _ = tx.send((swqos_type, result)).await;
         });
     }
     drop(tx); // 关闭发送端
 
     // 等待第一个成功的结果
     let mut errors = Vec::new();
+    // 保留 bundle 被拒绝的原因，调用方可以通过 downcast_ref 取得
+    let mut rejection = None;
+    // 超出速率限制被跳过的服务，没有 bundle 被拒绝时同样可以通过 downcast_ref 取得
+    let mut throttled = None;
+    let mut outcome = TradeOutcome::default();
 
     if !wait_transaction_confirmed {
-        return Ok(());
+        outcome.throttled = throttled_types(admissions).await;
+        return Ok(outcome);
     }
 
-    while let Some(result) = rx.recv().await {
+    while let Some((swqos_type, result)) = rx.recv().await {
         match result {
-            Ok(Ok(_)) => {
-                return Ok(());
+            Ok(Ok(bundle)) => {
+                outcome.bundles.extend(bundle.map(|bundle| (swqos_type, bundle)));
+                outcome.throttled = throttled_types(admissions).await;
+                return Ok(outcome);
+            }
+            Ok(Err(e)) => {
+                errors.push(format!("Task error: {}", e));
+                if let Some(throttle) = e.downcast_ref::<SwqosThrottledError>() {
+                    outcome.throttled.push(throttle.swqos_type.clone());
+                    if throttled.is_none() {
+                        throttled = Some(e);
+                    }
+                } else if let Some(bundle_rejection) = e.downcast_ref::<BundleRejectionError>() {
+                    outcome
+                        .bundles
+                        .push((swqos_type, BundleOutcome::Rejected(bundle_rejection.clone())));
+                    if rejection.is_none() {
+                        rejection = Some(e);
+                    }
+                }
             }
-            Ok(Err(e)) => errors.push(format!("Task error: {}", e)),
             Err(e) => errors.push(format!("Join error: {}", e)),
         }
     }
 
     // 如果没有成功的，返回错误
-    return Err(anyhow!("所有交易都失败了: {:?}", errors));
+    let mut message = format!("所有交易都失败了: {:?}", errors);
+    if !outcome.throttled.is_empty() {
+        message = format!("{}, 限流跳过: {:?}", message, outcome.throttled);
+    }
+    return Err(match rejection.or(throttled) {
+        Some(cause) => cause.context(message),
+        None => anyhow!(message),
+    });
 }
@@ -24,7 +24,13 @@ use crate::trading::common::get_multi_token_balances;
 use crate::trading::pumpswap::common::{
     coin_creator_vault_ata, coin_creator_vault_authority, get_token_balances,
 };
-use crate::trading::raydium_cpmm::common::get_pool_token_balances;
+use crate::trading::raydium_cpmm::common::{
+    fetch_transfer_fee, get_amm_config, get_pool_token_balances, pool_creator_fee_on,
+    pool_fee_rates, pool_vault_amounts,
+};
+use crate::utils::calc::raydium_cpmm::FeeRates;
+use solana_streamer_sdk::streaming::event_parser::protocols::raydium_cpmm::types::PoolState;
+use spl_token_2022::extension::transfer_fee::TransferFee;
 
 /// Common buy parameters
 /// Contains all necessary information for executing buy transactions
@@ -94,6 +100,19 @@ pub struct SellWithTipParams {
     pub protocol_params: Box<dyn ProtocolParams>,
 }
 
+/// Quote of a single swap, in the smallest units of the input and output tokens
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct SwapQuote {
+    /// Input amount passed to the builder
+    pub amount_in: u64,
+    /// Maximum input the instruction may spend, including slippage
+    pub max_amount_in: u64,
+    /// Expected output amount
+    pub amount_out: u64,
+    /// Minimum output enforced by the instruction
+    pub min_amount_out: u64,
+}
+
 /// PumpFun protocol specific parameters
 /// Configuration parameters specific to PumpFun trading protocol
 #[derive(Clone)]
@@ -141,6 +160,30 @@ impl PumpFunParams {
             close_token_account_when_sell: close_token_account_when_sell,
         }
     }
+
+    pub async fn from_mint_by_rpc(
+        rpc: &SolanaRpcClient,
+        mint: &Pubkey,
+    ) -> Result<Self, anyhow::Error> {
+        let (bonding_curve, bonding_curve_pda) =
+            crate::trading::pumpfun::common::fetch_bonding_curve_account(rpc, mint).await?;
+        let bonding_curve = BondingCurveAccount {
+            discriminator: 0,
+            account: bonding_curve_pda,
+            virtual_token_reserves: bonding_curve.virtual_token_reserves,
+            virtual_sol_reserves: bonding_curve.virtual_sol_reserves,
+            real_token_reserves: bonding_curve.real_token_reserves,
+            real_sol_reserves: bonding_curve.real_sol_reserves,
+            token_total_supply: bonding_curve.token_total_supply,
+            complete: bonding_curve.complete,
+            creator: bonding_curve.creator,
+        };
+        Ok(Self {
+            creator_vault: bonding_curve.get_creator_vault_pda(),
+            bonding_curve: Arc::new(bonding_curve),
+            close_token_account_when_sell: None,
+        })
+    }
 }
 
 impl ProtocolParams for PumpFunParams {
@@ -175,6 +218,8 @@ pub struct PumpSwapParams {
     pub pool_base_token_reserves: u64,
     /// Quote token reserves in the pool
     pub pool_quote_token_reserves: u64,
+    /// Total supply of the base token, prices the market cap fee tier of canonical pools
+    pub base_mint_supply: u64,
     /// Coin creator vault ATA
     pub coin_creator_vault_ata: Pubkey,
     /// Coin creator vault authority
@@ -196,6 +241,7 @@ impl PumpSwapParams {
             quote_mint: event.quote_mint,
             pool_base_token_reserves: event.pool_base_token_reserves,
             pool_quote_token_reserves: event.pool_quote_token_reserves,
+            base_mint_supply: crate::constants::pumpswap::accounts::BASE_MINT_SUPPLY,
             coin_creator_vault_ata: event.coin_creator_vault_ata,
             coin_creator_vault_authority: event.coin_creator_vault_authority,
             base_token_program: event.base_token_program,
@@ -211,6 +257,7 @@ impl PumpSwapParams {
             quote_mint: event.quote_mint,
             pool_base_token_reserves: event.pool_base_token_reserves,
             pool_quote_token_reserves: event.pool_quote_token_reserves,
+            base_mint_supply: crate::constants::pumpswap::accounts::BASE_MINT_SUPPLY,
             coin_creator_vault_ata: event.coin_creator_vault_ata,
             coin_creator_vault_authority: event.coin_creator_vault_authority,
             base_token_program: event.base_token_program,
@@ -219,6 +266,13 @@ impl PumpSwapParams {
         }
     }
 
+    /// Trade events do not carry the mint supply, `from_buy_trade`/`from_sell_trade` assume the
+    /// supply of a Pump.fun coin; set the real supply for mints that differ
+    pub fn with_base_mint_supply(mut self, base_mint_supply: u64) -> Self {
+        self.base_mint_supply = base_mint_supply;
+        self
+    }
+
     pub async fn from_pool_address_by_rpc(
         rpc: &SolanaRpcClient,
         pool_address: &Pubkey,
@@ -226,6 +280,7 @@ impl PumpSwapParams {
         let pool_data = crate::trading::pumpswap::common::fetch_pool(rpc, pool_address).await?;
         let (pool_base_token_reserves, pool_quote_token_reserves) =
             get_token_balances(&pool_data, rpc).await?;
+        let base_mint_supply = rpc.get_token_supply(&pool_data.base_mint).await?.amount.parse()?;
         let creator = pool_data.creator;
         let coin_creator_vault_ata = coin_creator_vault_ata(creator, pool_data.quote_mint);
         let coin_creator_vault_authority = coin_creator_vault_authority(creator);
@@ -249,6 +304,7 @@ impl PumpSwapParams {
             quote_mint: pool_data.quote_mint,
             pool_base_token_reserves: pool_base_token_reserves,
             pool_quote_token_reserves: pool_quote_token_reserves,
+            base_mint_supply,
             coin_creator_vault_ata: coin_creator_vault_ata,
             coin_creator_vault_authority: coin_creator_vault_authority,
             base_token_program: if pool_data.pool_base_token_account == base_token_program_ata {
@@ -266,6 +322,85 @@ impl PumpSwapParams {
     }
 }
 
+/// PumpSwap pool creation parameters
+///
+/// Describes a new PumpSwap pool and its initial liquidity. Pass it to
+/// `PumpSwapInstructionBuilder::build_create_pool_instructions` or
+/// `SolanaTrade::create_pumpswap_pool` to get the `create_pool` instructions together with
+/// ready-to-use `PumpSwapParams` for the new pool.
+#[derive(Clone)]
+pub struct PumpSwapCreatePoolParams {
+    /// Pool creator, pays for the pool accounts and provides the initial deposits
+    pub payer: Arc<Keypair>,
+    /// Pool index, lets one creator own several pools for the same pair
+    pub index: u16,
+    /// Base token mint address
+    pub base_mint: Pubkey,
+    /// Quote token mint address, usually WSOL
+    pub quote_mint: Pubkey,
+    /// Initial base token deposit
+    pub base_amount_in: u64,
+    /// Initial quote token deposit
+    pub quote_amount_in: u64,
+    /// Coin creator that receives creator fees, `Pubkey::default()` disables them
+    pub coin_creator: Pubkey,
+    /// Base token program ID
+    pub base_token_program: Pubkey,
+    /// Quote token program ID
+    pub quote_token_program: Pubkey,
+    /// Automatically handle WSOL wrapping
+    /// When true, wraps the WSOL side of the deposit before creation and closes the WSOL account after
+    pub auto_handle_wsol: bool,
+}
+
+impl PumpSwapCreatePoolParams {
+    pub fn new(
+        payer: Arc<Keypair>,
+        base_mint: Pubkey,
+        quote_mint: Pubkey,
+        base_amount_in: u64,
+        quote_amount_in: u64,
+    ) -> Self {
+        Self {
+            payer,
+            index: 0,
+            base_mint,
+            quote_mint,
+            base_amount_in,
+            quote_amount_in,
+            coin_creator: Pubkey::default(),
+            base_token_program: accounts::TOKEN_PROGRAM,
+            quote_token_program: accounts::TOKEN_PROGRAM,
+            auto_handle_wsol: true,
+        }
+    }
+
+    pub fn with_index(mut self, index: u16) -> Self {
+        self.index = index;
+        self
+    }
+
+    pub fn with_coin_creator(mut self, coin_creator: Pubkey) -> Self {
+        self.coin_creator = coin_creator;
+        self
+    }
+
+    pub fn with_token_programs(
+        mut self,
+        base_token_program: Pubkey,
+        quote_token_program: Pubkey,
+    ) -> Self {
+        self.base_token_program = base_token_program;
+        self.quote_token_program = quote_token_program;
+        self
+    }
+
+    pub fn with_auto_handle_wsol(mut self, auto_handle_wsol: bool) -> Self {
+        self.auto_handle_wsol = auto_handle_wsol;
+        self
+    }
+}
+
 impl ProtocolParams for PumpSwapParams {
     fn as_any(&self) -> &dyn std::any::Any {
         self
@@ -427,6 +562,18 @@ pub struct RaydiumCpmmParams {
     pub base_token_program: Pubkey,
     /// Quote token program ID (usually spl_token::ID or spl_token_2022::ID)
     pub quote_token_program: Pubkey,
+    /// AMM config account of the pool, selects the fee tier and is part of the pool address
+    pub amm_config: Pubkey,
+    /// Trade fee rate from the AMM config, in `FEE_RATE_DENOMINATOR_VALUE` units
+    pub trade_fee_rate: u64,
+    /// Protocol share of the trade fee from the AMM config
+    pub protocol_fee_rate: u64,
+    /// Fund share of the trade fee from the AMM config
+    pub fund_fee_rate: u64,
+    /// Creator fee rate, 0 when the pool has creator fees disabled
+    pub creator_fee_rate: u64,
+    /// Which token the creator fee is charged in: 0 both, 1 only token0, 2 only token1
+    pub creator_fee_on: u8,
     /// Whether to automatically handle wSOL wrapping and unwrapping
     pub auto_handle_wsol: bool,
 }
@@ -438,19 +585,50 @@ impl RaydiumCpmmParams {
     ) -> Result<Self, anyhow::Error> {
         let pool =
             crate::trading::raydium_cpmm::common::fetch_pool_state(rpc, pool_address).await?;
+        let amm_config =
+            crate::trading::raydium_cpmm::common::get_amm_config(rpc, &pool.amm_config).await?;
         let (token0_balance, token1_balance) =
             get_pool_token_balances(rpc, pool_address, &pool.token0_mint, &pool.token1_mint)
                 .await?;
+        let (base_reserve, quote_reserve) =
+            pool_vault_amounts(&pool, token0_balance, token1_balance);
+        let fee_rates = pool_fee_rates(&pool, &amm_config);
         Ok(Self {
             base_mint: pool.token0_mint,
             quote_mint: pool.token1_mint,
-            base_reserve: token0_balance,
-            quote_reserve: token1_balance,
+            base_reserve,
+            quote_reserve,
             base_token_program: pool.token0_program,
             quote_token_program: pool.token1_program,
+            amm_config: pool.amm_config,
+            trade_fee_rate: fee_rates.trade_fee_rate,
+            protocol_fee_rate: fee_rates.protocol_fee_rate,
+            fund_fee_rate: fee_rates.fund_fee_rate,
+            creator_fee_rate: fee_rates.creator_fee_rate,
+            creator_fee_on: pool_creator_fee_on(&pool),
             auto_handle_wsol: true,
         })
     }
+
+    /// Fee rates carried by these params
+    pub fn fee_rates(&self) -> FeeRates {
+        FeeRates {
+            trade_fee_rate: self.trade_fee_rate,
+            protocol_fee_rate: self.protocol_fee_rate,
+            fund_fee_rate: self.fund_fee_rate,
+            creator_fee_rate: self.creator_fee_rate,
+        }
+    }
+
+    /// Replace the fee tier, e.g. after reading a non-default `AmmConfig` for a new pool
+    pub fn with_fee_rates(mut self, amm_config: Pubkey, fee_rates: FeeRates) -> Self {
+        self.amm_config = amm_config;
+        self.trade_fee_rate = fee_rates.trade_fee_rate;
+        self.protocol_fee_rate = fee_rates.protocol_fee_rate;
+        self.fund_fee_rate = fee_rates.fund_fee_rate;
+        self.creator_fee_rate = fee_rates.creator_fee_rate;
+        self
+    }
 }
 
 impl ProtocolParams for RaydiumCpmmParams {
@@ -463,6 +641,172 @@ impl ProtocolParams for RaydiumCpmmParams {
     }
 }
 
+/// Describes a new Raydium CPMM pool and its initial liquidity. Mints can be given in any
+/// order, `RaydiumCpmmInstructionBuilder::build_initialize_instructions` sorts them into
+/// token0/token1 the way the program expects.
+#[derive(Clone)]
+pub struct RaydiumCpmmInitializeParams {
+    /// Pool creator, pays the pool creation fee and provides the initial deposits
+    pub payer: Arc<Keypair>,
+    /// AMM config that sets the pool fee tier
+    pub amm_config: Pubkey,
+    /// Fee rates of `amm_config`, carried into the returned pool params for quoting
+    pub fee_rates: FeeRates,
+    /// First token mint address
+    pub mint_0: Pubkey,
+    /// Second token mint address
+    pub mint_1: Pubkey,
+    /// Initial deposit of `mint_0`
+    pub init_amount_0: u64,
+    /// Initial deposit of `mint_1`
+    pub init_amount_1: u64,
+    /// Unix timestamp after which swaps are allowed, 0 opens the pool immediately
+    pub open_time: u64,
+    /// Token program ID of `mint_0` (spl_token::ID or spl_token_2022::ID)
+    pub token_0_program: Pubkey,
+    /// Token program ID of `mint_1` (spl_token::ID or spl_token_2022::ID)
+    pub token_1_program: Pubkey,
+    /// Automatically handle WSOL wrapping
+    /// When true, wraps the WSOL side of the deposit before initializing and closes the WSOL account after
+    pub auto_handle_wsol: bool,
+}
+
+impl RaydiumCpmmInitializeParams {
+    pub fn new(
+        payer: Arc<Keypair>,
+        mint_0: Pubkey,
+        mint_1: Pubkey,
+        init_amount_0: u64,
+        init_amount_1: u64,
+    ) -> Self {
+        Self {
+            payer,
+            amm_config: crate::constants::raydium_cpmm::accounts::AMM_CONFIG,
+            fee_rates: FeeRates::default(),
+            mint_0,
+            mint_1,
+            init_amount_0,
+            init_amount_1,
+            open_time: 0,
+            token_0_program: accounts::TOKEN_PROGRAM,
+            token_1_program: accounts::TOKEN_PROGRAM,
+            auto_handle_wsol: true,
+        }
+    }
+
+    /// Use another fee tier; `fee_rates` must be the rates of `amm_config`
+    pub fn with_amm_config(mut self, amm_config: Pubkey, fee_rates: FeeRates) -> Self {
+        self.amm_config = amm_config;
+        self.fee_rates = fee_rates;
+        self
+    }
+
+    /// Use another fee tier, loading its fee rates from chain
+    pub async fn with_amm_config_by_rpc(
+        self,
+        rpc: &SolanaRpcClient,
+        amm_config: Pubkey,
+    ) -> Result<Self, anyhow::Error> {
+        let config = get_amm_config(rpc, &amm_config).await?;
+        // Pools created with `initialize` have creator fees disabled
+        let fee_rates = FeeRates {
+            trade_fee_rate: config.trade_fee_rate,
+            protocol_fee_rate: config.protocol_fee_rate,
+            fund_fee_rate: config.fund_fee_rate,
+            creator_fee_rate: 0,
+        };
+        Ok(self.with_amm_config(amm_config, fee_rates))
+    }
+
+    pub fn with_open_time(mut self, open_time: u64) -> Self {
+        self.open_time = open_time;
+        self
+    }
+
+    pub fn with_token_programs(mut self, token_0_program: Pubkey, token_1_program: Pubkey) -> Self {
+        self.token_0_program = token_0_program;
+        self.token_1_program = token_1_program;
+        self
+    }
+
+    pub fn with_auto_handle_wsol(mut self, auto_handle_wsol: bool) -> Self {
+        self.auto_handle_wsol = auto_handle_wsol;
+        self
+    }
+}
+
+/// Deposit or withdraw parameters for a Raydium CPMM pool.
+/// The same parameters drive both `build_deposit_instructions` and `build_withdraw_instructions`;
+/// `lp_token_amount` is the amount of LP tokens to mint or burn.
+#[derive(Clone)]
+pub struct RaydiumCpmmLiquidityParams {
+    /// Liquidity provider
+    pub payer: Arc<Keypair>,
+    /// Pool state address
+    pub pool_state: Pubkey,
+    /// Decoded pool state
+    pub pool: PoolState,
+    /// token0 vault amount without protocol, fund and creator fees
+    pub token_0_vault_amount: u64,
+    /// token1 vault amount without protocol, fund and creator fees
+    pub token_1_vault_amount: u64,
+    /// Active Token-2022 transfer fee of token0, if any
+    pub token_0_transfer_fee: Option<TransferFee>,
+    /// Active Token-2022 transfer fee of token1, if any
+    pub token_1_transfer_fee: Option<TransferFee>,
+    /// LP tokens to mint (deposit) or burn (withdraw)
+    pub lp_token_amount: u64,
+    /// Slippage in basis points, defaults to `DEFAULT_SLIPPAGE`
+    pub slippage_basis_points: Option<u64>,
+    /// Automatically handle WSOL wrapping and unwrapping
+    pub auto_handle_wsol: bool,
+}
+
+impl RaydiumCpmmLiquidityParams {
+    pub async fn from_pool_address_by_rpc(
+        rpc: &SolanaRpcClient,
+        payer: Arc<Keypair>,
+        pool_address: &Pubkey,
+        lp_token_amount: u64,
+    ) -> Result<Self, anyhow::Error> {
+        let pool =
+            crate::trading::raydium_cpmm::common::fetch_pool_state(rpc, pool_address).await?;
+        let (token0_balance, token1_balance) =
+            get_multi_token_balances(rpc, &pool.token0_vault, &pool.token1_vault).await?;
+        let token_0_transfer_fee = fetch_transfer_fee(rpc, &pool.token0_mint).await?;
+        let token_1_transfer_fee = fetch_transfer_fee(rpc, &pool.token1_mint).await?;
+        let (token_0_vault_amount, token_1_vault_amount) =
+            pool_vault_amounts(&pool, token0_balance, token1_balance);
+        Ok(Self {
+            payer,
+            pool_state: *pool_address,
+            token_0_vault_amount,
+            token_1_vault_amount,
+            pool,
+            token_0_transfer_fee,
+            token_1_transfer_fee,
+            lp_token_amount,
+            slippage_basis_points: None,
+            auto_handle_wsol: true,
+        })
+    }
+
+    pub fn with_lp_token_amount(mut self, lp_token_amount: u64) -> Self {
+        self.lp_token_amount = lp_token_amount;
+        self
+    }
+
+    pub fn with_slippage_basis_points(mut self, slippage_basis_points: u64) -> Self {
+        self.slippage_basis_points = Some(slippage_basis_points);
+        self
+    }
+
+    pub fn with_auto_handle_wsol(mut self, auto_handle_wsol: bool) -> Self {
+        self.auto_handle_wsol = auto_handle_wsol;
+        self
+    }
+}
+
 /// RaydiumCpmm protocol specific parameters
 /// Configuration parameters specific to Raydium CPMM trading protocol
 #[derive(Clone)]
@@ -477,15 +821,19 @@ pub struct RaydiumAmmV4Params {
     pub token_coin: Pubkey,
     /// Pool's pc token account address
     pub token_pc: Pubkey,
-    /// Current coin reserve amount in the pool
+    /// Coin reserve the AMM program swaps against
     pub coin_reserve: u64,
-    /// Current pc reserve amount in the pool
+    /// Pc reserve the AMM program swaps against
     pub pc_reserve: u64,
     /// Whether to automatically handle wSOL wrapping and unwrapping
     pub auto_handle_wsol: bool,
+    /// Exact output to receive through `swap_base_out`; the buy `sol_amount` or sell
+    /// `token_amount` then caps the input instead of being spent in full
+    pub exact_amount_out: Option<u64>,
 }
 
 impl RaydiumAmmV4Params {
+    /// `coin_reserve` and `pc_reserve` are used as the reserves the AMM program swaps against
     pub fn from_amm_info_and_reserves(
         amm: Pubkey,
         amm_info: AmmInfo,
@@ -501,15 +849,36 @@ impl RaydiumAmmV4Params {
             coin_reserve,
             pc_reserve,
             auto_handle_wsol: true,
+            exact_amount_out: None,
         }
     }
+
+    /// `coin_vault_balance` and `pc_vault_balance` are the pool's vault balances, the PnL the
+    /// pool still owes to its owner is deducted from them. Funds on OpenBook are only counted by
+    /// `from_amm_address_by_rpc`.
+    pub fn from_amm_info_and_vault_balances(
+        amm: Pubkey,
+        amm_info: AmmInfo,
+        coin_vault_balance: u64,
+        pc_vault_balance: u64,
+    ) -> Self {
+        let (coin_reserve, pc_reserve) = crate::trading::raydium_amm_v4::common::effective_reserves(
+            &amm_info,
+            coin_vault_balance,
+            pc_vault_balance,
+            None,
+        );
+        Self::from_amm_info_and_reserves(amm, amm_info, coin_reserve, pc_reserve)
+    }
+
     pub async fn from_amm_address_by_rpc(
         rpc: &SolanaRpcClient,
         amm: Pubkey,
     ) -> Result<Self, anyhow::Error> {
         let amm_info = crate::trading::raydium_amm_v4::common::fetch_amm_info(rpc, amm).await?;
         let (coin_reserve, pc_reserve) =
-            get_multi_token_balances(rpc, &amm_info.token_coin, &amm_info.token_pc).await?;
+            crate::trading::raydium_amm_v4::common::fetch_effective_reserves(rpc, &amm_info)
+                .await?;
         Ok(Self {
             amm,
             coin_mint: amm_info.coin_mint,
@@ -519,8 +888,15 @@ impl RaydiumAmmV4Params {
             coin_reserve,
             pc_reserve,
             auto_handle_wsol: true,
+            exact_amount_out: None,
         })
     }
+
+    /// Swap for exactly `amount_out` through `swap_base_out`
+    pub fn with_exact_amount_out(mut self, amount_out: u64) -> Self {
+        self.exact_amount_out = Some(amount_out);
+        self
+    }
 }
 
 impl ProtocolParams for RaydiumAmmV4Params {
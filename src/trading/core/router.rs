@@ -0,0 +1,258 @@
+use anyhow::{anyhow, Result};
+use solana_sdk::{instruction::Instruction, pubkey::Pubkey};
+use std::sync::Arc;
+
+use super::{
+    params::{
+        BonkParams, PumpFunParams, PumpSwapParams, RaydiumAmmV4Params, RaydiumCpmmParams, SwapQuote,
+    },
+    swap_path::{SwapContext, SwapDirection, SwapLeg},
+};
+use crate::{
+    common::SolanaRpcClient,
+    constants::pumpswap::accounts::WSOL_TOKEN_ACCOUNT,
+    trading::{
+        factory::DexType, pumpswap::common::find_by_mint, raydium_amm_v4, raydium_cpmm,
+        MiddlewareManager,
+    },
+};
+
+/// Finds the pools trading `mint` against SOL that can be discovered from the mint alone
+///
+/// Looks up the PumpFun bonding curve, the Bonk launchpad pool, the PumpSwap pool and the
+/// deepest Raydium CPMM and AMM v4 pools paired with WSOL. Completed bonding curves, pools
+/// that don't accept swaps and pools that fail to load are skipped. AMM v4 pool addresses come
+/// from a program scan that is cached per mint, see `find_pools_by_mint_pair_cached`.
+pub async fn discover_pools(
+    rpc: &SolanaRpcClient,
+    mint: &Pubkey,
+    direction: SwapDirection,
+) -> Vec<SwapLeg> {
+    let (pumpfun, bonk, pumpswap, cpmm, amm_v4) = tokio::join!(
+        PumpFunParams::from_mint_by_rpc(rpc, mint),
+        BonkParams::from_mint_by_rpc(rpc, mint),
+        async {
+            let (pool_address, pool) = find_by_mint(rpc, mint).await?;
+            if pool.base_mint != WSOL_TOKEN_ACCOUNT && pool.quote_mint != WSOL_TOKEN_ACCOUNT {
+                return Err(anyhow!("PumpSwap pool {} is not paired with SOL", pool_address));
+            }
+            PumpSwapParams::from_pool_address_by_rpc(rpc, &pool_address).await
+        },
+        async {
+            let pools =
+                raydium_cpmm::common::find_pools_by_mint_pair(rpc, mint, &WSOL_TOKEN_ACCOUNT)
+                    .await?;
+            let pool = pools
+                .iter()
+                .find(|pool| pool.swap_enabled())
+                .ok_or_else(|| anyhow!("No Raydium CPMM pool accepts swaps for {}", mint))?;
+            RaydiumCpmmParams::from_pool_address_by_rpc(rpc, &pool.pool_address).await
+        },
+        async {
+            let pools = raydium_amm_v4::common::find_pools_by_mint_pair_cached(
+                rpc,
+                mint,
+                &WSOL_TOKEN_ACCOUNT,
+            )
+            .await?;
+            let pool = pools
+                .iter()
+                .find(|pool| raydium_amm_v4::common::swap_enabled(&pool.amm_info))
+                .ok_or_else(|| anyhow!("No Raydium AMM v4 pool accepts swaps for {}", mint))?;
+            RaydiumAmmV4Params::from_amm_address_by_rpc(rpc, pool.amm).await
+        },
+    );
+
+    let mut pools = vec![];
+    if let Ok(params) = pumpfun {
+        if !params.bonding_curve.complete {
+            pools.push(SwapLeg {
+                dex_type: DexType::PumpFun,
+                direction,
+                mint: *mint,
+                protocol_params: Box::new(params),
+            });
+        }
+    }
+    if let Ok(params) = bonk {
+        pools.push(SwapLeg {
+            dex_type: DexType::Bonk,
+            direction,
+            mint: *mint,
+            protocol_params: Box::new(params),
+        });
+    }
+    if let Ok(params) = pumpswap {
+        pools.push(SwapLeg {
+            dex_type: DexType::PumpSwap,
+            direction,
+            mint: *mint,
+            protocol_params: Box::new(params),
+        });
+    }
+    if let Ok(params) = cpmm {
+        pools.push(SwapLeg {
+            dex_type: DexType::RaydiumCpmm,
+            direction,
+            mint: *mint,
+            protocol_params: Box::new(params),
+        });
+    }
+    if let Ok(params) = amm_v4 {
+        pools.push(SwapLeg {
+            dex_type: DexType::RaydiumAmmV4,
+            direction,
+            mint: *mint,
+            protocol_params: Box::new(params),
+        });
+    }
+    pools
+}
+
+/// Part of an order routed to one pool
+#[derive(Clone)]
+pub struct RouteAllocation {
+    pub leg: SwapLeg,
+    pub quote: SwapQuote,
+}
+
+/// Order routed across one or more pools of the same mint
+#[derive(Clone, Default)]
+pub struct Route {
+    pub allocations: Vec<RouteAllocation>,
+    /// Total input spent across pools
+    pub amount_in: u64,
+    /// Total expected output after fees
+    pub amount_out: u64,
+    /// Total output guaranteed on-chain
+    pub min_amount_out: u64,
+}
+
+/// Parameters for routing an order
+#[derive(Clone)]
+pub struct RouteParams {
+    pub context: SwapContext,
+    pub mint: Pubkey,
+    pub direction: SwapDirection,
+    /// SOL to spend when buying, tokens to sell when selling
+    pub amount_in: u64,
+    /// Number of equal parts the order is split into; 1 sends everything to the best pool
+    pub split_parts: u32,
+}
+
+/// Picks the pools giving the best output after fees
+///
+/// The order is cut into `split_parts` equal parts and each part goes to the pool whose
+/// output grows the most by taking it, so a large order spreads across pools once the best
+/// pool's price impact makes another pool cheaper. Pools whose quote fails are ignored.
+pub async fn find_best_route(params: &RouteParams, pools: &[SwapLeg]) -> Result<Route> {
+    if params.amount_in == 0 {
+        return Err(anyhow!("Amount cannot be zero"));
+    }
+    if pools.is_empty() {
+        return Err(anyhow!("No pool found for mint {}", params.mint));
+    }
+
+    let parts = params.split_parts.max(1) as u64;
+    let part_amount = params.amount_in / parts;
+    let mut allocated = vec![0u64; pools.len()];
+    let mut quotes: Vec<Option<SwapQuote>> = vec![None; pools.len()];
+
+    for part in 0..parts {
+        let amount = if part == parts - 1 {
+            params.amount_in - part_amount * (parts - 1)
+        } else {
+            part_amount
+        };
+        if amount == 0 {
+            continue;
+        }
+
+        let mut best: Option<(usize, u64, SwapQuote)> = None;
+        for (i, pool) in pools.iter().enumerate() {
+            let quote = match params.context.quote_leg_within(pool, allocated[i] + amount).await {
+                Ok(Some(quote)) if quote.min_amount_out > 0 => quote,
+                _ => continue,
+            };
+            let current_out = quotes[i].map(|quote| quote.amount_out).unwrap_or(0);
+            let gain = quote.amount_out.saturating_sub(current_out);
+            if best.as_ref().is_none_or(|(_, best_gain, _)| gain > *best_gain) {
+                best = Some((i, gain, quote));
+            }
+        }
+        let (i, _, quote) =
+            best.ok_or_else(|| anyhow!("No pool can quote {} for mint {}", amount, params.mint))?;
+        allocated[i] += amount;
+        quotes[i] = Some(quote);
+    }
+
+    let mut route = Route::default();
+    for (pool, quote) in pools.iter().zip(quotes) {
+        if let Some(quote) = quote {
+            route.amount_in += quote.amount_in;
+            route.amount_out += quote.amount_out;
+            route.min_amount_out += quote.min_amount_out;
+            route.allocations.push(RouteAllocation { leg: pool.clone(), quote });
+        }
+    }
+    Ok(route)
+}
+
+/// Builds the instructions of every allocation of a route
+pub async fn build_route_instructions(
+    context: &SwapContext,
+    route: &Route,
+    middleware_manager: Option<Arc<MiddlewareManager>>,
+) -> Result<Vec<Instruction>> {
+    let mut instructions = vec![];
+    for allocation in &route.allocations {
+        instructions.extend(
+            context
+                .build_leg(&allocation.leg, allocation.quote.amount_in, middleware_manager.as_ref())
+                .await?,
+        );
+    }
+    Ok(instructions)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::trading::core::swap_path::test_support::{context, curve};
+    use solana_sdk::native_token::LAMPORTS_PER_SOL;
+
+    fn pool(mint: Pubkey, dev_sol_amount: u64) -> SwapLeg {
+        SwapLeg::buy(DexType::PumpFun, mint, curve(mint, dev_sol_amount))
+    }
+
+    fn route_params(mint: Pubkey, amount_in: u64, split_parts: u32) -> RouteParams {
+        RouteParams {
+            context: context(),
+            mint,
+            direction: SwapDirection::Buy,
+            amount_in,
+            split_parts,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_find_best_route() {
+        let mint = Pubkey::new_unique();
+        let pools = vec![pool(mint, 5 * LAMPORTS_PER_SOL), pool(mint, 0)];
+
+        // Everything goes to the cheaper curve without splitting
+        let route =
+            find_best_route(&route_params(mint, 20 * LAMPORTS_PER_SOL, 1), &pools).await.unwrap();
+        assert_eq!(route.allocations.len(), 1);
+        let single_out = route.amount_out;
+
+        // A large order spreads once the cheaper curve's price impact catches up
+        let route =
+            find_best_route(&route_params(mint, 20 * LAMPORTS_PER_SOL, 10), &pools).await.unwrap();
+        assert_eq!(route.allocations.len(), 2);
+        assert!(route.amount_out > single_out);
+        assert!(route.amount_in <= 20 * LAMPORTS_PER_SOL);
+
+        assert!(find_best_route(&route_params(mint, 1, 1), &[]).await.is_err());
+    }
+}
@@ -0,0 +1,512 @@
+use anyhow::{anyhow, Result};
+use solana_hash::Hash;
+use solana_sdk::{instruction::Instruction, pubkey::Pubkey, signature::Keypair};
+use std::sync::Arc;
+
+use super::{
+    params::{BuyParams, SellParams, SwapQuote},
+    traits::ProtocolParams,
+};
+use crate::{
+    common::{PriorityFee, SolanaRpcClient},
+    constants::{pumpswap::accounts::WSOL_TOKEN_ACCOUNT, trade::trade::DEFAULT_SLIPPAGE},
+    swqos::TradeType,
+    trading::{factory::DexType, MiddlewareManager, TradeFactory},
+};
+
+/// 调整输入金额使最大花费不超出预算的最大次数
+const MAX_SIZING_ROUNDS: usize = 4;
+
+/// Direction of a leg, every supported pool pairs a token with SOL
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SwapDirection {
+    /// Swap SOL for the leg's mint
+    Buy,
+    /// Swap the leg's mint for SOL
+    Sell,
+}
+
+impl SwapDirection {
+    /// Trade type that selects the tip and transaction layout for a swap in this direction
+    pub fn trade_type(&self) -> TradeType {
+        match self {
+            SwapDirection::Buy => TradeType::Buy,
+            SwapDirection::Sell => TradeType::Sell,
+        }
+    }
+}
+
+/// A single swap in a path
+#[derive(Clone)]
+pub struct SwapLeg {
+    pub dex_type: DexType,
+    pub direction: SwapDirection,
+    /// Token bought or sold against SOL
+    pub mint: Pubkey,
+    pub protocol_params: Box<dyn ProtocolParams>,
+}
+
+impl SwapLeg {
+    /// Leg swapping SOL for `mint`
+    pub fn buy(dex_type: DexType, mint: Pubkey, protocol_params: Box<dyn ProtocolParams>) -> Self {
+        Self { dex_type, direction: SwapDirection::Buy, mint, protocol_params }
+    }
+
+    /// Leg swapping `mint` for SOL
+    pub fn sell(dex_type: DexType, mint: Pubkey, protocol_params: Box<dyn ProtocolParams>) -> Self {
+        Self { dex_type, direction: SwapDirection::Sell, mint, protocol_params }
+    }
+
+    /// Mint spent by the leg, WSOL stands for SOL
+    pub fn input_mint(&self) -> Pubkey {
+        match self.direction {
+            SwapDirection::Buy => WSOL_TOKEN_ACCOUNT,
+            SwapDirection::Sell => self.mint,
+        }
+    }
+
+    /// Mint received by the leg, WSOL stands for SOL
+    pub fn output_mint(&self) -> Pubkey {
+        match self.direction {
+            SwapDirection::Buy => self.mint,
+            SwapDirection::Sell => WSOL_TOKEN_ACCOUNT,
+        }
+    }
+}
+
+/// Ordered legs executed in one transaction, each leg spends the previous leg's expected output
+///
+/// Token to token swaps go through SOL, e.g. Bonk token → SOL → PumpSwap token:
+///
+/// ```ignore
+/// let path = SwapPath::new()
+///     .sell(DexType::Bonk, bonk_mint, Box::new(bonk_params))
+///     .buy(DexType::PumpSwap, pumpswap_mint, Box::new(pumpswap_params));
+/// ```
+#[derive(Clone, Default)]
+pub struct SwapPath {
+    pub legs: Vec<SwapLeg>,
+}
+
+impl SwapPath {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_leg(mut self, leg: SwapLeg) -> Self {
+        self.legs.push(leg);
+        self
+    }
+
+    pub fn buy(self, dex_type: DexType, mint: Pubkey, params: Box<dyn ProtocolParams>) -> Self {
+        self.with_leg(SwapLeg::buy(dex_type, mint, params))
+    }
+
+    pub fn sell(self, dex_type: DexType, mint: Pubkey, params: Box<dyn ProtocolParams>) -> Self {
+        self.with_leg(SwapLeg::sell(dex_type, mint, params))
+    }
+
+    /// Direction of the path as a whole: a sell when it spends a token, a buy when it spends
+    /// SOL or has no legs
+    pub fn direction(&self) -> SwapDirection {
+        self.legs.first().map_or(SwapDirection::Buy, |leg| leg.direction)
+    }
+
+    /// Mint spent by the path
+    pub fn input_mint(&self) -> Option<Pubkey> {
+        self.legs.first().map(SwapLeg::input_mint)
+    }
+
+    /// Mint received by the path
+    pub fn output_mint(&self) -> Option<Pubkey> {
+        self.legs.last().map(SwapLeg::output_mint)
+    }
+
+    /// Checks that every leg spends the mint the previous leg received
+    pub fn validate(&self) -> Result<()> {
+        if self.legs.is_empty() {
+            return Err(anyhow!("Swap path has no legs"));
+        }
+        for (i, pair) in self.legs.windows(2).enumerate() {
+            if pair[0].output_mint() != pair[1].input_mint() {
+                return Err(anyhow!(
+                    "Swap path leg {} receives {} but leg {} spends {}",
+                    i,
+                    pair[0].output_mint(),
+                    i + 1,
+                    pair[1].input_mint()
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Quote of a swap path, built leg by leg
+#[derive(Clone, Debug, Default)]
+pub struct SwapPathQuote {
+    /// Quote of each leg, in path order
+    pub legs: Vec<SwapQuote>,
+    /// Amount spent by the first leg
+    pub amount_in: u64,
+    /// Expected output of the last leg
+    pub amount_out: u64,
+    /// Output of the last leg guaranteed on-chain
+    pub min_amount_out: u64,
+}
+
+/// Accounts and settings shared by every leg of a swap
+#[derive(Clone)]
+pub struct SwapContext {
+    pub rpc: Option<Arc<SolanaRpcClient>>,
+    pub payer: Arc<Keypair>,
+    /// Slippage applied to every leg, and end to end by the last leg of a swap path
+    pub slippage_basis_points: Option<u64>,
+    pub priority_fee: PriorityFee,
+    pub lookup_table_key: Option<Pubkey>,
+    pub recent_blockhash: Hash,
+    pub wait_transaction_confirmed: bool,
+}
+
+impl SwapContext {
+    fn buy_params(&self, leg: &SwapLeg, sol_amount: u64) -> BuyParams {
+        BuyParams {
+            rpc: self.rpc.clone(),
+            payer: self.payer.clone(),
+            mint: leg.mint,
+            sol_amount,
+            slippage_basis_points: self.slippage_basis_points,
+            priority_fee: self.priority_fee.clone(),
+            lookup_table_key: self.lookup_table_key,
+            recent_blockhash: self.recent_blockhash,
+            data_size_limit: 0,
+            wait_transaction_confirmed: self.wait_transaction_confirmed,
+            protocol_params: leg.protocol_params.clone(),
+        }
+    }
+
+    fn sell_params(&self, leg: &SwapLeg, token_amount: u64) -> SellParams {
+        SellParams {
+            rpc: self.rpc.clone(),
+            payer: self.payer.clone(),
+            mint: leg.mint,
+            token_amount: Some(token_amount),
+            slippage_basis_points: self.slippage_basis_points,
+            priority_fee: self.priority_fee.clone(),
+            lookup_table_key: self.lookup_table_key,
+            recent_blockhash: self.recent_blockhash,
+            wait_transaction_confirmed: self.wait_transaction_confirmed,
+            protocol_params: leg.protocol_params.clone(),
+        }
+    }
+
+    /// Quotes a leg for `amount_in`
+    pub async fn quote_leg(&self, leg: &SwapLeg, amount_in: u64) -> Result<SwapQuote> {
+        let builder = TradeFactory::create_instruction_builder(leg.dex_type.clone());
+        match leg.direction {
+            SwapDirection::Buy => builder.quote_buy(&self.buy_params(leg, amount_in)).await,
+            SwapDirection::Sell => builder.quote_sell(&self.sell_params(leg, amount_in)).await,
+        }
+    }
+
+    /// Quotes a leg for the largest input whose maximum spend fits in `available`
+    ///
+    /// Returns `None` when no such input is found.
+    pub async fn quote_leg_within(
+        &self,
+        leg: &SwapLeg,
+        available: u64,
+    ) -> Result<Option<SwapQuote>> {
+        let mut amount_in = available;
+        for _ in 0..MAX_SIZING_ROUNDS {
+            if amount_in == 0 {
+                break;
+            }
+            let quote = self.quote_leg(leg, amount_in).await?;
+            if quote.max_amount_in <= available {
+                return Ok(Some(quote));
+            }
+            amount_in =
+                (amount_in as u128 * available as u128 / quote.max_amount_in as u128) as u64;
+        }
+        Ok(None)
+    }
+
+    /// Builds the instructions of a leg for `amount_in`, after the protocol middlewares
+    pub async fn build_leg(
+        &self,
+        leg: &SwapLeg,
+        amount_in: u64,
+        middleware_manager: Option<&Arc<MiddlewareManager>>,
+    ) -> Result<Vec<Instruction>> {
+        let builder = TradeFactory::create_instruction_builder(leg.dex_type.clone());
+        let instructions = match leg.direction {
+            SwapDirection::Buy => {
+                builder.build_buy_instructions(&self.buy_params(leg, amount_in)).await?
+            }
+            SwapDirection::Sell => {
+                builder.build_sell_instructions(&self.sell_params(leg, amount_in)).await?
+            }
+        };
+        match middleware_manager {
+            Some(middleware_manager) => middleware_manager
+                .apply_middlewares_process_protocol_instructions(
+                    instructions,
+                    leg.dex_type.to_string(),
+                    leg.direction == SwapDirection::Buy,
+                ),
+            None => Ok(instructions),
+        }
+    }
+}
+
+/// Parameters for building a swap path
+#[derive(Clone)]
+pub struct SwapPathParams {
+    pub context: SwapContext,
+    pub path: SwapPath,
+    /// Amount of the path's input mint to spend
+    pub amount_in: u64,
+    /// End-to-end minimum output, enforced by the last leg when tighter than the slippage bound
+    pub min_amount_out: Option<u64>,
+}
+
+/// Builds the instructions of every leg of a swap path
+///
+/// The first leg spends at most `amount_in` and every following leg spends the previous leg's
+/// expected output, so nothing is left between legs when prices hold. Slippage is bounded once,
+/// end to end, by the last leg's minimum output: the expected output of the path less the
+/// context slippage, or `min_amount_out` when that is tighter. A leg that spends SOL never
+/// draws on the wallet: the leg before it must deliver all the SOL it may spend, so a shortfall
+/// fails the transaction, as a shortfall in a token does.
+pub async fn build_swap_path_instructions(
+    params: &SwapPathParams,
+    middleware_manager: Option<Arc<MiddlewareManager>>,
+) -> Result<(Vec<Instruction>, SwapPathQuote)> {
+    let (path_quote, contexts) = quote_swap_path(params).await?;
+
+    let mut instructions = vec![];
+    for ((leg, quote), context) in params.path.legs.iter().zip(&path_quote.legs).zip(&contexts) {
+        instructions
+            .extend(context.build_leg(leg, quote.amount_in, middleware_manager.as_ref()).await?);
+    }
+
+    Ok((instructions, path_quote))
+}
+
+/// Quotes every leg of a swap path, returning the context each leg is built with
+async fn quote_swap_path(params: &SwapPathParams) -> Result<(SwapPathQuote, Vec<SwapContext>)> {
+    params.path.validate()?;
+    if params.amount_in == 0 {
+        return Err(anyhow!("Amount cannot be zero"));
+    }
+
+    let legs = &params.path.legs;
+    let mut leg_quotes: Vec<SwapQuote> = Vec::with_capacity(legs.len());
+    let mut contexts = vec![params.context.clone(); legs.len()];
+    for (i, leg) in legs.iter().enumerate() {
+        // SOL comes from the previous leg, spend no more than it is expected to deliver
+        let available = match leg_quotes.last() {
+            None => Some(params.amount_in),
+            Some(previous) if leg.direction == SwapDirection::Buy => Some(previous.amount_out),
+            Some(_) => None,
+        };
+        let quote = match available {
+            Some(available) => {
+                params.context.quote_leg_within(leg, available).await?.ok_or_else(|| {
+                    anyhow!(
+                        "Swap path leg {} ({}) cannot spend {} within slippage",
+                        i,
+                        leg.dex_type,
+                        available
+                    )
+                })?
+            }
+            None => params.context.quote_leg(leg, leg_quotes[i - 1].amount_out).await?,
+        };
+        if quote.amount_out == 0 {
+            return Err(anyhow!("Swap path leg {} ({}) has no output", i, leg.dex_type));
+        }
+        if i > 0 && leg.direction == SwapDirection::Buy {
+            // The previous leg must deliver all the SOL this leg may spend
+            let (previous_quote, previous_context) = bound_min_amount_out(
+                &contexts[i - 1],
+                &legs[i - 1],
+                &leg_quotes[i - 1],
+                quote.max_amount_in,
+            )
+            .await
+            .map_err(|e| anyhow!("Swap path leg {} ({}): {}", i - 1, legs[i - 1].dex_type, e))?;
+            leg_quotes[i - 1] = previous_quote;
+            contexts[i - 1] = previous_context;
+        }
+        leg_quotes.push(quote);
+    }
+
+    // Tighten the last leg's slippage so its minimum output meets `min_amount_out`
+    let last = legs.len() - 1;
+    if let Some(min_amount_out) = params.min_amount_out {
+        let (last_quote, last_context) =
+            bound_min_amount_out(&contexts[last], &legs[last], &leg_quotes[last], min_amount_out)
+                .await
+                .map_err(|e| anyhow!("Swap path {}", e))?;
+        leg_quotes[last] = last_quote;
+        contexts[last] = last_context;
+    }
+
+    let path_quote = SwapPathQuote {
+        amount_in: leg_quotes[0].amount_in,
+        amount_out: leg_quotes[last].amount_out,
+        min_amount_out: leg_quotes[last].min_amount_out,
+        legs: leg_quotes,
+    };
+    Ok((path_quote, contexts))
+}
+
+/// Re-quotes a leg with tighter slippage so its minimum output is at least `min_amount_out`
+async fn bound_min_amount_out(
+    context: &SwapContext,
+    leg: &SwapLeg,
+    quote: &SwapQuote,
+    min_amount_out: u64,
+) -> Result<(SwapQuote, SwapContext)> {
+    let expected = quote.amount_out;
+    if expected < min_amount_out {
+        return Err(anyhow!("expects {} but min_amount_out is {}", expected, min_amount_out));
+    }
+    let slippage_basis_points = context.slippage_basis_points.unwrap_or(DEFAULT_SLIPPAGE);
+    let bound_basis_points =
+        ((expected - min_amount_out) as u128 * 10_000 / expected as u128) as u64;
+    let mut context = context.clone();
+    let mut quote = *quote;
+    if bound_basis_points < slippage_basis_points {
+        context.slippage_basis_points = Some(bound_basis_points);
+        quote = context.quote_leg(leg, quote.amount_in).await?;
+    }
+    if quote.min_amount_out < min_amount_out {
+        return Err(anyhow!(
+            "guarantees {} but min_amount_out is {}",
+            quote.min_amount_out,
+            min_amount_out
+        ));
+    }
+    Ok((quote, context))
+}
+
+/// Offline fixtures shared by the swap path and router tests
+#[cfg(test)]
+pub(crate) mod test_support {
+    use super::*;
+    use crate::{common::bonding_curve::BondingCurveAccount, trading::core::params::PumpFunParams};
+
+    /// PumpFun params of a fresh curve after a dev buy of `dev_sol_amount`
+    pub fn curve(mint: Pubkey, dev_sol_amount: u64) -> Box<dyn ProtocolParams> {
+        let dev_token_amount = if dev_sol_amount == 0 { 0 } else { 100_000_000_000_000 };
+        let bonding_curve =
+            BondingCurveAccount::from_dev_trade(&mint, dev_token_amount, dev_sol_amount, mint);
+        Box::new(PumpFunParams {
+            creator_vault: bonding_curve.get_creator_vault_pda(),
+            bonding_curve: Arc::new(bonding_curve),
+            close_token_account_when_sell: None,
+        })
+    }
+
+    /// Context without an RPC client and with 1% slippage
+    pub fn context() -> SwapContext {
+        SwapContext {
+            rpc: None,
+            payer: Arc::new(Keypair::new()),
+            slippage_basis_points: Some(100),
+            priority_fee: PriorityFee::default(),
+            lookup_table_key: None,
+            recent_blockhash: Hash::default(),
+            wait_transaction_confirmed: false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::test_support::{context, curve};
+    use super::*;
+    use crate::trading::core::params::PumpFunParams;
+    use solana_sdk::native_token::LAMPORTS_PER_SOL;
+
+    fn params() -> Box<dyn ProtocolParams> {
+        Box::new(PumpFunParams::immediate_sell(Pubkey::default(), false))
+    }
+
+    fn path_params(min_amount_out: Option<u64>) -> SwapPathParams {
+        let mint = Pubkey::new_unique();
+        SwapPathParams {
+            context: context(),
+            path: SwapPath::new().buy(DexType::PumpFun, mint, curve(mint, 0)).sell(
+                DexType::PumpFun,
+                mint,
+                curve(mint, 5 * LAMPORTS_PER_SOL),
+            ),
+            amount_in: LAMPORTS_PER_SOL,
+            min_amount_out,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_quote_swap_path_bounds_slippage_once() {
+        let (quote, contexts) = quote_swap_path(&path_params(None)).await.unwrap();
+        // The second leg spends the first leg's expected output, not its worst case
+        assert_eq!(quote.legs[1].amount_in, quote.legs[0].amount_out);
+        assert_eq!(quote.min_amount_out, quote.legs[1].min_amount_out);
+        assert!(quote.min_amount_out >= quote.amount_out * 9_900 / 10_000);
+        assert_eq!(contexts[1].slippage_basis_points, Some(100));
+
+        // A tighter end-to-end bound tightens only the last leg
+        let min_amount_out = quote.amount_out * 9_950 / 10_000;
+        let (tight, contexts) = quote_swap_path(&path_params(Some(min_amount_out))).await.unwrap();
+        assert_eq!(tight.legs[1].amount_in, quote.legs[1].amount_in);
+        assert!(tight.min_amount_out >= min_amount_out);
+        assert_eq!(contexts[0].slippage_basis_points, Some(100));
+        assert!(contexts[1].slippage_basis_points.unwrap() < 100);
+
+        assert!(quote_swap_path(&path_params(Some(quote.amount_out + 1))).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_quote_swap_path_never_tops_up_sol() {
+        let (token_a, token_b) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let params = SwapPathParams {
+            path: SwapPath::new()
+                .sell(DexType::PumpFun, token_a, curve(token_a, 5 * LAMPORTS_PER_SOL))
+                .buy(DexType::PumpFun, token_b, curve(token_b, 0)),
+            amount_in: 1_000_000_000_000,
+            ..path_params(None)
+        };
+        let (quote, contexts) = quote_swap_path(&params).await.unwrap();
+        // The buy leg may spend only SOL the sell leg is guaranteed to deliver
+        assert!(quote.legs[1].max_amount_in <= quote.legs[0].amount_out);
+        assert!(quote.legs[1].max_amount_in <= quote.legs[0].min_amount_out);
+        assert!(contexts[0].slippage_basis_points.unwrap() <= 100);
+    }
+
+    #[test]
+    fn test_validate_swap_path() {
+        let (token_a, token_b) = (Pubkey::new_unique(), Pubkey::new_unique());
+        assert!(SwapPath::new().validate().is_err());
+
+        let path = SwapPath::new().sell(DexType::Bonk, token_a, params()).buy(
+            DexType::PumpSwap,
+            token_b,
+            params(),
+        );
+        assert!(path.validate().is_ok());
+        assert_eq!(path.input_mint(), Some(token_a));
+        assert_eq!(path.output_mint(), Some(token_b));
+        assert!(matches!(path.direction().trade_type(), TradeType::Sell));
+
+        let path = SwapPath::new().buy(DexType::PumpFun, token_a, params()).sell(
+            DexType::PumpSwap,
+            token_b,
+            params(),
+        );
+        assert!(path.validate().is_err());
+    }
+}
@@ -1,25 +1,26 @@
 use std::sync::Arc;
 
-use anyhow::Result;
+use anyhow::{anyhow, Result};
 use solana_sdk::instruction::Instruction;
 use crate::trading::MiddlewareManager;
 
-use super::params::{BuyParams, BuyWithTipParams, SellParams, SellWithTipParams};
+use super::outcome::TradeOutcome;
+use super::params::{BuyParams, BuyWithTipParams, SellParams, SellWithTipParams, SwapQuote};
 
 /// 交易执行器trait - 定义了所有交易协议都需要实现的核心方法
 #[async_trait::async_trait]
 pub trait TradeExecutor: Send + Sync {
     /// 执行买入交易
-    async fn buy(&self, params: BuyParams, middleware_manager: Option<Arc<MiddlewareManager>>) -> Result<()>;
+    async fn buy(&self, params: BuyParams, middleware_manager: Option<Arc<MiddlewareManager>>) -> Result<TradeOutcome>;
 
     /// 使用MEV服务执行买入交易
-    async fn buy_with_tip(&self, params: BuyWithTipParams, middleware_manager: Option<Arc<MiddlewareManager>>) -> Result<()>;
+    async fn buy_with_tip(&self, params: BuyWithTipParams, middleware_manager: Option<Arc<MiddlewareManager>>) -> Result<TradeOutcome>;
 
     /// 执行卖出交易
-    async fn sell(&self, params: SellParams, middleware_manager: Option<Arc<MiddlewareManager>>) -> Result<()>;
+    async fn sell(&self, params: SellParams, middleware_manager: Option<Arc<MiddlewareManager>>) -> Result<TradeOutcome>;
 
     /// 使用MEV服务执行卖出交易
-    async fn sell_with_tip(&self, params: SellWithTipParams, middleware_manager: Option<Arc<MiddlewareManager>>) -> Result<()>;
+    async fn sell_with_tip(&self, params: SellWithTipParams, middleware_manager: Option<Arc<MiddlewareManager>>) -> Result<TradeOutcome>;
 
     /// 获取协议名称
     fn protocol_name(&self) -> &'static str;
@@ -33,6 +34,18 @@ pub trait InstructionBuilder: Send + Sync {
 
     /// 构建卖出指令
     async fn build_sell_instructions(&self, params: &SellParams) -> Result<Vec<Instruction>>;
+
+    /// 买入报价 - 与 build_buy_instructions 使用相同的计算, 返回最大花费与最少获得数量
+    /// 默认不支持报价, 此类协议无法用于交易路径与路由
+    async fn quote_buy(&self, _params: &BuyParams) -> Result<SwapQuote> {
+        Err(anyhow!("quote_buy is unsupported by this instruction builder"))
+    }
+
+    /// 卖出报价 - 与 build_sell_instructions 使用相同的计算, 返回最大花费与最少获得数量
+    /// 默认不支持报价, 此类协议无法用于交易路径与路由
+    async fn quote_sell(&self, _params: &SellParams) -> Result<SwapQuote> {
+        Err(anyhow!("quote_sell is unsupported by this instruction builder"))
+    }
 }
 
 /// 协议特定参数trait - 允许每个协议定义自己的参数
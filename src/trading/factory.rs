@@ -7,7 +7,10 @@ use crate::instruction::{
     raydium_cpmm::RaydiumCpmmInstructionBuilder,
 };
 
-use super::core::{executor::GenericTradeExecutor, traits::TradeExecutor};
+use super::core::{
+    executor::GenericTradeExecutor,
+    traits::{InstructionBuilder, TradeExecutor},
+};
 
 /// 支持的交易协议
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -76,6 +79,17 @@ impl TradeFactory {
         }
     }
 
+    /// 创建指定协议的指令构建器
+    pub fn create_instruction_builder(dex_type: DexType) -> Arc<dyn InstructionBuilder> {
+        match dex_type {
+            DexType::PumpFun => Arc::new(PumpFunInstructionBuilder),
+            DexType::PumpSwap => Arc::new(PumpSwapInstructionBuilder),
+            DexType::Bonk => Arc::new(BonkInstructionBuilder),
+            DexType::RaydiumCpmm => Arc::new(RaydiumCpmmInstructionBuilder),
+            DexType::RaydiumAmmV4 => Arc::new(RaydiumAmmV4InstructionBuilder),
+        }
+    }
+
     /// 获取所有支持的协议
     pub fn supported_dex_types() -> Vec<DexType> {
         vec![DexType::PumpFun, DexType::PumpSwap, DexType::Bonk, DexType::RaydiumCpmm]
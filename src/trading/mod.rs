@@ -8,6 +8,7 @@ pub mod pumpswap;
 pub mod raydium_amm_v4;
 pub mod raydium_cpmm;
 
+pub use core::outcome::TradeOutcome;
 pub use core::params::{BuyParams, BuyWithTipParams, SellParams, SellWithTipParams};
 pub use core::traits::{InstructionBuilder, TradeExecutor};
 pub use factory::TradeFactory;
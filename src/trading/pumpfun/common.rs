@@ -1,7 +1,16 @@
+use crate::solana_streamer_sdk::streaming::event_parser::protocols::pumpfun::types::{
+    global_decode, Global,
+};
 use crate::solana_streamer_sdk::streaming::event_parser::protocols::pumpfun::PumpFunTradeEvent;
 use crate::{
     common::{
-        bonding_curve::BondingCurveAccount, global::GlobalAccount, PriorityFee, SolanaRpcClient,
+        bonding_curve::BondingCurveAccount,
+        fee_config::FeeConfig,
+        global::GlobalAccount,
+        volume_accumulator::{
+            unix_timestamp, GlobalVolumeAccumulator, TokenIncentives, UserVolumeAccumulator,
+        },
+        PriorityFee, SolanaRpcClient,
     },
     constants::{self, trade::trade::DEFAULT_SLIPPAGE},
 };
@@ -14,6 +23,7 @@ use tokio::sync::RwLock;
 
 lazy_static::lazy_static! {
     static ref ACCOUNT_CACHE: RwLock<HashMap<Pubkey, Arc<GlobalAccount>>> = RwLock::new(HashMap::new());
+    static ref FEE_CONFIG_CACHE: RwLock<Option<Arc<FeeConfig>>> = RwLock::new(None);
 }
 
 #[inline]
@@ -87,11 +97,91 @@ pub fn get_metadata_pda(mint: &Pubkey) -> Pubkey {
 }
 
 #[inline]
-pub async fn get_global_account(/*rpc: &SolanaRpcClient*/
+pub fn get_fee_config_pda() -> Pubkey {
+    static FEE_CONFIG_PDA: once_cell::sync::Lazy<Pubkey> = once_cell::sync::Lazy::new(|| {
+        Pubkey::find_program_address(
+            &[
+                constants::pumpfun::seeds::FEE_CONFIG_SEED,
+                constants::pumpfun::accounts::PUMPFUN.as_ref(),
+            ],
+            &constants::pumpfun::accounts::FEE_PROGRAM,
+        )
+        .0
+    });
+    *FEE_CONFIG_PDA
+}
+
+/// Fetch the global account from RPC and cache it
+pub async fn fetch_global_account(
+    rpc: &SolanaRpcClient,
 ) -> Result<Arc<GlobalAccount>, anyhow::Error> {
-    let global_account = GlobalAccount::new();
-    let global_account = Arc::new(global_account);
-    Ok(global_account)
+    let global_pda = get_global_pda();
+    let account = rpc.get_account(&global_pda).await?;
+    if account.owner != constants::pumpfun::accounts::PUMPFUN || account.data.len() < 8 {
+        return Err(anyhow!("Global account not found"));
+    }
+    let global = global_decode(&account.data[8..])
+        .ok_or_else(|| anyhow!("Failed to decode global account"))?;
+    Ok(update_global_account(&global).await)
+}
+
+/// Cache a global account received from a stream, e.g. `PumpFunGlobalAccountEvent`
+pub async fn update_global_account(global: &Global) -> Arc<GlobalAccount> {
+    let global_pda = get_global_pda();
+    let global_account = Arc::new(GlobalAccount::from_global(global_pda, global));
+    ACCOUNT_CACHE.write().await.insert(global_pda, global_account.clone());
+    global_account
+}
+
+/// Get the global account, reading it from RPC on first use
+#[inline]
+pub async fn get_global_account(
+    rpc: &SolanaRpcClient,
+) -> Result<Arc<GlobalAccount>, anyhow::Error> {
+    if let Some(global_account) = ACCOUNT_CACHE.read().await.get(&get_global_pda()) {
+        return Ok(global_account.clone());
+    }
+    fetch_global_account(rpc).await
+}
+
+/// Get the cached global account, falling back to the constants when it has not been read yet
+#[inline]
+pub async fn get_cached_global_account() -> Arc<GlobalAccount> {
+    if let Some(global_account) = ACCOUNT_CACHE.read().await.get(&get_global_pda()) {
+        return global_account.clone();
+    }
+    Arc::new(GlobalAccount::new())
+}
+
+/// Fetch the fee config from RPC and cache it
+pub async fn fetch_fee_config(rpc: &SolanaRpcClient) -> Result<Arc<FeeConfig>, anyhow::Error> {
+    let account = rpc.get_account(&get_fee_config_pda()).await?;
+    if account.owner != constants::pumpfun::accounts::FEE_PROGRAM {
+        return Err(anyhow!("Fee config account not found"));
+    }
+    let fee_config =
+        FeeConfig::decode(&account.data).ok_or_else(|| anyhow!("Failed to decode fee config"))?;
+    Ok(update_fee_config(fee_config).await)
+}
+
+/// Cache a fee config received from a stream, decode the account data with `FeeConfig::decode`
+pub async fn update_fee_config(fee_config: FeeConfig) -> Arc<FeeConfig> {
+    let fee_config = Arc::new(fee_config);
+    *FEE_CONFIG_CACHE.write().await = Some(fee_config.clone());
+    fee_config
+}
+
+/// Get the cached fee config, `None` until it has been read
+#[inline]
+pub async fn get_cached_fee_config() -> Option<Arc<FeeConfig>> {
+    FEE_CONFIG_CACHE.read().await.clone()
+}
+
+/// Read the global account and fee config from RPC into the cache
+pub async fn refresh_fee_accounts(rpc: &SolanaRpcClient) -> Result<(), anyhow::Error> {
+    fetch_global_account(rpc).await?;
+    fetch_fee_config(rpc).await?;
+    Ok(())
 }
 
 #[inline]
@@ -122,6 +212,61 @@ pub async fn fetch_bonding_curve_account(
     Ok((Arc::new(bonding_curve), bonding_curve_pda))
 }
 
+/// Lamports above rent held by the creator vault of `creator`, claimable with `collect_creator_fee`
+///
+/// The vault is shared by every coin the creator launched.
+pub async fn get_creator_vault_balance(
+    rpc: &SolanaRpcClient,
+    creator: &Pubkey,
+) -> Result<u64, anyhow::Error> {
+    let creator_vault =
+        get_creator_vault_pda(creator).ok_or(anyhow!("Failed to derive creator vault"))?;
+    let lamports = rpc.get_balance(&creator_vault).await?;
+    Ok(lamports.saturating_sub(solana_sdk::rent::Rent::default().minimum_balance(0)))
+}
+
+/// Fetch the global volume accumulator, which holds the incentive mint and daily volumes
+pub async fn fetch_global_volume_accumulator(
+    rpc: &SolanaRpcClient,
+) -> Result<GlobalVolumeAccumulator, anyhow::Error> {
+    let address = get_global_volume_accumulator_pda()
+        .ok_or(anyhow!("Failed to derive global volume accumulator"))?;
+    let account = rpc.get_account(&address).await?;
+    if account.owner != constants::pumpfun::accounts::PUMPFUN {
+        return Err(anyhow!("Global volume accumulator not found"));
+    }
+    GlobalVolumeAccumulator::decode(&account.data)
+        .ok_or_else(|| anyhow!("Failed to decode global volume accumulator"))
+}
+
+/// Fetch the volume accumulator of `user`, `None` until the wallet trades or initializes it
+pub async fn fetch_user_volume_accumulator(
+    rpc: &SolanaRpcClient,
+    user: &Pubkey,
+) -> Result<Option<UserVolumeAccumulator>, anyhow::Error> {
+    let address = get_user_volume_accumulator_pda(user)
+        .ok_or(anyhow!("Failed to derive user volume accumulator"))?;
+    let Some(account) = rpc.get_account_with_commitment(&address, rpc.commitment()).await?.value
+    else {
+        return Ok(None);
+    };
+    UserVolumeAccumulator::decode(&account.data)
+        .map(Some)
+        .ok_or_else(|| anyhow!("Failed to decode user volume accumulator"))
+}
+
+/// Token incentives `user` earned by trading, `None` when the wallet has no volume accumulator
+pub async fn get_token_incentives(
+    rpc: &SolanaRpcClient,
+    user: &Pubkey,
+) -> Result<Option<TokenIncentives>, anyhow::Error> {
+    let (global, user) = tokio::try_join!(
+        fetch_global_volume_accumulator(rpc),
+        fetch_user_volume_accumulator(rpc, user)
+    )?;
+    Ok(user.map(|user| user.incentives(&global, unix_timestamp())))
+}
+
 #[inline]
 pub async fn init_bonding_curve_account(
     mint: &Pubkey,
@@ -1,9 +1,25 @@
-use crate::common::SolanaRpcClient;
-use crate::constants::pumpswap::accounts;
+use crate::common::{
+    fee_config::FeeConfig,
+    volume_accumulator::{
+        unix_timestamp, GlobalVolumeAccumulator, TokenIncentives, UserVolumeAccumulator,
+    },
+    SolanaRpcClient,
+};
+use crate::constants::pumpswap::{accounts, seeds};
+use crate::trading::common::get_token_balance_or_zero;
 use anyhow::anyhow;
 use solana_account_decoder::UiAccountEncoding;
 use solana_sdk::pubkey::Pubkey;
-use solana_streamer_sdk::streaming::event_parser::protocols::pumpswap::types::{pool_decode, Pool};
+use solana_streamer_sdk::streaming::event_parser::protocols::pumpswap::types::{
+    global_config_decode, pool_decode, GlobalConfig, Pool,
+};
+use std::sync::Arc;
+use tokio::sync::RwLock;
+
+lazy_static::lazy_static! {
+    static ref GLOBAL_CONFIG_CACHE: RwLock<Option<Arc<GlobalConfig>>> = RwLock::new(None);
+    static ref FEE_CONFIG_CACHE: RwLock<Option<Arc<FeeConfig>>> = RwLock::new(None);
+}
 
 // Find a pool for a specific mint
 pub async fn find_pool(rpc: &SolanaRpcClient, mint: &Pubkey) -> Result<Pubkey, anyhow::Error> {
@@ -55,6 +71,129 @@ pub fn get_global_volume_accumulator_pda() -> Option<Pubkey> {
     pda.map(|pubkey| pubkey.0)
 }
 
+pub fn get_pool_pda(
+    index: u16,
+    creator: &Pubkey,
+    base_mint: &Pubkey,
+    quote_mint: &Pubkey,
+) -> Option<Pubkey> {
+    let index_bytes = index.to_le_bytes();
+    let seeds: &[&[u8]; 5] = &[
+        crate::constants::pumpswap::seeds::POOL_SEED,
+        &index_bytes,
+        creator.as_ref(),
+        base_mint.as_ref(),
+        quote_mint.as_ref(),
+    ];
+    let program_id: &Pubkey = &crate::constants::pumpswap::accounts::AMM_PROGRAM;
+    let pda: Option<(Pubkey, u8)> = Pubkey::try_find_program_address(seeds, program_id);
+    pda.map(|pubkey| pubkey.0)
+}
+
+/// Pump.fun PDA that creates the canonical pool of a migrated coin
+pub fn get_pump_pool_authority_pda(base_mint: &Pubkey) -> Option<Pubkey> {
+    let seeds: &[&[u8]; 2] = &[seeds::POOL_AUTHORITY_SEED, base_mint.as_ref()];
+    let program_id: &Pubkey = &crate::constants::pumpfun::accounts::PUMPFUN;
+    let pda: Option<(Pubkey, u8)> = Pubkey::try_find_program_address(seeds, program_id);
+    pda.map(|pubkey| pubkey.0)
+}
+
+/// Whether `pool` is the canonical pool of a coin migrated from Pump.fun: the first
+/// `base_mint`/WSOL pool created by the Pump.fun pool authority. Only these pools are charged
+/// the market cap fee tiers, every other pool pays the flat fees.
+pub fn is_canonical_pool(pool: &Pubkey, base_mint: &Pubkey, quote_mint: &Pubkey) -> bool {
+    if *quote_mint != accounts::WSOL_TOKEN_ACCOUNT {
+        return false;
+    }
+    get_pump_pool_authority_pda(base_mint)
+        .and_then(|authority| get_pool_pda(0, &authority, base_mint, quote_mint))
+        .is_some_and(|canonical| canonical == *pool)
+}
+
+pub fn get_lp_mint_pda(pool: &Pubkey) -> Option<Pubkey> {
+    let seeds: &[&[u8]; 2] = &[crate::constants::pumpswap::seeds::POOL_LP_MINT_SEED, pool.as_ref()];
+    let program_id: &Pubkey = &crate::constants::pumpswap::accounts::AMM_PROGRAM;
+    let pda: Option<(Pubkey, u8)> = Pubkey::try_find_program_address(seeds, program_id);
+    pda.map(|pubkey| pubkey.0)
+}
+
+/// Pool vaults are the pool's associated token accounts for each side
+pub fn get_pool_token_account(pool: &Pubkey, mint: &Pubkey, token_program: &Pubkey) -> Pubkey {
+    spl_associated_token_account::get_associated_token_address_with_program_id(
+        pool,
+        mint,
+        token_program,
+    )
+}
+
+pub fn get_fee_config_pda() -> Pubkey {
+    static FEE_CONFIG_PDA: once_cell::sync::Lazy<Pubkey> = once_cell::sync::Lazy::new(|| {
+        Pubkey::find_program_address(
+            &[seeds::FEE_CONFIG_SEED, accounts::AMM_PROGRAM.as_ref()],
+            &accounts::FEE_PROGRAM,
+        )
+        .0
+    });
+    *FEE_CONFIG_PDA
+}
+
+/// Fetch the global config from RPC and cache it
+pub async fn fetch_global_config(
+    rpc: &SolanaRpcClient,
+) -> Result<Arc<GlobalConfig>, anyhow::Error> {
+    let account = rpc.get_account(&accounts::GLOBAL_ACCOUNT).await?;
+    if account.owner != accounts::AMM_PROGRAM || account.data.len() < 8 {
+        return Err(anyhow!("Global config not found"));
+    }
+    let global_config = global_config_decode(&account.data[8..])
+        .ok_or_else(|| anyhow!("Failed to decode global config"))?;
+    Ok(update_global_config(global_config).await)
+}
+
+/// Cache a global config received from a stream, e.g. `PumpSwapGlobalConfigAccountEvent`
+pub async fn update_global_config(global_config: GlobalConfig) -> Arc<GlobalConfig> {
+    let global_config = Arc::new(global_config);
+    *GLOBAL_CONFIG_CACHE.write().await = Some(global_config.clone());
+    global_config
+}
+
+/// Get the cached global config, `None` until it has been read
+#[inline]
+pub async fn get_cached_global_config() -> Option<Arc<GlobalConfig>> {
+    GLOBAL_CONFIG_CACHE.read().await.clone()
+}
+
+/// Fetch the fee config from RPC and cache it
+pub async fn fetch_fee_config(rpc: &SolanaRpcClient) -> Result<Arc<FeeConfig>, anyhow::Error> {
+    let account = rpc.get_account(&get_fee_config_pda()).await?;
+    if account.owner != accounts::FEE_PROGRAM {
+        return Err(anyhow!("Fee config account not found"));
+    }
+    let fee_config =
+        FeeConfig::decode(&account.data).ok_or_else(|| anyhow!("Failed to decode fee config"))?;
+    Ok(update_fee_config(fee_config).await)
+}
+
+/// Cache a fee config received from a stream, decode the account data with `FeeConfig::decode`
+pub async fn update_fee_config(fee_config: FeeConfig) -> Arc<FeeConfig> {
+    let fee_config = Arc::new(fee_config);
+    *FEE_CONFIG_CACHE.write().await = Some(fee_config.clone());
+    fee_config
+}
+
+/// Get the cached fee config, `None` until it has been read
+#[inline]
+pub async fn get_cached_fee_config() -> Option<Arc<FeeConfig>> {
+    FEE_CONFIG_CACHE.read().await.clone()
+}
+
+/// Read the global config and fee config from RPC into the cache
+pub async fn refresh_fee_accounts(rpc: &SolanaRpcClient) -> Result<(), anyhow::Error> {
+    fetch_global_config(rpc).await?;
+    fetch_fee_config(rpc).await?;
+    Ok(())
+}
+
 pub async fn fetch_pool(
     rpc: &SolanaRpcClient,
     pool_address: &Pubkey,
@@ -152,6 +291,62 @@ pub async fn find_by_mint(
     Err(anyhow!("No pool found for mint {}", mint))
 }
 
+/// Fees in `quote_mint` waiting in the coin creator vault, claimable with
+/// `collect_coin_creator_fee`
+///
+/// The vault is shared by every pool whose coin creator is `coin_creator`.
+pub async fn get_coin_creator_vault_balance(
+    rpc: &SolanaRpcClient,
+    coin_creator: &Pubkey,
+    quote_mint: &Pubkey,
+) -> Result<u64, anyhow::Error> {
+    let vault_ata = coin_creator_vault_ata(*coin_creator, *quote_mint);
+    // 金库账户在第一笔手续费到账前不存在
+    get_token_balance_or_zero(rpc, &vault_ata).await
+}
+
+/// Fetch the global volume accumulator, which holds the incentive mint and daily volumes
+pub async fn fetch_global_volume_accumulator(
+    rpc: &SolanaRpcClient,
+) -> Result<GlobalVolumeAccumulator, anyhow::Error> {
+    let address = get_global_volume_accumulator_pda()
+        .ok_or(anyhow!("Failed to derive global volume accumulator"))?;
+    let account = rpc.get_account(&address).await?;
+    if account.owner != accounts::AMM_PROGRAM {
+        return Err(anyhow!("Global volume accumulator not found"));
+    }
+    GlobalVolumeAccumulator::decode(&account.data)
+        .ok_or_else(|| anyhow!("Failed to decode global volume accumulator"))
+}
+
+/// Fetch the volume accumulator of `user`, `None` until the wallet trades or initializes it
+pub async fn fetch_user_volume_accumulator(
+    rpc: &SolanaRpcClient,
+    user: &Pubkey,
+) -> Result<Option<UserVolumeAccumulator>, anyhow::Error> {
+    let address = get_user_volume_accumulator_pda(user)
+        .ok_or(anyhow!("Failed to derive user volume accumulator"))?;
+    let Some(account) = rpc.get_account_with_commitment(&address, rpc.commitment()).await?.value
+    else {
+        return Ok(None);
+    };
+    UserVolumeAccumulator::decode(&account.data)
+        .map(Some)
+        .ok_or_else(|| anyhow!("Failed to decode user volume accumulator"))
+}
+
+/// Token incentives `user` earned by trading, `None` when the wallet has no volume accumulator
+pub async fn get_token_incentives(
+    rpc: &SolanaRpcClient,
+    user: &Pubkey,
+) -> Result<Option<TokenIncentives>, anyhow::Error> {
+    let (global, user) = tokio::try_join!(
+        fetch_global_volume_accumulator(rpc),
+        fetch_user_volume_accumulator(rpc, user)
+    )?;
+    Ok(user.map(|user| user.incentives(&global, unix_timestamp())))
+}
+
 pub async fn get_token_balances(
     pool: &Pool,
     rpc: &SolanaRpcClient,
@@ -1,10 +1,43 @@
 use anyhow::anyhow;
+use solana_account_decoder::UiAccountEncoding;
 use solana_sdk::pubkey::Pubkey;
 use solana_streamer_sdk::streaming::event_parser::protocols::raydium_amm_v4::types::{
-    amm_info_decode, AmmInfo,
+    amm_info_decode, AmmInfo, AMM_INFO_SIZE,
 };
+use std::{
+    collections::HashMap,
+    time::{Duration, Instant},
+};
+use tokio::sync::RwLock;
+
+use crate::{
+    common::{volume_accumulator::unix_timestamp, SolanaRpcClient},
+    constants::raydium_amm_v4::accounts,
+    trading::common::{
+        get_multi_token_balances, get_multiple_accounts_batched, token_account_amount,
+    },
+};
+
+/// Offset of `coin_mint` in an AMM account
+const AMM_INFO_COIN_MINT_OFFSET: usize = 400;
+/// Offset of `pc_mint` in an AMM account
+const AMM_INFO_PC_MINT_OFFSET: usize = 432;
+
+/// How long pool addresses found by scanning the program are reused before scanning again
+const POOL_DISCOVERY_TTL: Duration = Duration::from_secs(600);
+
+/// Pool addresses found by the last scan of a mint pair and when it ran
+type DiscoveredPools = HashMap<(Pubkey, Pubkey), (Vec<Pubkey>, Instant)>;
 
-use crate::common::SolanaRpcClient;
+lazy_static::lazy_static! {
+    // 按 (mint_a, mint_b) 缓存扫描得到的池子地址，没有池子的结果同样缓存
+    static ref POOL_DISCOVERY_CACHE: RwLock<DiscoveredPools> = RwLock::new(HashMap::new());
+}
+
+/// Offset of `native_coin_total` in an OpenBook open orders account
+const OPEN_ORDERS_NATIVE_COIN_TOTAL_OFFSET: usize = 85;
+/// Offset of `native_pc_total` in an OpenBook open orders account
+const OPEN_ORDERS_NATIVE_PC_TOTAL_OFFSET: usize = 101;
 
 pub async fn fetch_amm_info(rpc: &SolanaRpcClient, amm: Pubkey) -> Result<AmmInfo, anyhow::Error> {
     let amm_info = rpc.get_account_data(&amm).await?;
@@ -12,3 +45,325 @@ pub async fn fetch_amm_info(rpc: &SolanaRpcClient, amm: Pubkey) -> Result<AmmInf
         amm_info_decode(&amm_info).ok_or_else(|| anyhow!("Failed to decode amm info"))?;
     Ok(amm_info)
 }
+
+/// Whether the pool keeps liquidity on its OpenBook market
+///
+/// Mirrors `AmmStatus::orderbook_permission`: `Initialized`, `OrderBookOnly` and
+/// `WaitingTrade` pools place orders, swap-only pools don't.
+pub fn orderbook_enabled(amm_info: &AmmInfo) -> bool {
+    matches!(amm_info.status, 1 | 5 | 7)
+}
+
+/// Whether the pool currently accepts swaps, mirrors `AmmStatus::swap_permission`
+///
+/// `Initialized` and `SwapOnly` pools always swap, `WaitingTrade` pools once `pool_open_time`
+/// has passed.
+pub fn swap_enabled(amm_info: &AmmInfo) -> bool {
+    swap_enabled_at(amm_info, unix_timestamp())
+}
+
+/// Whether the pool accepts swaps at unix time `now`
+pub fn swap_enabled_at(amm_info: &AmmInfo, now: i64) -> bool {
+    match amm_info.status {
+        1 | 6 => true,
+        7 => now >= 0 && now as u64 >= amm_info.out_put.pool_open_time,
+        _ => false,
+    }
+}
+
+/// Decodes the coin and pc totals of an OpenBook open orders account
+pub fn open_orders_totals(data: &[u8]) -> Option<(u64, u64)> {
+    let read_u64 = |offset: usize| {
+        data.get(offset..offset + 8).map(|bytes| u64::from_le_bytes(bytes.try_into().unwrap()))
+    };
+    Some((
+        read_u64(OPEN_ORDERS_NATIVE_COIN_TOTAL_OFFSET)?,
+        read_u64(OPEN_ORDERS_NATIVE_PC_TOTAL_OFFSET)?,
+    ))
+}
+
+/// Fetches the coin and pc totals the pool holds in its OpenBook open orders account
+pub async fn fetch_open_orders_totals(
+    rpc: &SolanaRpcClient,
+    open_orders: &Pubkey,
+) -> Result<(u64, u64), anyhow::Error> {
+    let data = rpc.get_account_data(open_orders).await?;
+    open_orders_totals(&data).ok_or_else(|| anyhow!("Failed to decode open orders {}", open_orders))
+}
+
+/// Reserves the AMM program swaps against
+///
+/// Like the program's `calc_total_without_take_pnl`: vault balances plus the funds held in
+/// OpenBook, minus the PnL the pool still owes to its owner. Fills not yet consumed from the
+/// market's event queue are not accounted for.
+///
+/// # Returns
+/// Returns coin_reserve, pc_reserve
+pub fn effective_reserves(
+    amm_info: &AmmInfo,
+    coin_vault_amount: u64,
+    pc_vault_amount: u64,
+    open_orders_totals: Option<(u64, u64)>,
+) -> (u64, u64) {
+    let (coin_in_orderbook, pc_in_orderbook) = open_orders_totals.unwrap_or_default();
+    let coin_reserve = coin_vault_amount
+        .saturating_add(coin_in_orderbook)
+        .saturating_sub(amm_info.out_put.need_take_pnl_coin);
+    let pc_reserve = pc_vault_amount
+        .saturating_add(pc_in_orderbook)
+        .saturating_sub(amm_info.out_put.need_take_pnl_pc);
+    (coin_reserve, pc_reserve)
+}
+
+/// Fetches the vault balances and, for pools trading on OpenBook, the open orders totals,
+/// and returns the effective reserves
+///
+/// # Returns
+/// Returns coin_reserve, pc_reserve
+pub async fn fetch_effective_reserves(
+    rpc: &SolanaRpcClient,
+    amm_info: &AmmInfo,
+) -> Result<(u64, u64), anyhow::Error> {
+    let (coin_vault_amount, pc_vault_amount) =
+        get_multi_token_balances(rpc, &amm_info.token_coin, &amm_info.token_pc).await?;
+    let open_orders_totals = if orderbook_enabled(amm_info) {
+        Some(fetch_open_orders_totals(rpc, &amm_info.open_orders).await?)
+    } else {
+        None
+    };
+    Ok(effective_reserves(amm_info, coin_vault_amount, pc_vault_amount, open_orders_totals))
+}
+
+/// Raydium AMM v4 pool with its liquidity
+#[derive(Debug, Clone)]
+pub struct AmmV4Pool {
+    /// AMM pool address
+    pub amm: Pubkey,
+    pub amm_info: AmmInfo,
+    /// Effective coin reserve, see `effective_reserves`
+    pub coin_reserve: u64,
+    /// Effective pc reserve, see `effective_reserves`
+    pub pc_reserve: u64,
+}
+
+impl AmmV4Pool {
+    /// Reserve of `mint`, 0 if the pool doesn't trade it
+    pub fn reserve_of(&self, mint: &Pubkey) -> u64 {
+        if self.amm_info.coin_mint == *mint {
+            self.coin_reserve
+        } else if self.amm_info.pc_mint == *mint {
+            self.pc_reserve
+        } else {
+            0
+        }
+    }
+}
+
+/// Scans the AMM program for pools whose mint at each offset matches
+async fn scan_pools(
+    rpc: &SolanaRpcClient,
+    mints: Vec<(usize, Pubkey)>,
+) -> Result<Vec<(Pubkey, AmmInfo)>, anyhow::Error> {
+    let mut filters =
+        vec![solana_rpc_client_api::filter::RpcFilterType::DataSize(AMM_INFO_SIZE as u64)];
+    filters.extend(mints.into_iter().map(|(offset, mint)| {
+        solana_rpc_client_api::filter::RpcFilterType::Memcmp(
+            solana_client::rpc_filter::Memcmp::new_base58_encoded(offset, &mint.to_bytes()),
+        )
+    }));
+    let config = solana_rpc_client_api::config::RpcProgramAccountsConfig {
+        filters: Some(filters),
+        account_config: solana_rpc_client_api::config::RpcAccountInfoConfig {
+            encoding: Some(UiAccountEncoding::Base64),
+            data_slice: None,
+            commitment: None,
+            min_context_slot: None,
+        },
+        with_context: None,
+        sort_results: None,
+    };
+    let accounts = rpc.get_program_accounts_with_config(&accounts::RAYDIUM_AMM_V4, config).await?;
+    Ok(accounts
+        .into_iter()
+        .filter_map(|(address, account)| amm_info_decode(&account.data).map(|info| (address, info)))
+        .collect())
+}
+
+/// Loads the effective reserves of `pools` in batched requests, sorted by the reserve of
+/// `mint` in descending order
+async fn load_pools(
+    rpc: &SolanaRpcClient,
+    pools: Vec<(Pubkey, AmmInfo)>,
+    mint: &Pubkey,
+) -> Result<Vec<AmmV4Pool>, anyhow::Error> {
+    // 每个池子读取两个金库和 OpenBook open orders，未使用订单簿的池子不计入 open orders
+    let addresses: Vec<Pubkey> = pools
+        .iter()
+        .flat_map(|(_, info)| [info.token_coin, info.token_pc, info.open_orders])
+        .collect();
+    let accounts = get_multiple_accounts_batched(rpc, &addresses).await?;
+    let mut pools: Vec<AmmV4Pool> = pools
+        .into_iter()
+        .zip(accounts.chunks(3))
+        .filter_map(|((amm, amm_info), accounts)| {
+            let coin_vault_amount = token_account_amount(&accounts[0].as_ref()?.data)?;
+            let pc_vault_amount = token_account_amount(&accounts[1].as_ref()?.data)?;
+            let open_orders_totals = if orderbook_enabled(&amm_info) {
+                Some(open_orders_totals(&accounts[2].as_ref()?.data)?)
+            } else {
+                None
+            };
+            let (coin_reserve, pc_reserve) = effective_reserves(
+                &amm_info,
+                coin_vault_amount,
+                pc_vault_amount,
+                open_orders_totals,
+            );
+            Some(AmmV4Pool { amm, amm_info, coin_reserve, pc_reserve })
+        })
+        .collect();
+    pools.sort_by_key(|pool| std::cmp::Reverse(pool.reserve_of(mint)));
+    Ok(pools)
+}
+
+/// Finds every pool trading `mint` as coin or pc
+///
+/// # Returns
+/// Returns the pools sorted by the reserve of `mint`, deepest first
+pub async fn find_pools_by_mint(
+    rpc: &SolanaRpcClient,
+    mint: &Pubkey,
+) -> Result<Vec<AmmV4Pool>, anyhow::Error> {
+    let (as_coin, as_pc) = tokio::try_join!(
+        scan_pools(rpc, vec![(AMM_INFO_COIN_MINT_OFFSET, *mint)]),
+        scan_pools(rpc, vec![(AMM_INFO_PC_MINT_OFFSET, *mint)])
+    )?;
+    let pools: Vec<_> = as_coin.into_iter().chain(as_pc).collect();
+    if pools.is_empty() {
+        return Err(anyhow!("No pool found for mint {}", mint));
+    }
+    load_pools(rpc, pools, mint).await
+}
+
+/// Finds every pool trading `mint_a` against `mint_b`, in either coin/pc order
+///
+/// # Returns
+/// Returns the pools sorted by the reserve of `mint_a`, deepest first
+pub async fn find_pools_by_mint_pair(
+    rpc: &SolanaRpcClient,
+    mint_a: &Pubkey,
+    mint_b: &Pubkey,
+) -> Result<Vec<AmmV4Pool>, anyhow::Error> {
+    let pools = scan_pools_by_mint_pair(rpc, mint_a, mint_b).await?;
+    if pools.is_empty() {
+        return Err(anyhow!("No pool found for mints {} and {}", mint_a, mint_b));
+    }
+    load_pools(rpc, pools, mint_a).await
+}
+
+/// Like `find_pools_by_mint_pair`, but reuses the pool addresses found by the last scan of the
+/// pair for `POOL_DISCOVERY_TTL`
+///
+/// Program scans are slow and many RPC providers reject or rate-limit them. Pool state and
+/// reserves are still read fresh on every call, only pools created within the TTL are missed.
+pub async fn find_pools_by_mint_pair_cached(
+    rpc: &SolanaRpcClient,
+    mint_a: &Pubkey,
+    mint_b: &Pubkey,
+) -> Result<Vec<AmmV4Pool>, anyhow::Error> {
+    let key = (*mint_a, *mint_b);
+    let cached = POOL_DISCOVERY_CACHE
+        .read()
+        .await
+        .get(&key)
+        .filter(|(_, scanned_at)| scanned_at.elapsed() < POOL_DISCOVERY_TTL)
+        .map(|(amms, _)| amms.clone());
+    let pools = match cached {
+        Some(amms) => {
+            let accounts = get_multiple_accounts_batched(rpc, &amms).await?;
+            amms.into_iter()
+                .zip(accounts)
+                .filter_map(|(amm, account)| Some((amm, amm_info_decode(&account?.data)?)))
+                .collect()
+        }
+        None => {
+            let pools = scan_pools_by_mint_pair(rpc, mint_a, mint_b).await?;
+            let amms = pools.iter().map(|(amm, _)| *amm).collect();
+            POOL_DISCOVERY_CACHE.write().await.insert(key, (amms, Instant::now()));
+            pools
+        }
+    };
+    if pools.is_empty() {
+        return Err(anyhow!("No pool found for mints {} and {}", mint_a, mint_b));
+    }
+    load_pools(rpc, pools, mint_a).await
+}
+
+/// Scans the AMM program for pools trading `mint_a` against `mint_b`, in either coin/pc order
+async fn scan_pools_by_mint_pair(
+    rpc: &SolanaRpcClient,
+    mint_a: &Pubkey,
+    mint_b: &Pubkey,
+) -> Result<Vec<(Pubkey, AmmInfo)>, anyhow::Error> {
+    let (a_coin, b_coin) = tokio::try_join!(
+        scan_pools(
+            rpc,
+            vec![(AMM_INFO_COIN_MINT_OFFSET, *mint_a), (AMM_INFO_PC_MINT_OFFSET, *mint_b)]
+        ),
+        scan_pools(
+            rpc,
+            vec![(AMM_INFO_COIN_MINT_OFFSET, *mint_b), (AMM_INFO_PC_MINT_OFFSET, *mint_a)]
+        )
+    )?;
+    Ok(a_coin.into_iter().chain(b_coin).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_streamer_sdk::streaming::event_parser::protocols::raydium_amm_v4::types::OutPutData;
+
+    #[test]
+    fn test_effective_reserves() {
+        let amm_info = AmmInfo {
+            out_put: OutPutData {
+                need_take_pnl_coin: 10,
+                need_take_pnl_pc: 20,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        assert_eq!(effective_reserves(&amm_info, 1_000, 2_000, None), (990, 1_980));
+        assert_eq!(effective_reserves(&amm_info, 1_000, 2_000, Some((100, 200))), (1_090, 2_180));
+        assert_eq!(effective_reserves(&amm_info, 5, 2_000, None), (0, 1_980));
+    }
+
+    #[test]
+    fn test_swap_enabled_at() {
+        let amm_info = AmmInfo {
+            status: 7,
+            out_put: OutPutData { pool_open_time: 1_000, ..Default::default() },
+            ..Default::default()
+        };
+        // WaitingTrade pools swap once they open
+        assert!(!swap_enabled_at(&amm_info, 999));
+        assert!(swap_enabled_at(&amm_info, 1_000));
+        assert!(swap_enabled_at(&AmmInfo { status: 6, ..amm_info.clone() }, 0));
+        assert!(!swap_enabled_at(&AmmInfo { status: 5, ..amm_info }, 1_000));
+    }
+
+    #[test]
+    fn test_amm_info_mint_offsets() {
+        let (coin_mint, pc_mint) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let mut data = vec![0u8; AMM_INFO_SIZE];
+        data[AMM_INFO_COIN_MINT_OFFSET..AMM_INFO_COIN_MINT_OFFSET + 32]
+            .copy_from_slice(coin_mint.as_ref());
+        data[AMM_INFO_PC_MINT_OFFSET..AMM_INFO_PC_MINT_OFFSET + 32]
+            .copy_from_slice(pc_mint.as_ref());
+
+        let amm_info = amm_info_decode(&data).unwrap();
+        assert_eq!(amm_info.coin_mint, coin_mint);
+        assert_eq!(amm_info.pc_mint, pc_mint);
+    }
+}
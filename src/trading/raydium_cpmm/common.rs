@@ -5,11 +5,123 @@ use crate::{
         raydium_cpmm::accounts::{self},
     },
 };
+use crate::{
+    trading::common::{
+        get_multi_token_balances, get_multiple_accounts_batched, get_token_balance_or_zero,
+        token_account_amount,
+    },
+    utils::calc::raydium_cpmm::{
+        lp_tokens_to_trading_tokens, vault_amount_without_fees, FeeRates, RoundDirection,
+    },
+};
 use anyhow::anyhow;
+use solana_account_decoder::UiAccountEncoding;
 use solana_sdk::pubkey::Pubkey;
 use solana_streamer_sdk::streaming::event_parser::protocols::raydium_cpmm::types::{
-    pool_state_decode, PoolState,
+    amm_config_decode, pool_state_decode, AmmConfig, PoolState, POOL_STATE_SIZE,
+};
+use spl_token_2022::{
+    extension::{
+        transfer_fee::{TransferFee, TransferFeeConfig},
+        BaseStateWithExtensions, StateWithExtensions,
+    },
+    state::Mint,
 };
+use std::collections::HashMap;
+use tokio::sync::RwLock;
+
+/// Offset of `token0_mint` in a pool state account, including the discriminator
+const POOL_STATE_TOKEN0_MINT_OFFSET: usize = 168;
+/// Offset of `token1_mint` in a pool state account, including the discriminator
+const POOL_STATE_TOKEN1_MINT_OFFSET: usize = 200;
+
+lazy_static::lazy_static! {
+    static ref AMM_CONFIG_CACHE: RwLock<HashMap<Pubkey, AmmConfig>> = RwLock::new(HashMap::new());
+}
+
+pub async fn fetch_amm_config(
+    rpc: &SolanaRpcClient,
+    amm_config: &Pubkey,
+) -> Result<AmmConfig, anyhow::Error> {
+    let account = rpc.get_account(amm_config).await?;
+    if account.owner != accounts::RAYDIUM_CPMM {
+        return Err(anyhow!("Account is not owned by Raydium Cpmm program"));
+    }
+    let config = amm_config_decode(&account.data[8..])
+        .ok_or_else(|| anyhow!("Failed to decode amm config"))?;
+    AMM_CONFIG_CACHE.write().await.insert(*amm_config, config.clone());
+    Ok(config)
+}
+
+/// 获取 AmmConfig，优先使用缓存
+///
+/// AmmConfig 只有管理员会修改，首次读取后缓存，需要最新费率时调用 `fetch_amm_config`
+pub async fn get_amm_config(
+    rpc: &SolanaRpcClient,
+    amm_config: &Pubkey,
+) -> Result<AmmConfig, anyhow::Error> {
+    if let Some(config) = AMM_CONFIG_CACHE.read().await.get(amm_config) {
+        return Ok(config.clone());
+    }
+    fetch_amm_config(rpc, amm_config).await
+}
+
+/// AmmConfig 的创作者费率，位于 `fund_owner` 之后的第一个 u64
+pub fn amm_config_creator_fee_rate(config: &AmmConfig) -> u64 {
+    config.padding[0]
+}
+
+/// 池子的创作者费模式：0 双边收取，1 仅 token0，2 仅 token1
+pub fn pool_creator_fee_on(pool: &PoolState) -> u8 {
+    pool.padding[0].to_le_bytes()[0]
+}
+
+/// 池子是否开启了创作者费
+pub fn pool_enable_creator_fee(pool: &PoolState) -> bool {
+    pool.padding[0].to_le_bytes()[1] != 0
+}
+
+/// 池子中未领取的创作者费，返回 (token0, token1)
+pub fn pool_creator_fees(pool: &PoolState) -> (u64, u64) {
+    (pool.padding[1], pool.padding[2])
+}
+
+/// 池子的费率，未开启创作者费时创作者费率为 0
+pub fn pool_fee_rates(pool: &PoolState, config: &AmmConfig) -> FeeRates {
+    FeeRates {
+        trade_fee_rate: config.trade_fee_rate,
+        protocol_fee_rate: config.protocol_fee_rate,
+        fund_fee_rate: config.fund_fee_rate,
+        creator_fee_rate: if pool_enable_creator_fee(pool) {
+            amm_config_creator_fee_rate(config)
+        } else {
+            0
+        },
+    }
+}
+
+/// 金库余额扣除未领取的协议费、基金费和创作者费，返回 (token0, token1)
+pub fn pool_vault_amounts(
+    pool: &PoolState,
+    token0_balance: u64,
+    token1_balance: u64,
+) -> (u64, u64) {
+    let (creator_fees_token0, creator_fees_token1) = pool_creator_fees(pool);
+    (
+        vault_amount_without_fees(
+            token0_balance,
+            pool.protocol_fees_token0,
+            pool.fund_fees_token0,
+            creator_fees_token0,
+        ),
+        vault_amount_without_fees(
+            token1_balance,
+            pool.protocol_fees_token1,
+            pool.fund_fees_token1,
+            creator_fees_token1,
+        ),
+    )
+}
 
 pub async fn fetch_pool_state(
     rpc: &SolanaRpcClient,
@@ -36,6 +148,14 @@ pub fn get_pool_pda(amm_config: &Pubkey, mint1: &Pubkey, mint2: &Pubkey) -> Opti
     pda.map(|pubkey| pubkey.0)
 }
 
+pub fn get_amm_config_pda(index: u16) -> Option<Pubkey> {
+    let index = index.to_be_bytes();
+    let seeds: &[&[u8]; 2] = &[constants::raydium_cpmm::seeds::AMM_CONFIG_SEED, &index];
+    let program_id: &Pubkey = &constants::raydium_cpmm::accounts::RAYDIUM_CPMM;
+    let pda: Option<(Pubkey, u8)> = Pubkey::try_find_program_address(seeds, program_id);
+    pda.map(|pubkey| pubkey.0)
+}
+
 pub fn get_vault_pda(pool_state: &Pubkey, mint: &Pubkey) -> Option<Pubkey> {
     let seeds: &[&[u8]; 3] =
         &[constants::raydium_cpmm::seeds::POOL_VAULT_SEED, pool_state.as_ref(), mint.as_ref()];
@@ -52,6 +172,221 @@ pub fn get_observation_state_pda(pool_state: &Pubkey) -> Option<Pubkey> {
     pda.map(|pubkey| pubkey.0)
 }
 
+pub fn get_lp_mint_pda(pool_state: &Pubkey) -> Option<Pubkey> {
+    let seeds: &[&[u8]; 2] =
+        &[constants::raydium_cpmm::seeds::POOL_LP_MINT_SEED, pool_state.as_ref()];
+    let program_id: &Pubkey = &constants::raydium_cpmm::accounts::RAYDIUM_CPMM;
+    let pda: Option<(Pubkey, u8)> = Pubkey::try_find_program_address(seeds, program_id);
+    pda.map(|pubkey| pubkey.0)
+}
+
+/// 获取 Token-2022 代币当前 epoch 生效的转账手续费
+///
+/// # 返回值
+/// 普通 SPL 代币或没有 TransferFeeConfig 扩展的代币返回 None
+pub async fn fetch_transfer_fee(
+    rpc: &SolanaRpcClient,
+    mint: &Pubkey,
+) -> Result<Option<TransferFee>, anyhow::Error> {
+    let account = rpc.get_account(mint).await?;
+    if account.owner != accounts::TOKEN_PROGRAM_2022 {
+        return Ok(None);
+    }
+    let mint_state = StateWithExtensions::<Mint>::unpack(&account.data)
+        .map_err(|e| anyhow!("Failed to unpack mint {}: {}", mint, e))?;
+    let Ok(config) = mint_state.get_extension::<TransferFeeConfig>() else {
+        return Ok(None);
+    };
+    let epoch = rpc.get_epoch_info().await?.epoch;
+    Ok(Some(*config.get_epoch_fee(epoch)))
+}
+
+/// LP position of an owner in a Raydium CPMM pool
+#[derive(Debug, Clone)]
+pub struct LpPosition {
+    /// Pool state address
+    pub pool_state: Pubkey,
+    /// LP mint of the pool
+    pub lp_mint: Pubkey,
+    /// LP tokens held by the owner
+    pub lp_token_amount: u64,
+    /// Current LP supply of the pool
+    pub lp_supply: u64,
+    /// token0 the LP tokens can be withdrawn for, before transfer fees
+    pub token_0_amount: u64,
+    /// token1 the LP tokens can be withdrawn for, before transfer fees
+    pub token_1_amount: u64,
+    /// Share of the pool owned, between 0 and 1
+    pub share: f64,
+}
+
+/// 读取用户在 CPMM 池子中的 LP 仓位
+///
+/// LP 代币按池子金库余额（扣除未领取的协议费、基金费与创作者费）换算成两边代币数量
+pub async fn get_lp_position(
+    rpc: &SolanaRpcClient,
+    pool_address: &Pubkey,
+    owner: &Pubkey,
+) -> Result<LpPosition, anyhow::Error> {
+    let pool = fetch_pool_state(rpc, pool_address).await?;
+    let owner_lp_token = spl_associated_token_account::get_associated_token_address_with_program_id(
+        owner,
+        &pool.lp_mint,
+        &accounts::TOKEN_PROGRAM,
+    );
+    // 没有 LP 代币账户即没有仓位
+    let lp_token_amount = get_token_balance_or_zero(rpc, &owner_lp_token).await?;
+    let (token0_balance, token1_balance) =
+        get_multi_token_balances(rpc, &pool.token0_vault, &pool.token1_vault).await?;
+    let (token_0_vault_amount, token_1_vault_amount) =
+        pool_vault_amounts(&pool, token0_balance, token1_balance);
+    let amounts = lp_tokens_to_trading_tokens(
+        lp_token_amount,
+        pool.lp_supply,
+        token_0_vault_amount,
+        token_1_vault_amount,
+        RoundDirection::Floor,
+    )
+    .ok_or_else(|| anyhow!("Pool has no LP supply"))?;
+    let share = lp_token_amount as f64 / pool.lp_supply as f64;
+    Ok(LpPosition {
+        pool_state: *pool_address,
+        lp_mint: pool.lp_mint,
+        lp_token_amount,
+        lp_supply: pool.lp_supply,
+        token_0_amount: amounts.token_0_amount,
+        token_1_amount: amounts.token_1_amount,
+        share,
+    })
+}
+
+/// Raydium CPMM pool with its liquidity
+#[derive(Debug, Clone)]
+pub struct CpmmPool {
+    /// Pool state address
+    pub pool_address: Pubkey,
+    pub pool_state: PoolState,
+    /// token0 in the vault after unclaimed protocol, fund and creator fees
+    pub token0_reserve: u64,
+    /// token1 in the vault after unclaimed protocol, fund and creator fees
+    pub token1_reserve: u64,
+}
+
+impl CpmmPool {
+    /// Reserve of `mint`, 0 if the pool doesn't trade it
+    pub fn reserve_of(&self, mint: &Pubkey) -> u64 {
+        if self.pool_state.token0_mint == *mint {
+            self.token0_reserve
+        } else if self.pool_state.token1_mint == *mint {
+            self.token1_reserve
+        } else {
+            0
+        }
+    }
+
+    /// Whether the pool currently accepts swaps
+    pub fn swap_enabled(&self) -> bool {
+        // status 第 2 位为禁止兑换
+        self.pool_state.status & 0b100 == 0
+    }
+}
+
+/// 批量读取池子金库余额，按 `mint` 的储备从大到小排序
+async fn load_pools(
+    rpc: &SolanaRpcClient,
+    pools: Vec<(Pubkey, PoolState)>,
+    mint: &Pubkey,
+) -> Result<Vec<CpmmPool>, anyhow::Error> {
+    let vaults: Vec<Pubkey> =
+        pools.iter().flat_map(|(_, pool)| [pool.token0_vault, pool.token1_vault]).collect();
+    let vault_accounts = get_multiple_accounts_batched(rpc, &vaults).await?;
+    let mut pools: Vec<CpmmPool> = pools
+        .into_iter()
+        .zip(vault_accounts.chunks(2))
+        .filter_map(|((pool_address, pool_state), vaults)| {
+            let token0_balance = token_account_amount(&vaults[0].as_ref()?.data)?;
+            let token1_balance = token_account_amount(&vaults[1].as_ref()?.data)?;
+            let (token0_reserve, token1_reserve) =
+                pool_vault_amounts(&pool_state, token0_balance, token1_balance);
+            Some(CpmmPool { pool_address, pool_state, token0_reserve, token1_reserve })
+        })
+        .collect();
+    pools.sort_by_key(|pool| std::cmp::Reverse(pool.reserve_of(mint)));
+    Ok(pools)
+}
+
+/// 按代币对查找池子
+///
+/// 通过前 `AMM_CONFIG_INDEX_COUNT` 个 AmmConfig 推导池子 PDA，一次请求读取，不需要
+/// `getProgramAccounts`。只能找到地址为 PDA 的池子，这也是交易指令支持的池子。
+///
+/// # 返回值
+/// 返回按 `mint_a` 储备从大到小排序的池子
+pub async fn find_pools_by_mint_pair(
+    rpc: &SolanaRpcClient,
+    mint_a: &Pubkey,
+    mint_b: &Pubkey,
+) -> Result<Vec<CpmmPool>, anyhow::Error> {
+    let (token0_mint, token1_mint) =
+        if mint_a < mint_b { (mint_a, mint_b) } else { (mint_b, mint_a) };
+    let addresses: Vec<Pubkey> = (0..accounts::AMM_CONFIG_INDEX_COUNT)
+        .filter_map(get_amm_config_pda)
+        .filter_map(|amm_config| get_pool_pda(&amm_config, token0_mint, token1_mint))
+        .collect();
+    let pools: Vec<(Pubkey, PoolState)> = get_multiple_accounts_batched(rpc, &addresses)
+        .await?
+        .into_iter()
+        .zip(addresses)
+        .filter_map(|(account, address)| {
+            let account = account.filter(|account| account.owner == accounts::RAYDIUM_CPMM)?;
+            pool_state_decode(account.data.get(8..)?).map(|pool| (address, pool))
+        })
+        .collect();
+    if pools.is_empty() {
+        return Err(anyhow!("No pool found for mints {} and {}", mint_a, mint_b));
+    }
+    load_pools(rpc, pools, mint_a).await
+}
+
+/// 使用 getProgramAccounts 查找包含 `mint` 的所有池子，`mint` 可以是 token0 或 token1
+///
+/// # 返回值
+/// 返回按 `mint` 储备从大到小排序的池子
+pub async fn find_pools_by_mint(
+    rpc: &SolanaRpcClient,
+    mint: &Pubkey,
+) -> Result<Vec<CpmmPool>, anyhow::Error> {
+    let mut pools = vec![];
+    for offset in [POOL_STATE_TOKEN0_MINT_OFFSET, POOL_STATE_TOKEN1_MINT_OFFSET] {
+        let filters = vec![
+            solana_rpc_client_api::filter::RpcFilterType::DataSize((8 + POOL_STATE_SIZE) as u64),
+            solana_rpc_client_api::filter::RpcFilterType::Memcmp(
+                solana_client::rpc_filter::Memcmp::new_base58_encoded(offset, &mint.to_bytes()),
+            ),
+        ];
+        let config = solana_rpc_client_api::config::RpcProgramAccountsConfig {
+            filters: Some(filters),
+            account_config: solana_rpc_client_api::config::RpcAccountInfoConfig {
+                encoding: Some(UiAccountEncoding::Base64),
+                data_slice: None,
+                commitment: None,
+                min_context_slot: None,
+            },
+            with_context: None,
+            sort_results: None,
+        };
+        let accounts =
+            rpc.get_program_accounts_with_config(&accounts::RAYDIUM_CPMM, config).await?;
+        pools.extend(accounts.into_iter().filter_map(|(address, account)| {
+            pool_state_decode(account.data.get(8..)?).map(|pool| (address, pool))
+        }));
+    }
+    if pools.is_empty() {
+        return Err(anyhow!("No pool found for mint {}", mint));
+    }
+    load_pools(rpc, pools, mint).await
+}
+
 /// 获取池子中两个代币的余额
 ///
 /// # 返回值
@@ -113,6 +448,12 @@ mod tests {
         assert_eq!(result, Some(pool_state));
     }
 
+    #[test]
+    fn test_get_amm_config_pda() {
+        let amm_config = constants::raydium_cpmm::accounts::AMM_CONFIG;
+        assert_eq!(get_amm_config_pda(0), Some(amm_config));
+    }
+
     #[test]
     fn test_get_vault_pda() {
         // 测试get_vault_pda函数
@@ -1,10 +1,87 @@
 use solana_sdk::{native_token::sol_str_to_lamports, pubkey::Pubkey};
 
 use crate::{
+    common::{fee_config::FeeConfig, global::GlobalAccount},
     constants::pumpfun::global_constants::{CREATOR_FEE, FEE_BASIS_POINTS},
     utils::calc::common::compute_fee,
 };
 
+/// Fee basis points charged on a bonding curve trade.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PumpFunFees {
+    /// Protocol fee in basis points
+    pub fee_basis_points: u64,
+    /// Creator fee in basis points, only charged when the coin has a creator
+    pub creator_fee_basis_points: u64,
+}
+
+impl Default for PumpFunFees {
+    /// Fees from the crate constants, used when the on-chain config is unavailable
+    fn default() -> Self {
+        Self { fee_basis_points: FEE_BASIS_POINTS, creator_fee_basis_points: CREATOR_FEE }
+    }
+}
+
+impl PumpFunFees {
+    /// Total fee basis points for a coin with the given creator
+    pub fn total_basis_points(&self, creator: Pubkey) -> u64 {
+        self.fee_basis_points
+            + if creator != Pubkey::default() { self.creator_fee_basis_points } else { 0 }
+    }
+}
+
+/// Market cap of a bonding curve in lamports.
+///
+/// # Arguments
+/// * `virtual_sol_reserves` - Virtual SOL reserves in the bonding curve
+/// * `virtual_token_reserves` - Virtual token reserves in the bonding curve
+/// * `mint_supply` - Total supply of the token
+pub fn bonding_curve_market_cap(
+    virtual_sol_reserves: u128,
+    virtual_token_reserves: u128,
+    mint_supply: u128,
+) -> u128 {
+    if virtual_token_reserves == 0 {
+        return 0;
+    }
+    virtual_sol_reserves.saturating_mul(mint_supply) / virtual_token_reserves
+}
+
+/// Selects the fees for a bonding curve trade.
+///
+/// The market cap tier of the fee config wins when a fee config is available, otherwise the
+/// global account's fees apply.
+///
+/// # Arguments
+/// * `global` - Global account, read from chain or built from constants
+/// * `fee_config` - Fee config account, if it has been read
+/// * `virtual_sol_reserves` - Virtual SOL reserves in the bonding curve
+/// * `virtual_token_reserves` - Virtual token reserves in the bonding curve
+/// * `mint_supply` - Total supply of the token
+pub fn compute_fees(
+    global: &GlobalAccount,
+    fee_config: Option<&FeeConfig>,
+    virtual_sol_reserves: u128,
+    virtual_token_reserves: u128,
+    mint_supply: u128,
+) -> PumpFunFees {
+    match fee_config {
+        Some(fee_config) => {
+            let market_cap =
+                bonding_curve_market_cap(virtual_sol_reserves, virtual_token_reserves, mint_supply);
+            let fees = fee_config.get_fees(market_cap);
+            PumpFunFees {
+                fee_basis_points: fees.protocol_fee_bps,
+                creator_fee_basis_points: fees.creator_fee_bps,
+            }
+        }
+        None => PumpFunFees {
+            fee_basis_points: global.fee_basis_points,
+            creator_fee_basis_points: global.creator_fee,
+        },
+    }
+}
+
 /// Calculates the amount of tokens that can be purchased with a given SOL amount
 /// using the bonding curve formula.
 ///
@@ -14,6 +91,7 @@ use crate::{
 /// * `real_token_reserves` - Actual token reserves available for purchase
 /// * `creator` - Creator's public key (affects fee calculation)
 /// * `amount` - SOL amount to spend (in lamports)
+/// * `fees` - Fees for this bonding curve, see `compute_fees`
 ///
 /// # Returns
 /// The amount of tokens that will be received (in token's smallest unit)
@@ -23,6 +101,7 @@ pub fn get_buy_token_amount_from_sol_amount(
     real_token_reserves: u128,
     creator: Pubkey,
     amount: u64,
+    fees: &PumpFunFees,
 ) -> u64 {
     if amount == 0 {
         return 0;
@@ -32,8 +111,7 @@ pub fn get_buy_token_amount_from_sol_amount(
         return 0;
     }
 
-    let total_fee_basis_points =
-        FEE_BASIS_POINTS + if creator != Pubkey::default() { CREATOR_FEE } else { 0 };
+    let total_fee_basis_points = fees.total_basis_points(creator);
 
     // Convert to u128 to prevent overflow
     let amount_128 = amount as u128;
@@ -71,6 +149,7 @@ pub fn get_buy_token_amount_from_sol_amount(
 /// * `virtual_sol_reserves` - Virtual SOL reserves in the bonding curve
 /// * `creator` - Creator's public key (affects fee calculation)
 /// * `amount` - Token amount to sell (in token's smallest unit)
+/// * `fees` - Fees for this bonding curve, see `compute_fees`
 ///
 /// # Returns
 /// The amount of SOL that will be received after fees (in lamports)
@@ -79,6 +158,7 @@ pub fn get_sell_sol_amount_from_token_amount(
     virtual_sol_reserves: u128,
     creator: Pubkey,
     amount: u64,
+    fees: &PumpFunFees,
 ) -> u64 {
     if amount == 0 {
         return 0;
@@ -97,8 +177,7 @@ pub fn get_sell_sol_amount_from_token_amount(
 
     let sol_cost = numerator.checked_div(denominator).unwrap_or(0);
 
-    let total_fee_basis_points =
-        FEE_BASIS_POINTS + if creator != Pubkey::default() { CREATOR_FEE } else { 0 };
+    let total_fee_basis_points = fees.total_basis_points(creator);
     let total_fee_basis_points_128 = total_fee_basis_points as u128;
 
     // Calculate transaction fee
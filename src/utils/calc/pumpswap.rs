@@ -1,10 +1,111 @@
 use super::common::{
     calculate_with_slippage_buy, calculate_with_slippage_sell, ceil_div, compute_fee,
 };
-use crate::constants::pumpswap::accounts::{
-    COIN_CREATOR_FEE_BASIS_POINTS, LP_FEE_BASIS_POINTS, PROTOCOL_FEE_BASIS_POINTS,
+use crate::{
+    common::fee_config::FeeConfig,
+    constants::pumpswap::accounts::{
+        COIN_CREATOR_FEE_BASIS_POINTS, LP_FEE_BASIS_POINTS, PROTOCOL_FEE_BASIS_POINTS,
+    },
 };
 use solana_sdk::pubkey::Pubkey;
+use solana_streamer_sdk::streaming::event_parser::protocols::pumpswap::types::GlobalConfig;
+
+/// Fee basis points charged on a pool trade
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PumpSwapFees {
+    /// LP fee in basis points
+    pub lp_fee_basis_points: u64,
+    /// Protocol fee in basis points
+    pub protocol_fee_basis_points: u64,
+    /// Coin creator fee in basis points, only charged when the pool has a coin creator
+    pub coin_creator_fee_basis_points: u64,
+}
+
+impl Default for PumpSwapFees {
+    /// Fees from the crate constants, used when the on-chain config is unavailable
+    fn default() -> Self {
+        Self {
+            lp_fee_basis_points: LP_FEE_BASIS_POINTS,
+            protocol_fee_basis_points: PROTOCOL_FEE_BASIS_POINTS,
+            coin_creator_fee_basis_points: COIN_CREATOR_FEE_BASIS_POINTS,
+        }
+    }
+}
+
+impl PumpSwapFees {
+    /// Coin creator fee basis points for a pool with the given coin creator
+    pub fn coin_creator_basis_points(&self, coin_creator: &Pubkey) -> u64 {
+        if *coin_creator == Pubkey::default() {
+            0
+        } else {
+            self.coin_creator_fee_basis_points
+        }
+    }
+
+    /// Total fee basis points for a pool with the given coin creator
+    pub fn total_basis_points(&self, coin_creator: &Pubkey) -> u64 {
+        self.lp_fee_basis_points
+            + self.protocol_fee_basis_points
+            + self.coin_creator_basis_points(coin_creator)
+    }
+}
+
+/// Market cap of a pool in quote tokens.
+///
+/// # Arguments
+/// * `base_reserve` - Base token reserves in the pool
+/// * `quote_reserve` - Quote token reserves in the pool
+/// * `base_mint_supply` - Total supply of the base token
+pub fn pool_market_cap(base_reserve: u64, quote_reserve: u64, base_mint_supply: u64) -> u128 {
+    if base_reserve == 0 {
+        return 0;
+    }
+    (quote_reserve as u128).saturating_mul(base_mint_supply as u128) / base_reserve as u128
+}
+
+/// Selects the fees for a pool trade.
+///
+/// When a fee config is available, canonical pools of migrated Pump.fun coins pay the market
+/// cap tier and every other pool pays the flat fees. Without a fee config the global config's
+/// fees apply, and the crate constants when neither has been read.
+///
+/// # Arguments
+/// * `global_config` - PumpSwap global config, if it has been read
+/// * `fee_config` - Fee config account, if it has been read
+/// * `is_canonical_pool` - Whether the pool is the canonical pool of a migrated coin
+/// * `base_reserve` - Base token reserves in the pool
+/// * `quote_reserve` - Quote token reserves in the pool
+/// * `base_mint_supply` - Total supply of the base token
+pub fn compute_fees(
+    global_config: Option<&GlobalConfig>,
+    fee_config: Option<&FeeConfig>,
+    is_canonical_pool: bool,
+    base_reserve: u64,
+    quote_reserve: u64,
+    base_mint_supply: u64,
+) -> PumpSwapFees {
+    if let Some(fee_config) = fee_config {
+        let fees = if is_canonical_pool {
+            let market_cap = pool_market_cap(base_reserve, quote_reserve, base_mint_supply);
+            fee_config.get_fees(market_cap)
+        } else {
+            fee_config.flat_fees
+        };
+        return PumpSwapFees {
+            lp_fee_basis_points: fees.lp_fee_bps,
+            protocol_fee_basis_points: fees.protocol_fee_bps,
+            coin_creator_fee_basis_points: fees.creator_fee_bps,
+        };
+    }
+    match global_config {
+        Some(global_config) => PumpSwapFees {
+            lp_fee_basis_points: global_config.lp_fee_basis_points,
+            protocol_fee_basis_points: global_config.protocol_fee_basis_points,
+            coin_creator_fee_basis_points: global_config.coin_creator_fee_basis_points,
+        },
+        None => PumpSwapFees::default(),
+    }
+}
 
 /// Result for buying base tokens with base amount input
 #[derive(Clone, Debug)]
@@ -58,6 +159,7 @@ pub struct SellQuoteInputResult {
 /// * `base_reserve` - Base token reserves in the pool
 /// * `quote_reserve` - Quote token reserves in the pool
 /// * `coin_creator` - Token creator address
+/// * `fees` - Fees for this pool, see `compute_fees`
 ///
 /// # Returns
 /// * `BuyBaseInputResult` containing quote amounts and slippage calculations
@@ -67,6 +169,7 @@ pub fn buy_base_input_internal(
     base_reserve: u64,
     quote_reserve: u64,
     coin_creator: &Pubkey,
+    fees: &PumpSwapFees,
 ) -> Result<BuyBaseInputResult, String> {
     if base_reserve == 0 || quote_reserve == 0 {
         return Err("Invalid input: 'baseReserve' or 'quoteReserve' cannot be zero.".to_string());
@@ -86,14 +189,12 @@ pub fn buy_base_input_internal(
     let quote_amount_in = ceil_div(numerator, denominator as u128) as u64;
 
     // Calculate fees
-    let lp_fee = compute_fee(quote_amount_in as u128, LP_FEE_BASIS_POINTS as u128) as u64;
+    let lp_fee = compute_fee(quote_amount_in as u128, fees.lp_fee_basis_points as u128) as u64;
     let protocol_fee =
-        compute_fee(quote_amount_in as u128, PROTOCOL_FEE_BASIS_POINTS as u128) as u64;
-    let coin_creator_fee = if *coin_creator == Pubkey::default() {
-        0
-    } else {
-        compute_fee(quote_amount_in as u128, COIN_CREATOR_FEE_BASIS_POINTS as u128) as u64
-    };
+        compute_fee(quote_amount_in as u128, fees.protocol_fee_basis_points as u128) as u64;
+    let coin_creator_fee_bps = fees.coin_creator_basis_points(coin_creator);
+    let coin_creator_fee =
+        compute_fee(quote_amount_in as u128, coin_creator_fee_bps as u128) as u64;
     let total_quote = quote_amount_in + lp_fee + protocol_fee + coin_creator_fee;
 
     // Calculate max quote with slippage
@@ -114,6 +215,7 @@ pub fn buy_base_input_internal(
 /// * `base_reserve` - Base token reserves in the pool
 /// * `quote_reserve` - Quote token reserves in the pool
 /// * `coin_creator` - Token creator address
+/// * `fees` - Fees for this pool, see `compute_fees`
 ///
 /// # Returns
 /// * `BuyQuoteInputResult` containing base amount and slippage calculations
@@ -123,15 +225,14 @@ pub fn buy_quote_input_internal(
     base_reserve: u64,
     quote_reserve: u64,
     coin_creator: &Pubkey,
+    fees: &PumpSwapFees,
 ) -> Result<BuyQuoteInputResult, String> {
     if base_reserve == 0 || quote_reserve == 0 {
         return Err("Invalid input: 'baseReserve' or 'quoteReserve' cannot be zero.".to_string());
     }
 
     // Calculate total fee basis points
-    let total_fee_bps = LP_FEE_BASIS_POINTS
-        + PROTOCOL_FEE_BASIS_POINTS
-        + if *coin_creator == Pubkey::default() { 0 } else { COIN_CREATOR_FEE_BASIS_POINTS };
+    let total_fee_bps = fees.total_basis_points(coin_creator);
     let denominator = 10_000 + total_fee_bps;
 
     // Calculate effective quote amount after fees
@@ -165,6 +266,7 @@ pub fn buy_quote_input_internal(
 /// * `base_reserve` - Base token reserves in the pool
 /// * `quote_reserve` - Quote token reserves in the pool
 /// * `coin_creator` - Token creator address
+/// * `fees` - Fees for this pool, see `compute_fees`
 ///
 /// # Returns
 /// * `SellBaseInputResult` containing quote amounts and slippage calculations
@@ -174,6 +276,7 @@ pub fn sell_base_input_internal(
     base_reserve: u64,
     quote_reserve: u64,
     coin_creator: &Pubkey,
+    fees: &PumpSwapFees,
 ) -> Result<SellBaseInputResult, String> {
     if base_reserve == 0 || quote_reserve == 0 {
         return Err("Invalid input: 'baseReserve' or 'quoteReserve' cannot be zero.".to_string());
@@ -184,14 +287,12 @@ pub fn sell_base_input_internal(
         / ((base_reserve as u128) + (base as u128))) as u64;
 
     // Calculate fees
-    let lp_fee = compute_fee(quote_amount_out as u128, LP_FEE_BASIS_POINTS as u128) as u64;
+    let lp_fee = compute_fee(quote_amount_out as u128, fees.lp_fee_basis_points as u128) as u64;
     let protocol_fee =
-        compute_fee(quote_amount_out as u128, PROTOCOL_FEE_BASIS_POINTS as u128) as u64;
-    let coin_creator_fee = if *coin_creator == Pubkey::default() {
-        0
-    } else {
-        compute_fee(quote_amount_out as u128, COIN_CREATOR_FEE_BASIS_POINTS as u128) as u64
-    };
+        compute_fee(quote_amount_out as u128, fees.protocol_fee_basis_points as u128) as u64;
+    let coin_creator_fee_bps = fees.coin_creator_basis_points(coin_creator);
+    let coin_creator_fee =
+        compute_fee(quote_amount_out as u128, coin_creator_fee_bps as u128) as u64;
 
     // Calculate final quote after fees
     let total_fees = lp_fee + protocol_fee + coin_creator_fee;
@@ -234,6 +335,7 @@ fn calculate_quote_amount_out(
 /// * `base_reserve` - Base token reserves in the pool
 /// * `quote_reserve` - Quote token reserves in the pool
 /// * `coin_creator` - Token creator address
+/// * `fees` - Fees for this pool, see `compute_fees`
 ///
 /// # Returns
 /// * `SellQuoteInputResult` containing base amount and slippage calculations
@@ -243,6 +345,7 @@ pub fn sell_quote_input_internal(
     base_reserve: u64,
     quote_reserve: u64,
     coin_creator: &Pubkey,
+    fees: &PumpSwapFees,
 ) -> Result<SellQuoteInputResult, String> {
     if base_reserve == 0 || quote_reserve == 0 {
         return Err("Invalid input: 'baseReserve' or 'quoteReserve' cannot be zero.".to_string());
@@ -254,9 +357,9 @@ pub fn sell_quote_input_internal(
     // Calculate raw quote amount including fees
     let raw_quote = calculate_quote_amount_out(
         quote,
-        LP_FEE_BASIS_POINTS,
-        PROTOCOL_FEE_BASIS_POINTS,
-        if *coin_creator == Pubkey::default() { 0 } else { COIN_CREATOR_FEE_BASIS_POINTS },
+        fees.lp_fee_basis_points,
+        fees.protocol_fee_basis_points,
+        fees.coin_creator_basis_points(coin_creator),
     );
 
     // Calculate base amount needed using inverse constant product formula
@@ -273,3 +376,59 @@ pub fn sell_quote_input_internal(
 
     Ok(SellQuoteInputResult { internal_raw_quote: raw_quote, base: base_amount_in, min_quote })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::fee_config::{FeeTier, Fees};
+
+    #[test]
+    fn test_compute_fees_by_market_cap() {
+        assert_eq!(compute_fees(None, None, true, 1_000, 1_000, 1_000), PumpSwapFees::default());
+
+        let global_config = GlobalConfig {
+            lp_fee_basis_points: 25,
+            protocol_fee_basis_points: 5,
+            coin_creator_fee_basis_points: 0,
+            ..Default::default()
+        };
+        let fees = compute_fees(Some(&global_config), None, true, 1_000, 1_000, 1_000);
+        assert_eq!(fees.lp_fee_basis_points, 25);
+        assert_eq!(fees.coin_creator_basis_points(&Pubkey::new_unique()), 0);
+
+        let tier = |threshold, lp_fee_bps| FeeTier {
+            market_cap_lamports_threshold: threshold,
+            fees: Fees { lp_fee_bps, protocol_fee_bps: 5, creator_fee_bps: 30 },
+        };
+        let fee_config =
+            FeeConfig { fee_tiers: vec![tier(0, 2), tier(500, 20)], ..Default::default() };
+        // market cap = quote_reserve * supply / base_reserve
+        let fees = compute_fees(Some(&global_config), Some(&fee_config), true, 1_000, 100, 1_000);
+        assert_eq!(fees.lp_fee_basis_points, 2);
+        let fees = compute_fees(Some(&global_config), Some(&fee_config), true, 1_000, 600, 1_000);
+        assert_eq!(fees.lp_fee_basis_points, 20);
+        assert_eq!(fees.total_basis_points(&Pubkey::default()), 25);
+        assert_eq!(fees.total_basis_points(&Pubkey::new_unique()), 55);
+
+        // The tier follows the real mint supply: half the supply halves the market cap
+        let fees = compute_fees(Some(&global_config), Some(&fee_config), true, 1_000, 600, 500);
+        assert_eq!(fees.lp_fee_basis_points, 2);
+    }
+
+    #[test]
+    fn test_compute_fees_non_canonical_pool_pays_flat_fees() {
+        let flat_fees = Fees { lp_fee_bps: 25, protocol_fee_bps: 5, creator_fee_bps: 0 };
+        let fee_config = FeeConfig {
+            flat_fees,
+            fee_tiers: vec![FeeTier {
+                market_cap_lamports_threshold: 0,
+                fees: Fees { lp_fee_bps: 2, protocol_fee_bps: 5, creator_fee_bps: 30 },
+            }],
+            ..Default::default()
+        };
+        let fees = compute_fees(None, Some(&fee_config), false, 1_000, 600, 1_000);
+        assert_eq!(fees.lp_fee_basis_points, 25);
+        assert_eq!(fees.protocol_fee_basis_points, 5);
+        assert_eq!(fees.coin_creator_fee_basis_points, 0);
+    }
+}
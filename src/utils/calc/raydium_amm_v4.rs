@@ -148,3 +148,60 @@ pub fn compute_swap_amount(
         fee: swap_result.trade_fee,
     }
 }
+
+/// Computes the input needed to receive exactly `amount_out`, the way `swap_base_out` does.
+///
+/// The program grosses the constant-product input up by the swap fee with ceiling division.
+///
+/// # Arguments
+/// * `base_reserve` - The current reserve amount of the base token in the pool
+/// * `quote_reserve` - The current reserve amount of the quote token in the pool
+/// * `is_base_in` - Whether the input token is the base token (true) or quote token (false)
+/// * `amount_out` - The exact amount of output tokens to receive
+///
+/// # Returns
+/// The input amount including fees, or `None` if the pool cannot pay `amount_out`
+pub fn compute_swap_amount_base_out(
+    base_reserve: u64,
+    quote_reserve: u64,
+    is_base_in: bool,
+    amount_out: u64,
+) -> Option<u64> {
+    let (input_reserve, output_reserve) =
+        if is_base_in { (base_reserve, quote_reserve) } else { (quote_reserve, base_reserve) };
+    if amount_out == 0 || amount_out >= output_reserve {
+        return None;
+    }
+
+    let numerator = (input_reserve as u128) * (amount_out as u128);
+    let denominator = (output_reserve - amount_out) as u128;
+    let amount_in_before_fee = numerator.div_ceil(denominator);
+
+    let amount_in = (amount_in_before_fee * SWAP_FEE_DENOMINATOR as u128)
+        .div_ceil((SWAP_FEE_DENOMINATOR - SWAP_FEE_NUMERATOR) as u128);
+    u64::try_from(amount_in).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_compute_swap_amount_base_out() {
+        let (base_reserve, quote_reserve) = (1_000_000_000_000, 50_000_000_000);
+        let amount_in =
+            compute_swap_amount_base_out(base_reserve, quote_reserve, false, 10_000_000_000)
+                .unwrap();
+
+        // Spending the quoted input through swap_base_in yields at least the requested output
+        let swap = compute_swap_amount(base_reserve, quote_reserve, false, amount_in, 0);
+        assert!(swap.amount_out >= 10_000_000_000 - 10_000_000_000 / 1000);
+        let swap =
+            compute_swap_amount(base_reserve, quote_reserve, false, amount_in - 1_000_000, 0);
+        assert!(swap.amount_out < 10_000_000_000);
+
+        assert!(compute_swap_amount_base_out(base_reserve, quote_reserve, false, base_reserve)
+            .is_none());
+        assert!(compute_swap_amount_base_out(base_reserve, quote_reserve, true, 0).is_none());
+    }
+}
@@ -1,6 +1,8 @@
 use crate::constants::raydium_cpmm::accounts::{
-    CREATOR_FEE_RATE, FEE_RATE_DENOMINATOR_VALUE, FUND_FEE_RATE, PROTOCOL_FEE_RATE, TRADE_FEE_RATE,
+    CREATOR_FEE_RATE, FEE_RATE_DENOMINATOR_VALUE, FUND_FEE_RATE, LOCKED_LP_AMOUNT,
+    PROTOCOL_FEE_RATE, TRADE_FEE_RATE,
 };
+use spl_token_2022::extension::transfer_fee::TransferFee;
 
 /// Computes trading fee using ceiling division.
 ///
@@ -141,6 +143,47 @@ fn swap_base_input(
     }
 }
 
+/// Fee rates of a CPMM pool, in `FEE_RATE_DENOMINATOR_VALUE` units.
+///
+/// Trade, protocol and fund rates come from the pool's `AmmConfig`; the creator rate is the
+/// config's creator fee rate when the pool has creator fees enabled, otherwise 0.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FeeRates {
+    /// Trade fee rate charged on the swap input
+    pub trade_fee_rate: u64,
+    /// Share of the trade fee that goes to the protocol
+    pub protocol_fee_rate: u64,
+    /// Share of the trade fee that goes to the fund
+    pub fund_fee_rate: u64,
+    /// Creator fee rate
+    pub creator_fee_rate: u64,
+}
+
+impl Default for FeeRates {
+    /// Rates of the default 0.25% fee tier, used when the pool's config is unknown
+    fn default() -> Self {
+        Self {
+            trade_fee_rate: TRADE_FEE_RATE,
+            protocol_fee_rate: PROTOCOL_FEE_RATE,
+            fund_fee_rate: FUND_FEE_RATE,
+            creator_fee_rate: CREATOR_FEE_RATE,
+        }
+    }
+}
+
+/// Whether the creator fee is charged on the input token of a swap.
+///
+/// # Arguments
+/// * `creator_fee_on` - The pool's creator fee mode: 0 both tokens, 1 only token0, 2 only token1
+/// * `is_token_0_in` - Whether token0 is the swap input
+pub fn is_creator_fee_on_input(creator_fee_on: u8, is_token_0_in: bool) -> bool {
+    match creator_fee_on {
+        1 => is_token_0_in,
+        2 => !is_token_0_in,
+        _ => true,
+    }
+}
+
 /// Computes swap parameters including amounts, fees, and slippage protection.
 ///
 /// This function calculates the expected output amount, minimum output amount (with slippage),
@@ -152,6 +195,8 @@ fn swap_base_input(
 /// * `is_base_in` - Whether the input token is the base token (true) or quote token (false)
 /// * `amount_in` - The amount of input tokens to swap
 /// * `slippage_basis_points` - The acceptable slippage in basis points (e.g., 100 for 1%)
+/// * `fee_rates` - The pool's fee rates, see `FeeRates`
+/// * `is_creator_fee_on_input` - Whether the creator fee is charged on the input token
 ///
 /// # Returns
 /// A `ComputeSwapParams` struct containing all computed swap parameters
@@ -161,6 +206,8 @@ pub fn compute_swap_amount(
     is_base_in: bool,
     amount_in: u64,
     slippage_basis_points: u64,
+    fee_rates: &FeeRates,
+    is_creator_fee_on_input: bool,
 ) -> ComputeSwapParams {
     let (input_reserve, output_reserve) =
         if is_base_in { (base_reserve, quote_reserve) } else { (quote_reserve, base_reserve) };
@@ -169,14 +216,15 @@ pub fn compute_swap_amount(
         amount_in,
         input_reserve,
         output_reserve,
-        TRADE_FEE_RATE,
-        CREATOR_FEE_RATE,
-        PROTOCOL_FEE_RATE,
-        FUND_FEE_RATE,
-        true,
+        fee_rates.trade_fee_rate,
+        fee_rates.creator_fee_rate,
+        fee_rates.protocol_fee_rate,
+        fee_rates.fund_fee_rate,
+        is_creator_fee_on_input,
     );
 
-    let min_amount_out = ((swap_result.output_amount as f64) * (1.0 - (slippage_basis_points as f64) / 10000.0)) as u64;
+    let min_amount_out = ((swap_result.output_amount as f64)
+        * (1.0 - (slippage_basis_points as f64) / 10000.0)) as u64;
 
     let all_trade = swap_result.input_amount == amount_in;
 
@@ -188,3 +236,307 @@ pub fn compute_swap_amount(
         fee: swap_result.trade_fee,
     }
 }
+
+/// Rounding direction used when converting between LP tokens and pool tokens.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RoundDirection {
+    /// Round down, used for withdrawals so the pool never pays out more than it owns
+    Floor,
+    /// Round up, used for deposits so the pool never receives less than it is owed
+    Ceiling,
+}
+
+/// Pool token amounts that correspond to an amount of LP tokens.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TradingTokenResult {
+    /// Amount of token0
+    pub token_0_amount: u64,
+    /// Amount of token1
+    pub token_1_amount: u64,
+}
+
+/// Deposit or withdraw amounts computed for a given LP token amount.
+#[derive(Debug, Clone)]
+pub struct ComputeLiquidityParams {
+    /// LP tokens minted (deposit) or burned (withdraw)
+    pub lp_token_amount: u64,
+    /// Expected token0 amount moved in or out of the user's account, including transfer fees
+    pub token_0_amount: u64,
+    /// Expected token1 amount moved in or out of the user's account, including transfer fees
+    pub token_1_amount: u64,
+    /// Slippage limit for token0: maximum to pay on deposit, minimum to receive on withdraw
+    pub token_0_limit: u64,
+    /// Slippage limit for token1: maximum to pay on deposit, minimum to receive on withdraw
+    pub token_1_limit: u64,
+}
+
+/// Returns the part of a vault balance that belongs to liquidity providers.
+///
+/// Protocol, fund and creator fees are taken at `FEE_RATE_DENOMINATOR_VALUE` precision and
+/// stay in the vault until collected, so they must be excluded before converting between
+/// LP tokens and pool tokens or quoting swaps.
+///
+/// # Arguments
+/// * `vault_balance` - Current token balance of the vault
+/// * `protocol_fees` - Uncollected protocol fees for this side, from `PoolState`
+/// * `fund_fees` - Uncollected fund fees for this side, from `PoolState`
+/// * `creator_fees` - Uncollected creator fees for this side, from `PoolState`
+pub fn vault_amount_without_fees(
+    vault_balance: u64,
+    protocol_fees: u64,
+    fund_fees: u64,
+    creator_fees: u64,
+) -> u64 {
+    vault_balance
+        .saturating_sub(protocol_fees)
+        .saturating_sub(fund_fees)
+        .saturating_sub(creator_fees)
+}
+
+/// Converts an LP token amount into the matching amounts of token0 and token1.
+///
+/// # Arguments
+/// * `lp_token_amount` - Amount of LP tokens
+/// * `lp_token_supply` - Current LP supply of the pool
+/// * `token_0_vault_amount` - token0 vault amount without fees, see `vault_amount_without_fees`
+/// * `token_1_vault_amount` - token1 vault amount without fees, see `vault_amount_without_fees`
+/// * `round_direction` - `Ceiling` for deposits, `Floor` for withdrawals
+///
+/// # Returns
+/// `None` if the pool has no LP supply or the result does not fit in a `u64`
+pub fn lp_tokens_to_trading_tokens(
+    lp_token_amount: u64,
+    lp_token_supply: u64,
+    token_0_vault_amount: u64,
+    token_1_vault_amount: u64,
+    round_direction: RoundDirection,
+) -> Option<TradingTokenResult> {
+    if lp_token_supply == 0 {
+        return None;
+    }
+    let convert = |vault_amount: u64| -> Option<u64> {
+        let numerator = (lp_token_amount as u128).checked_mul(vault_amount as u128)?;
+        let supply = lp_token_supply as u128;
+        let amount = match round_direction {
+            RoundDirection::Floor => numerator / supply,
+            RoundDirection::Ceiling => numerator.div_ceil(supply),
+        };
+        u64::try_from(amount).ok()
+    };
+    Some(TradingTokenResult {
+        token_0_amount: convert(token_0_vault_amount)?,
+        token_1_amount: convert(token_1_vault_amount)?,
+    })
+}
+
+/// Computes how many LP tokens a deposit of `token_amount` on one side is worth.
+///
+/// Rounds down, so depositing the returned LP amount never needs more than `token_amount`
+/// of that side (before transfer fees).
+pub fn lp_tokens_from_trading_token(
+    token_amount: u64,
+    vault_amount: u64,
+    lp_token_supply: u64,
+) -> Option<u64> {
+    if vault_amount == 0 {
+        return None;
+    }
+    let lp = (token_amount as u128).checked_mul(lp_token_supply as u128)? / vault_amount as u128;
+    u64::try_from(lp).ok()
+}
+
+/// Computes the LP tokens the creator receives when initializing a pool.
+///
+/// The program mints `sqrt(init_amount_0 * init_amount_1)` LP tokens and locks
+/// `LOCKED_LP_AMOUNT` of them in the pool forever.
+pub fn compute_initial_lp_amount(init_amount_0: u64, init_amount_1: u64) -> u64 {
+    let liquidity = ((init_amount_0 as u128) * (init_amount_1 as u128)).isqrt() as u64;
+    liquidity.saturating_sub(LOCKED_LP_AMOUNT)
+}
+
+/// Applies slippage to an amount at `FEE_RATE_DENOMINATOR_VALUE` precision.
+///
+/// Slippage is given in basis points and scaled to the pool's fee rate denominator, rounding
+/// up for maximum limits and down for minimum limits.
+fn apply_slippage(amount: u64, slippage_basis_points: u64, is_maximum: bool) -> u64 {
+    let slippage_rate = (slippage_basis_points as u128) * (FEE_RATE_DENOMINATOR_VALUE / 10_000);
+    if is_maximum {
+        let numerator = (amount as u128) * (FEE_RATE_DENOMINATOR_VALUE + slippage_rate);
+        u64::try_from(numerator.div_ceil(FEE_RATE_DENOMINATOR_VALUE)).unwrap_or(u64::MAX)
+    } else {
+        let rate = FEE_RATE_DENOMINATOR_VALUE.saturating_sub(slippage_rate);
+        ((amount as u128) * rate / FEE_RATE_DENOMINATOR_VALUE) as u64
+    }
+}
+
+/// Computes the token amounts and maximum limits for depositing `lp_token_amount` LP tokens.
+///
+/// Token-2022 transfer fees are added on top of what the pool must receive, so the limits
+/// cover the amount actually debited from the user's accounts.
+///
+/// # Arguments
+/// * `lp_token_amount` - LP tokens to mint
+/// * `lp_token_supply` - Current LP supply of the pool
+/// * `token_0_vault_amount` - token0 vault amount without fees
+/// * `token_1_vault_amount` - token1 vault amount without fees
+/// * `token_0_transfer_fee` - Active Token-2022 transfer fee of token0, if any
+/// * `token_1_transfer_fee` - Active Token-2022 transfer fee of token1, if any
+/// * `slippage_basis_points` - The acceptable slippage in basis points (e.g., 100 for 1%)
+pub fn compute_deposit_amount(
+    lp_token_amount: u64,
+    lp_token_supply: u64,
+    token_0_vault_amount: u64,
+    token_1_vault_amount: u64,
+    token_0_transfer_fee: Option<&TransferFee>,
+    token_1_transfer_fee: Option<&TransferFee>,
+    slippage_basis_points: u64,
+) -> Option<ComputeLiquidityParams> {
+    let result = lp_tokens_to_trading_tokens(
+        lp_token_amount,
+        lp_token_supply,
+        token_0_vault_amount,
+        token_1_vault_amount,
+        RoundDirection::Ceiling,
+    )?;
+    let with_fee = |amount: u64, fee: Option<&TransferFee>| -> Option<u64> {
+        match fee {
+            Some(fee) => amount.checked_add(fee.calculate_inverse_fee(amount)?),
+            None => Some(amount),
+        }
+    };
+    let token_0_amount = with_fee(result.token_0_amount, token_0_transfer_fee)?;
+    let token_1_amount = with_fee(result.token_1_amount, token_1_transfer_fee)?;
+    Some(ComputeLiquidityParams {
+        lp_token_amount,
+        token_0_amount,
+        token_1_amount,
+        token_0_limit: apply_slippage(token_0_amount, slippage_basis_points, true),
+        token_1_limit: apply_slippage(token_1_amount, slippage_basis_points, true),
+    })
+}
+
+/// Computes the token amounts and minimum limits for withdrawing `lp_token_amount` LP tokens.
+///
+/// Token-2022 transfer fees are deducted from what the pool pays out, so the limits match the
+/// amount actually credited to the user's accounts.
+///
+/// # Arguments
+/// * `lp_token_amount` - LP tokens to burn
+/// * `lp_token_supply` - Current LP supply of the pool
+/// * `token_0_vault_amount` - token0 vault amount without fees
+/// * `token_1_vault_amount` - token1 vault amount without fees
+/// * `token_0_transfer_fee` - Active Token-2022 transfer fee of token0, if any
+/// * `token_1_transfer_fee` - Active Token-2022 transfer fee of token1, if any
+/// * `slippage_basis_points` - The acceptable slippage in basis points (e.g., 100 for 1%)
+pub fn compute_withdraw_amount(
+    lp_token_amount: u64,
+    lp_token_supply: u64,
+    token_0_vault_amount: u64,
+    token_1_vault_amount: u64,
+    token_0_transfer_fee: Option<&TransferFee>,
+    token_1_transfer_fee: Option<&TransferFee>,
+    slippage_basis_points: u64,
+) -> Option<ComputeLiquidityParams> {
+    let result = lp_tokens_to_trading_tokens(
+        lp_token_amount,
+        lp_token_supply,
+        token_0_vault_amount,
+        token_1_vault_amount,
+        RoundDirection::Floor,
+    )?;
+    let without_fee = |amount: u64, fee: Option<&TransferFee>| -> Option<u64> {
+        match fee {
+            Some(fee) => fee.calculate_post_fee_amount(amount),
+            None => Some(amount),
+        }
+    };
+    let token_0_amount = without_fee(result.token_0_amount, token_0_transfer_fee)?;
+    let token_1_amount = without_fee(result.token_1_amount, token_1_transfer_fee)?;
+    Some(ComputeLiquidityParams {
+        lp_token_amount,
+        token_0_amount,
+        token_1_amount,
+        token_0_limit: apply_slippage(token_0_amount, slippage_basis_points, false),
+        token_1_limit: apply_slippage(token_1_amount, slippage_basis_points, false),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_compute_swap_amount_uses_fee_rates() {
+        let default_tier =
+            compute_swap_amount(1_000_000, 1_000_000, true, 10_000, 0, &FeeRates::default(), true);
+        let high_tier = FeeRates { trade_fee_rate: 10_000, ..FeeRates::default() };
+        let high = compute_swap_amount(1_000_000, 1_000_000, true, 10_000, 0, &high_tier, true);
+        assert_eq!(default_tier.fee, 25);
+        assert_eq!(high.fee, 100);
+        assert!(high.amount_out < default_tier.amount_out);
+
+        let creator = FeeRates { creator_fee_rate: 10_000, ..FeeRates::default() };
+        let on_input = compute_swap_amount(1_000_000, 1_000_000, true, 10_000, 0, &creator, true);
+        let on_output = compute_swap_amount(1_000_000, 1_000_000, true, 10_000, 0, &creator, false);
+        assert!(on_input.amount_out < default_tier.amount_out);
+        assert!(on_output.amount_out < default_tier.amount_out);
+    }
+
+    #[test]
+    fn test_is_creator_fee_on_input() {
+        assert!(is_creator_fee_on_input(0, true));
+        assert!(is_creator_fee_on_input(0, false));
+        assert!(is_creator_fee_on_input(1, true));
+        assert!(!is_creator_fee_on_input(1, false));
+        assert!(!is_creator_fee_on_input(2, true));
+        assert!(is_creator_fee_on_input(2, false));
+    }
+
+    #[test]
+    fn test_lp_tokens_to_trading_tokens_rounding() {
+        let floor = lp_tokens_to_trading_tokens(10, 30, 100, 200, RoundDirection::Floor).unwrap();
+        assert_eq!(floor, TradingTokenResult { token_0_amount: 33, token_1_amount: 66 });
+        let ceil = lp_tokens_to_trading_tokens(10, 30, 100, 200, RoundDirection::Ceiling).unwrap();
+        assert_eq!(ceil, TradingTokenResult { token_0_amount: 34, token_1_amount: 67 });
+        assert!(lp_tokens_to_trading_tokens(10, 0, 100, 200, RoundDirection::Floor).is_none());
+    }
+
+    #[test]
+    fn test_compute_initial_lp_amount() {
+        assert_eq!(compute_initial_lp_amount(1_000_000, 4_000_000), 2_000_000 - LOCKED_LP_AMOUNT);
+        assert_eq!(compute_initial_lp_amount(10, 10), 0);
+    }
+
+    #[test]
+    fn test_deposit_and_withdraw_limits() {
+        let deposit =
+            compute_deposit_amount(1_000, 10_000, 50_000, 80_000, None, None, 100).unwrap();
+        assert_eq!(deposit.token_0_amount, 5_000);
+        assert_eq!(deposit.token_1_amount, 8_000);
+        assert_eq!(deposit.token_0_limit, 5_050);
+        assert_eq!(deposit.token_1_limit, 8_080);
+
+        let withdraw =
+            compute_withdraw_amount(1_000, 10_000, 50_000, 80_000, None, None, 100).unwrap();
+        assert_eq!(withdraw.token_0_limit, 4_950);
+        assert_eq!(withdraw.token_1_limit, 7_920);
+    }
+
+    #[test]
+    fn test_deposit_and_withdraw_with_transfer_fee() {
+        let fee = TransferFee {
+            epoch: 0.into(),
+            maximum_fee: u64::MAX.into(),
+            transfer_fee_basis_points: 100.into(),
+        };
+        let deposit =
+            compute_deposit_amount(1_000, 10_000, 99_000, 80_000, Some(&fee), None, 0).unwrap();
+        assert_eq!(fee.calculate_post_fee_amount(deposit.token_0_amount), Some(9_900));
+        assert_eq!(deposit.token_1_amount, 8_000);
+
+        let withdraw =
+            compute_withdraw_amount(1_000, 10_000, 99_000, 80_000, Some(&fee), None, 0).unwrap();
+        assert_eq!(withdraw.token_0_amount, 9_801);
+        assert_eq!(withdraw.token_1_amount, 8_000);
+    }
+}
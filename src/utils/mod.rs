@@ -1,6 +1,7 @@
 pub mod price;
 pub mod calc;
 
+use crate::common::volume_accumulator::TokenIncentives;
 use crate::solana_streamer_sdk::streaming::event_parser::protocols::pumpfun::PumpFunTradeEvent;
 use crate::trading;
 use crate::SolanaTrade;
@@ -8,6 +9,30 @@ use solana_sdk::pubkey::Pubkey;
 use solana_sdk::signature::Keypair;
 use solana_sdk::signer::Signer;
 
+/// Creator fees a wallet can collect, summed over every coin it created
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CreatorFees {
+    /// Lamports in the PumpFun creator vault, from bonding curve trades
+    pub pumpfun: u64,
+    /// WSOL in the PumpSwap coin creator vault, from pool trades after migration
+    pub pumpswap: u64,
+}
+
+impl CreatorFees {
+    pub fn total(&self) -> u64 {
+        self.pumpfun + self.pumpswap
+    }
+}
+
+/// Trading incentives of a wallet, `None` where it has no volume accumulator
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct UnclaimedIncentives {
+    /// Incentives earned on Pump.fun bonding curves
+    pub pumpfun: Option<TokenIncentives>,
+    /// Incentives earned on PumpSwap pools
+    pub pumpswap: Option<TokenIncentives>,
+}
+
 impl SolanaTrade {
     #[inline]
     pub async fn get_sol_balance(&self, payer: &Pubkey) -> Result<u64, anyhow::Error> {
@@ -58,6 +83,35 @@ impl SolanaTrade {
         trading::common::utils::close_token_account(&self.rpc, self.payer.as_ref(), mint).await
     }
 
+    /// Creator fees `creator` can collect from PumpFun and PumpSwap
+    ///
+    /// Both protocols keep one vault per creator, so this covers every coin the wallet created.
+    #[inline]
+    pub async fn get_creator_fees(&self, creator: &Pubkey) -> Result<CreatorFees, anyhow::Error> {
+        let (pumpfun, pumpswap) = tokio::try_join!(
+            trading::pumpfun::common::get_creator_vault_balance(&self.rpc, creator),
+            trading::pumpswap::common::get_coin_creator_vault_balance(
+                &self.rpc,
+                creator,
+                &crate::constants::pumpswap::accounts::WSOL_TOKEN_ACCOUNT,
+            ),
+        )?;
+        Ok(CreatorFees { pumpfun, pumpswap })
+    }
+
+    /// Incentive tokens `user` earned by trading on PumpFun and PumpSwap
+    #[inline]
+    pub async fn get_token_incentives(
+        &self,
+        user: &Pubkey,
+    ) -> Result<UnclaimedIncentives, anyhow::Error> {
+        let (pumpfun, pumpswap) = tokio::try_join!(
+            trading::pumpfun::common::get_token_incentives(&self.rpc, user),
+            trading::pumpswap::common::get_token_incentives(&self.rpc, user),
+        )?;
+        Ok(UnclaimedIncentives { pumpfun, pumpswap })
+    }
+
     // -------------------------------- PumpFun --------------------------------
 
     #[inline]
@@ -150,4 +204,16 @@ impl SolanaTrade {
 
         Ok(base_amount)
     }
+
+    // -------------------------------- Raydium CPMM --------------------------------
+
+    /// Read `owner`'s LP position in a Raydium CPMM pool
+    #[inline]
+    pub async fn get_raydium_cpmm_lp_position(
+        &self,
+        pool_address: &Pubkey,
+        owner: &Pubkey,
+    ) -> Result<trading::raydium_cpmm::common::LpPosition, anyhow::Error> {
+        trading::raydium_cpmm::common::get_lp_position(&self.rpc, pool_address, owner).await
+    }
 }